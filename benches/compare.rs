@@ -0,0 +1,77 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Compares `DenseBitSet` with `fixedbitset`, `bit-set` and `roaring`
+//! across the sizes and densities of `BENCH_SIZES` and `BENCH_DENSITIES`.
+//!
+//! Run with `cargo bench --features compare`.
+
+#[macro_use]
+extern crate criterion;
+extern crate bit_set;
+extern crate bitsets;
+extern crate fixedbitset;
+extern crate roaring;
+
+use criterion::measurement::WallTime;
+use criterion::{ black_box, BenchmarkGroup, BenchmarkId, Criterion };
+use fixedbitset::FixedBitSet;
+use roaring::RoaringBitmap;
+
+use bitsets::{ BenchBackend, BenchWorkload, DenseBitSet, BENCH_DENSITIES, BENCH_SIZES };
+
+fn bench_backend<B: BenchBackend>(group: &mut BenchmarkGroup<WallTime>, op: &str, workload: &BenchWorkload) {
+    let (a, b) = workload.build::<B>();
+    let id = BenchmarkId::new(B::NAME, format!("{}/{}", workload.len, workload.density));
+
+    match op {
+        "contains" => group.bench_function(id, |bench| {
+            bench.iter(|| workload.probes.iter().filter(|&&i| a.contains(black_box(i))).count())
+        }),
+        "count" => group.bench_function(id, |bench| bench.iter(|| black_box(&a).count())),
+        "intersection_count" => group.bench_function(id, |bench| {
+            bench.iter(|| black_box(&a).intersection_count(black_box(&b)))
+        }),
+        "union" => group.bench_function(id, |bench| bench.iter(|| black_box(&a).union(black_box(&b)))),
+        "iterate" => group.bench_function(id, |bench| bench.iter(|| black_box(&a).sum_ones())),
+        _ => unreachable!(),
+    };
+}
+
+fn compare(c: &mut Criterion) {
+    for &op in &["contains", "count", "intersection_count", "union", "iterate"] {
+        let mut group = c.benchmark_group(op);
+        for &len in &BENCH_SIZES {
+            for &density in &BENCH_DENSITIES {
+                let workload = BenchWorkload::new(len, density, 0x5eed);
+                bench_backend::<DenseBitSet>(&mut group, op, &workload);
+                bench_backend::<FixedBitSet>(&mut group, op, &workload);
+                bench_backend::<bit_set::BitSet>(&mut group, op, &workload);
+                bench_backend::<RoaringBitmap>(&mut group, op, &workload);
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, compare);
+criterion_main!(benches);
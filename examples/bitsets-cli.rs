@@ -0,0 +1,159 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `bitsets-cli`: builds a bitmap index over the columns of a CSV file and
+//! answers boolean queries against it.
+//!
+//! ```text
+//! cargo run --example bitsets-cli -- examples/people.csv
+//! cargo run --example bitsets-cli -- examples/people.csv "city.Oslo & !(plan.free | active.no)"
+//! ```
+//!
+//! The first line of the CSV names the columns. Every distinct
+//! `column.value` pair gets a `DenseBitSet` with one bit per row. Queries
+//! use the `Query` language, `!`, `-`, `&`, `^` and `|` with parentheses,
+//! and are evaluated in one fused pass over the words of every operand.
+//! Without a query the tool prints statistics for every index, after
+//! checking that each one survives the portable byte format and EWAH
+//! compression unchanged. Use `-` as the path to read from stdin.
+
+extern crate bitsets;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader };
+use std::mem;
+use std::process;
+
+use bitsets::{ DenseBitSet, EwahBitSet, Query };
+
+struct Index {
+    rows: usize,
+    columns: Vec<String>,
+    bitmaps: BTreeMap<String, DenseBitSet>,
+}
+
+/// a set of exactly `rows` bits, so `!` never sets rows that do not exist
+fn empty(rows: usize) -> DenseBitSet {
+    DenseBitSet::from_vec_masked(vec![0; rows.div_ceil(usize::BITS as usize)], rows)
+}
+
+fn build_index<R: BufRead>(input: R) -> Result<Index, String> {
+    let mut lines = input.lines();
+    let header = match lines.next() {
+        Some(line) => line.map_err(|e| e.to_string())?,
+        None => return Err("empty input".to_string()),
+    };
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_string()).collect();
+
+    let mut records = Vec::new();
+    for (n, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<String> = line.split(',').map(|f| f.trim().to_string()).collect();
+        if fields.len() != columns.len() {
+            return Err(format!("line {}: expected {} fields, found {}", n + 2, columns.len(), fields.len()));
+        }
+        records.push(fields);
+    }
+
+    let rows = records.len();
+    let mut bitmaps = BTreeMap::new();
+    for (row, fields) in records.iter().enumerate() {
+        for (column, value) in columns.iter().zip(fields) {
+            bitmaps.entry(format!("{}.{}", column, value))
+                .or_insert_with(|| empty(rows))
+                .set(row);
+        }
+    }
+
+    Ok(Index { rows, columns, bitmaps })
+}
+
+fn run_query(index: &Index, text: &str) -> Result<DenseBitSet, String> {
+    let query = Query::parse(text).map_err(|e| e.to_string())?;
+    if let Some(name) = query.names().find(|name| !name.contains('.')) {
+        return Err(format!("expected column.value, found '{}'", name));
+    }
+
+    // unknown pairs match no rows
+    let none = empty(index.rows);
+    query.eval_with(|name| Some(index.bitmaps.get(name).unwrap_or(&none))).map_err(|e| e.to_string())
+}
+
+fn print_stats(index: &Index) -> Result<(), String> {
+    println!("{} rows, {} columns, {} bitmaps", index.rows, index.columns.len(), index.bitmaps.len());
+    println!("{:<32} {:>8} {:>8} {:>10} {:>10} {:>12}", "bitmap", "rows", "density", "bytes", "ewah", "backend");
+
+    for (key, bitmap) in &index.bitmaps {
+        let bytes = bitmap.to_le_bytes();
+        if DenseBitSet::from_le_bytes(&bytes).to_indices() != bitmap.to_indices() {
+            return Err(format!("{}: bitmap changed after a round trip through bytes", key));
+        }
+
+        let ewah = EwahBitSet::from(bitmap);
+        if ewah.to_dense().to_indices() != bitmap.to_indices() {
+            return Err(format!("{}: bitmap changed after a round trip through EWAH", key));
+        }
+
+        let report = bitmap.storage_report();
+        println!("{:<32} {:>8} {:>8.3} {:>10} {:>10} {:>12?}",
+                 key, report.ones, report.density(), bytes.len(),
+                 ewah.size_in_words() * mem::size_of::<usize>(), report.recommend_backend());
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        return Err("usage: bitsets-cli <file.csv | -> [query]".to_string());
+    }
+
+    let index = if args[0] == "-" {
+        let stdin = io::stdin();
+        build_index(stdin.lock())?
+    } else {
+        let file = File::open(&args[0]).map_err(|e| format!("{}: {}", args[0], e))?;
+        build_index(BufReader::new(file))?
+    };
+
+    match args.get(1) {
+        None => print_stats(&index)?,
+        Some(text) => {
+            let result = run_query(&index, text)?;
+            println!("{} of {} rows match", result.count_ones(), index.rows);
+            println!("{}", result);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("bitsets-cli: {}", e);
+        process::exit(1);
+    }
+}
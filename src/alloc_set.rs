@@ -0,0 +1,357 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `AllocBitSet`, a bit set whose words come from a caller-chosen allocator
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+
+use { get_bitmask, get_low_mask, get_word_offset, BitSet, BitWords, DenseBitSet, DenseOnesIterator, BITS_PER_WORD };
+
+/// A source of word buffers for `AllocBitSet`.
+///
+/// This stands in for the unstable `allocator_api`: an allocator hands out
+/// any buffer that derefs to a word slice, so sets can live in arenas, bump
+/// allocators or shared memory owned by the application.
+pub trait WordAllocator {
+    /// The buffer handed out, which owns or borrows the words
+    type Buffer: AsRef<[usize]> + AsMut<[usize]>;
+
+    /// Returns a buffer of exactly `words` zeroed words, or `None` if the
+    /// allocator is out of space
+    fn allocate_zeroed(&self, words: usize) -> Option<Self::Buffer>;
+}
+
+/// Allocates from the global heap, like `DenseBitSet`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapAllocator;
+
+impl WordAllocator for HeapAllocator {
+    type Buffer = Box<[usize]>;
+
+    fn allocate_zeroed(&self, words: usize) -> Option<Box<[usize]>> {
+        Some(vec![0; words].into_boxed_slice())
+    }
+}
+
+/// A bump allocator that carves buffers out of a borrowed slice of words.
+///
+/// The memory is given back all at once when the backing slice goes out of
+/// scope; nothing is freed per set.
+pub struct ArenaAllocator<'a> {
+    rest: Cell<&'a mut [usize]>,
+}
+
+impl<'a> ArenaAllocator<'a> {
+    /// Creates an arena over `memory`
+    pub fn new(memory: &'a mut [usize]) -> ArenaAllocator<'a> {
+        ArenaAllocator { rest: Cell::new(memory) }
+    }
+
+    /// Returns the number of words still available
+    pub fn remaining(&self) -> usize {
+        let rest = self.rest.take();
+        let remaining = rest.len();
+        self.rest.set(rest);
+        remaining
+    }
+}
+
+impl<'a> fmt::Debug for ArenaAllocator<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArenaAllocator").field("remaining", &self.remaining()).finish()
+    }
+}
+
+impl<'a> WordAllocator for ArenaAllocator<'a> {
+    type Buffer = &'a mut [usize];
+
+    fn allocate_zeroed(&self, words: usize) -> Option<&'a mut [usize]> {
+        let rest = self.rest.take();
+        if rest.len() < words {
+            self.rest.set(rest);
+            return None;
+        }
+        let (buffer, rest) = rest.split_at_mut(words);
+        self.rest.set(rest);
+        for word in buffer.iter_mut() {
+            *word = 0;
+        }
+        Some(buffer)
+    }
+}
+
+/// A fixed-length bit set stored in a buffer from a `WordAllocator`.
+///
+/// It mirrors the core `DenseBitSet` API. The buffer can also be supplied
+/// directly with `from_buffer`, for instance a slice of a memory-mapped
+/// region shared between processes.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ AllocBitSet, ArenaAllocator };
+///
+/// let mut memory = [0usize; 8];
+/// let arena = ArenaAllocator::new(&mut memory);
+///
+/// let mut a = AllocBitSet::new_in(100, &arena).unwrap();
+/// let mut b = AllocBitSet::new_in(100, &arena).unwrap();
+/// a.set(3);
+/// b.set(99);
+/// a.inplace_or(&b);
+///
+/// assert_eq!(a.ones().collect::<Vec<_>>(), vec![3, 99]);
+/// ```
+#[derive(Debug)]
+pub struct AllocBitSet<B> {
+    words: B,
+    num_bits: usize,
+}
+
+impl<B: AsRef<[usize]> + AsMut<[usize]>> AllocBitSet<B> {
+    /// Creates a set of `num_bits` clear bits in a buffer from `allocator`,
+    /// or returns `None` if the allocator is out of space
+    pub fn new_in<A>(num_bits: usize, allocator: &A) -> Option<AllocBitSet<B>>
+        where A: WordAllocator<Buffer = B> + ?Sized
+    {
+        let words = allocator.allocate_zeroed(num_bits.div_ceil(BITS_PER_WORD))?;
+        Some(AllocBitSet { words, num_bits })
+    }
+
+    /// Creates a set of `num_bits` clear bits in `buffer`, which is cleared
+    /// first. Returns `None` if the buffer holds fewer than `num_bits` bits.
+    pub fn from_buffer(mut buffer: B, num_bits: usize) -> Option<AllocBitSet<B>> {
+        if buffer.as_ref().len() * BITS_PER_WORD < num_bits {
+            return None;
+        }
+        for word in buffer.as_mut() {
+            *word = 0;
+        }
+        Some(AllocBitSet { words: buffer, num_bits })
+    }
+
+    /// Returns the buffer, giving it back to its owner
+    pub fn into_buffer(self) -> B {
+        self.words
+    }
+
+    /// Returns the number of bits in the set
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns the words holding the bits, trimmed to `len()`
+    pub fn as_words(&self) -> &[usize] {
+        &self.words.as_ref()[..self.num_bits.div_ceil(BITS_PER_WORD)]
+    }
+
+    fn words_mut(&mut self) -> &mut [usize] {
+        let words = self.num_bits.div_ceil(BITS_PER_WORD);
+        &mut self.words.as_mut()[..words]
+    }
+
+    fn check(&self, i: usize) {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+    }
+
+    /// Tests whether the ith bit is set
+    #[inline]
+    pub fn test(&self, i: usize) -> bool {
+        self.check(i);
+        self.words.as_ref()[get_word_offset(i)] & get_bitmask(i) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    #[inline]
+    pub fn set(&mut self, i: usize) -> bool {
+        let was_clear = !self.test(i);
+        self.words.as_mut()[get_word_offset(i)] |= get_bitmask(i);
+        was_clear
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    #[inline]
+    pub fn remove(&mut self, i: usize) -> bool {
+        let was_set = self.test(i);
+        self.words.as_mut()[get_word_offset(i)] &= !get_bitmask(i);
+        was_set
+    }
+
+    /// Flips the ith bit
+    #[inline]
+    pub fn flip(&mut self, i: usize) {
+        self.check(i);
+        self.words.as_mut()[get_word_offset(i)] ^= get_bitmask(i);
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.as_words().iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> DenseOnesIterator<'_> {
+        DenseOnesIterator::new(self.as_words(), self.num_bits)
+    }
+
+    /// In-place bitwise-and with `other`
+    pub fn inplace_and<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let other = other.as_words();
+        for (i, word) in self.words_mut().iter_mut().enumerate() {
+            *word &= other.get(i).copied().unwrap_or(0);
+        }
+    }
+
+    /// In-place bitwise-or with `other`. Bits of `other` past `len()` are ignored.
+    pub fn inplace_or<S: BitWords + ?Sized>(&mut self, other: &S) {
+        for (word, other) in self.words_mut().iter_mut().zip(other.as_words().iter()) {
+            *word |= *other;
+        }
+        self.clear_padding();
+    }
+
+    /// In-place bitwise-xor with `other`. Bits of `other` past `len()` are ignored.
+    pub fn inplace_xor<S: BitWords + ?Sized>(&mut self, other: &S) {
+        for (word, other) in self.words_mut().iter_mut().zip(other.as_words().iter()) {
+            *word ^= *other;
+        }
+        self.clear_padding();
+    }
+
+    fn clear_padding(&mut self) {
+        let mask = get_low_mask(self.num_bits - get_word_offset(self.num_bits.saturating_sub(1)) * BITS_PER_WORD);
+        if let Some(last) = self.words_mut().last_mut() {
+            *last &= mask;
+        }
+    }
+
+    /// Copies the bits into a `DenseBitSet`
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(self.as_words().to_vec(), self.num_bits)
+    }
+
+    /// Copies the bits into a new buffer from `allocator`, or returns `None`
+    /// if it is out of space
+    pub fn clone_in<A: WordAllocator + ?Sized>(&self, allocator: &A) -> Option<AllocBitSet<A::Buffer>> {
+        let mut output = AllocBitSet::new_in(self.num_bits, allocator)?;
+        output.words_mut().copy_from_slice(self.as_words());
+        Some(output)
+    }
+}
+
+impl<B: AsRef<[usize]> + AsMut<[usize]>> BitWords for AllocBitSet<B> {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        Cow::Borrowed(AllocBitSet::as_words(self))
+    }
+
+    fn bit_len(&self) -> usize {
+        self.num_bits
+    }
+}
+
+impl<B: AsRef<[usize]> + AsMut<[usize]>> BitSet for AllocBitSet<B> {
+    fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    fn test(&self, i: usize) -> bool {
+        AllocBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        AllocBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        AllocBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        AllocBitSet::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        self.ones().find(|&i| i >= from)
+    }
+}
+
+impl<B: AsRef<[usize]> + AsMut<[usize]>, C: AsRef<[usize]> + AsMut<[usize]>> PartialEq<AllocBitSet<C>> for AllocBitSet<B> {
+    fn eq(&self, other: &AllocBitSet<C>) -> bool {
+        self.num_bits == other.num_bits && self.as_words() == other.as_words()
+    }
+}
+
+// AllocBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn arena_hands_out_disjoint_buffers() {
+        let mut memory = [!0usize; 5];
+        let arena = ArenaAllocator::new(&mut memory);
+
+        let mut a = AllocBitSet::new_in(2 * BITS_PER_WORD, &arena).unwrap();
+        let b = AllocBitSet::new_in(BITS_PER_WORD + 1, &arena).unwrap();
+        assert_eq!(arena.remaining(), 1);
+        assert!(AllocBitSet::new_in(2 * BITS_PER_WORD, &arena).is_none());
+
+        assert_eq!(b.count_ones(), 0);
+        a.set(BITS_PER_WORD + 1);
+        assert!(!b.test(1));
+        assert_eq!(a.to_dense().to_indices(), vec![BITS_PER_WORD + 1]);
+    }
+
+    #[test]
+    fn matches_dense() {
+        let mut a = AllocBitSet::new_in(100, &HeapAllocator).unwrap();
+        let dense = DenseBitSet::from_indices(128, [1, 50, 99, 120]);
+        for i in 0..100 {
+            a.flip(i);
+        }
+        a.inplace_and(&dense);
+        assert_eq!(a.ones().collect::<Vec<_>>(), vec![1, 50, 99]);
+
+        a.inplace_xor(&dense);
+        assert_eq!(a.count_ones(), 0);
+        a.inplace_or(&dense);
+        assert_eq!(a.count_ones(), 3);
+
+        let mut memory = [0usize; 2];
+        let copy = a.clone_in(&ArenaAllocator::new(&mut memory)).unwrap();
+        assert_eq!(copy, a);
+    }
+
+    #[test]
+    fn from_buffer_checks_size() {
+        let mut memory = [!0usize; 1];
+        assert!(AllocBitSet::from_buffer(&mut memory[..], BITS_PER_WORD + 1).is_none());
+        let a = AllocBitSet::from_buffer(&mut memory[..], 10).unwrap();
+        assert_eq!(a.count_ones(), 0);
+    }
+}
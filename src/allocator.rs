@@ -0,0 +1,212 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A contiguous-range allocator over a `DenseBitSet`
+
+use std::ops::Range;
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// Free-space statistics for a `BitmapAllocator`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AllocatorStats {
+    /// number of slots managed
+    pub capacity: usize,
+    /// number of free slots
+    pub free: usize,
+    /// number of maximal runs of free slots
+    pub free_runs: usize,
+    /// length of the longest run of free slots
+    pub largest_free_run: usize,
+}
+
+impl AllocatorStats {
+    /// returns the fraction of free space outside the largest free run:
+    /// 0 when all free space is contiguous, approaching 1 as it splinters
+    pub fn fragmentation(&self) -> f64 {
+        if self.free == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free_run as f64 / self.free as f64
+        }
+    }
+}
+
+/// Hands out contiguous ranges of slots (pages, blocks, channels) tracked
+/// one bit per slot, first fit.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::BitmapAllocator;
+///
+/// let mut pages = BitmapAllocator::new(16);
+///
+/// let a = pages.allocate(4).unwrap();
+/// let b = pages.allocate_aligned(4, 8).unwrap();
+/// assert_eq!((a.clone(), b), (0..4, 8..12));
+///
+/// pages.free(a);
+/// assert_eq!(pages.allocate(6), Some(0..6));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitmapAllocator {
+    used: DenseBitSet,
+}
+
+impl BitmapAllocator {
+    /// Creates an allocator managing `capacity` free slots
+    pub fn new(capacity: usize) -> BitmapAllocator {
+        let words = capacity.div_ceil(BITS_PER_WORD);
+        BitmapAllocator { used: DenseBitSet::from_vec_masked(vec![0; words], capacity) }
+    }
+
+    /// returns the number of slots managed
+    pub fn capacity(&self) -> usize {
+        self.used.len()
+    }
+
+    /// returns the number of allocated slots
+    pub fn allocated(&self) -> usize {
+        self.used.count_ones()
+    }
+
+    /// returns whether slot `i` is allocated
+    pub fn is_allocated(&self, i: usize) -> bool {
+        self.used.test(i)
+    }
+
+    /// Borrows the allocation bitmap, one set bit per allocated slot
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.used
+    }
+
+    /// Allocates the first run of `n` free slots
+    pub fn allocate(&mut self, n: usize) -> Option<Range<usize>> {
+        let start = self.used.find_zero_run(n)?;
+        self.used.set_range(start..start + n);
+        Some(start..start + n)
+    }
+
+    /// Allocates the first run of `n` free slots starting at a multiple of
+    /// `align`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::BitmapAllocator;
+    ///
+    /// let mut blocks = BitmapAllocator::new(64);
+    /// blocks.allocate(1);
+    ///
+    /// assert_eq!(blocks.allocate_aligned(3, 16), Some(16..19));
+    /// assert_eq!(blocks.allocate_aligned(3, 16), Some(32..35));
+    /// ```
+    pub fn allocate_aligned(&mut self, n: usize, align: usize) -> Option<Range<usize>> {
+        assert!(align > 0);
+
+        let mut pos = 0;
+        loop {
+            let run = self.used.find_zero_run_from(pos, n)?;
+            let start = run.div_ceil(align) * align;
+            if start + n > self.capacity() {
+                return None;
+            }
+            if !self.used.any_in_range(start..start + n) {
+                self.used.set_range(start..start + n);
+                return Some(start..start + n);
+            }
+            pos = start;
+        }
+    }
+
+    /// Returns the slots in `range` to the free pool.
+    /// Panics if any slot in `range` is not allocated.
+    pub fn free(&mut self, range: Range<usize>) {
+        assert!(self.used.all_in_range(range.clone()), "freeing unallocated slots in {:?}", range);
+        self.used.clear_range(range);
+    }
+
+    /// Returns free-space and fragmentation statistics
+    pub fn stats(&self) -> AllocatorStats {
+        let mut stats = AllocatorStats { capacity: self.capacity(), free: 0, free_runs: 0, largest_free_run: 0 };
+
+        let mut pos = 0;
+        while let Some(start) = self.used.next_clear_bit(pos) {
+            let end = self.used.next_set_bit(start).unwrap_or(self.capacity());
+            stats.free += end - start;
+            stats.free_runs += 1;
+            stats.largest_free_run = stats.largest_free_run.max(end - start);
+            pos = end;
+        }
+        stats
+    }
+}
+
+// BitmapAllocator TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_allocate_and_free() {
+        let mut alloc = BitmapAllocator::new(100);
+
+        let a = alloc.allocate(30).unwrap();
+        let b = alloc.allocate(30).unwrap();
+        let c = alloc.allocate(30).unwrap();
+        assert_eq!((a.clone(), b.clone(), c), (0..30, 30..60, 60..90));
+        assert_eq!(alloc.allocate(11), None);
+
+        alloc.free(b);
+        assert_eq!(alloc.allocated(), 60);
+        assert_eq!(alloc.allocate(31), None);
+        assert_eq!(alloc.allocate(20), Some(30..50));
+        alloc.free(a);
+        assert_eq!(alloc.allocate(40), None);
+    }
+
+    #[test]
+    fn reports_fragmentation() {
+        let mut alloc = BitmapAllocator::new(200);
+        assert_eq!(alloc.stats().fragmentation(), 0.0);
+
+        let ranges: Vec<_> = (0..10).map(|_| alloc.allocate(20).unwrap()).collect();
+        for range in ranges.into_iter().step_by(2) {
+            alloc.free(range);
+        }
+
+        let stats = alloc.stats();
+        assert_eq!((stats.free, stats.free_runs, stats.largest_free_run), (100, 5, 20));
+        assert!((stats.fragmentation() - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_double_free() {
+        let mut alloc = BitmapAllocator::new(10);
+        let range = alloc.allocate(3).unwrap();
+        alloc.free(range.clone());
+        alloc.free(range);
+    }
+}
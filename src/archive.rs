@@ -0,0 +1,447 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Chunked, zstd-compressed archives of `DenseBitSet`s, enabled by the
+//! `compress` feature
+//!
+//! An archive holds:
+//!
+//! * the magic bytes `BSZ1`,
+//! * the number of bits, a little-endian `u64`,
+//! * the number of 64-bit words per chunk and the number of chunks, two
+//!   little-endian `u32`s,
+//! * an index entry per chunk: a kind byte (0 for all zeros, 1 for raw
+//!   words, 2 for zstd) and the stored length, a little-endian `u64`,
+//! * the stored chunks, back to back.
+//!
+//! Each chunk is compressed only if that makes it smaller, and chunks of
+//! zeros take no space at all. The index lets a reader that can seek
+//! decompress single chunks with `CompressedArchive`.
+
+use std::convert::{ TryFrom, TryInto };
+use std::error::Error;
+use std::fmt;
+use std::io::{ self, Read, Seek, SeekFrom, Write };
+
+use zstd;
+
+use word::words_from_le_bytes;
+use DenseBitSet;
+
+/// words per chunk used by `save_compressed`, 64 KiB of bits
+pub const ARCHIVE_CHUNK_WORDS: usize = 8192;
+
+/// largest chunk an archive may use, 8 MiB of decoded words. Bounds what a
+/// reader allocates for any one chunk, whatever the header claims.
+pub const ARCHIVE_MAX_CHUNK_WORDS: usize = 1 << 20;
+
+/// index entries reserved up front; the header's chunk count is not
+/// trusted until the entries have actually been read
+const PREALLOC_ENTRIES: usize = 4096;
+
+const MAGIC: &[u8; 4] = b"BSZ1";
+const HEADER_BYTES: u64 = 20;
+const ENTRY_BYTES: u64 = 9;
+
+const ZEROS: u8 = 0;
+const RAW: u8 = 1;
+const ZSTD: u8 = 2;
+
+/// An error found while reading a compressed archive
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// the reader failed, or the archive ended early
+    Io(io::Error),
+    /// the archive does not start with a valid header and index
+    InvalidHeader,
+    /// a chunk does not decode to the number of words it should hold
+    CorruptChunk {
+        /// index of the chunk in the archive
+        chunk: usize,
+    },
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArchiveError::Io(ref e) => write!(f, "archive read failed: {}", e),
+            ArchiveError::InvalidHeader => write!(f, "archive header is invalid"),
+            ArchiveError::CorruptChunk { chunk } => write!(f, "archive chunk {} is corrupt", chunk),
+        }
+    }
+}
+
+impl Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ArchiveError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> ArchiveError {
+        ArchiveError::Io(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    kind: u8,
+    offset: u64,
+    stored: u64,
+}
+
+/// the header and index of an archive
+#[derive(Debug)]
+struct Header {
+    num_bits: usize,
+    chunk_words: usize,
+    entries: Vec<Entry>,
+}
+
+impl Header {
+    fn read<R: Read>(reader: &mut R) -> Result<Header, ArchiveError> {
+        let mut fixed = [0u8; HEADER_BYTES as usize];
+        reader.read_exact(&mut fixed)?;
+        if &fixed[..4] != MAGIC {
+            return Err(ArchiveError::InvalidHeader);
+        }
+        let num_bits = u64::from_le_bytes(fixed[4..12].try_into().unwrap());
+        let chunk_words = u32::from_le_bytes(fixed[12..16].try_into().unwrap()) as usize;
+        let chunks = u32::from_le_bytes(fixed[16..20].try_into().unwrap()) as usize;
+
+        let num_bits = usize::try_from(num_bits).map_err(|_| ArchiveError::InvalidHeader)?;
+        if chunk_words == 0 || chunk_words > ARCHIVE_MAX_CHUNK_WORDS {
+            return Err(ArchiveError::InvalidHeader);
+        }
+        if chunks != num_bits.div_ceil(64).div_ceil(chunk_words) {
+            return Err(ArchiveError::InvalidHeader);
+        }
+
+        let mut entries = Vec::with_capacity(chunks.min(PREALLOC_ENTRIES));
+        let mut offset = 0u64;
+        for _ in 0..chunks {
+            let mut entry = [0u8; ENTRY_BYTES as usize];
+            reader.read_exact(&mut entry)?;
+            let stored = u64::from_le_bytes(entry[1..].try_into().unwrap());
+            if entry[0] > ZSTD || (entry[0] == ZEROS && stored != 0) {
+                return Err(ArchiveError::InvalidHeader);
+            }
+            entries.push(Entry { kind: entry[0], offset, stored });
+            offset = offset.checked_add(stored).ok_or(ArchiveError::InvalidHeader)?;
+        }
+        Ok(Header { num_bits, chunk_words, entries })
+    }
+
+    /// returns the number of payload bytes chunk `i` decodes to
+    fn chunk_bytes(&self, i: usize) -> usize {
+        let words = self.num_bits.div_ceil(64);
+        let start = i * self.chunk_words;
+        8 * (words - start).min(self.chunk_words)
+    }
+
+    /// decodes the stored bytes of chunk `i`
+    fn decode(&self, i: usize, stored: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+        let expected = self.chunk_bytes(i);
+        let bytes = match self.entries[i].kind {
+            ZEROS => vec![0; expected],
+            RAW => stored.to_vec(),
+            _ => zstd::bulk::decompress(stored, expected).map_err(|_| ArchiveError::CorruptChunk { chunk: i })?,
+        };
+        if bytes.len() != expected {
+            return Err(ArchiveError::CorruptChunk { chunk: i });
+        }
+        Ok(bytes)
+    }
+}
+
+fn read_stored<R: Read>(reader: &mut R, entry: &Entry) -> Result<Vec<u8>, ArchiveError> {
+    let len = usize::try_from(entry.stored).map_err(|_| ArchiveError::InvalidHeader)?;
+    let mut stored = Vec::new();
+    reader.take(entry.stored).read_to_end(&mut stored)?;
+    if stored.len() != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(stored)
+}
+
+/// builds a set of `num_bits` bits from little-endian `u64` bytes
+fn from_bytes(mut bytes: Vec<u8>, num_bits: usize) -> Option<DenseBitSet> {
+    bytes.truncate(num_bits.div_ceil(8));
+    DenseBitSet::from_vec_with_len(words_from_le_bytes(&bytes), num_bits)
+}
+
+impl DenseBitSet {
+    /// Writes the set as a compressed archive with chunks of
+    /// `ARCHIVE_CHUNK_WORDS` words
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(1 << 20, (0..1000).map(|i| i * 7));
+    /// let mut archive = Vec::new();
+    /// bs.save_compressed(&mut archive).unwrap();
+    ///
+    /// assert!(archive.len() < bs.len() / 8 / 10);
+    /// assert_eq!(DenseBitSet::load_compressed(&archive[..]).unwrap(), bs);
+    /// ```
+    pub fn save_compressed<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.save_compressed_chunked(writer, ARCHIVE_CHUNK_WORDS)
+    }
+
+    /// Writes the set as a compressed archive with chunks of `chunk_words`
+    /// 64-bit words. Smaller chunks make partial reads cheaper and
+    /// compress less well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_words` is zero or above `ARCHIVE_MAX_CHUNK_WORDS`.
+    pub fn save_compressed_chunked<W: Write>(&self, mut writer: W, chunk_words: usize) -> io::Result<()> {
+        assert!(chunk_words > 0 && chunk_words <= ARCHIVE_MAX_CHUNK_WORDS, "invalid chunk size {}", chunk_words);
+        let mut bytes = self.to_le_bytes();
+        bytes.resize(self.len().div_ceil(64) * 8, 0);
+
+        let mut entries = Vec::new();
+        let mut payloads = Vec::new();
+        for chunk in bytes.chunks(chunk_words * 8) {
+            if chunk.iter().all(|&b| b == 0) {
+                entries.push((ZEROS, 0));
+                continue;
+            }
+            let compressed = zstd::bulk::compress(chunk, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+            if compressed.len() < chunk.len() {
+                entries.push((ZSTD, compressed.len()));
+                payloads.push(compressed);
+            } else {
+                entries.push((RAW, chunk.len()));
+                payloads.push(chunk.to_vec());
+            }
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        writer.write_all(&(chunk_words as u32).to_le_bytes())?;
+        writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+        for &(kind, stored) in &entries {
+            writer.write_all(&[kind])?;
+            writer.write_all(&(stored as u64).to_le_bytes())?;
+        }
+        for payload in &payloads {
+            writer.write_all(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a whole set written by `save_compressed`
+    pub fn load_compressed<R: Read>(mut reader: R) -> Result<DenseBitSet, ArchiveError> {
+        let header = Header::read(&mut reader)?;
+        // grown chunk by chunk, so a header claiming more bits than the
+        // stream holds fails on the missing chunks instead of allocating
+        let mut bytes = Vec::new();
+        for (i, entry) in header.entries.iter().enumerate() {
+            let stored = read_stored(&mut reader, entry)?;
+            bytes.extend(header.decode(i, &stored)?);
+        }
+        // only the last chunk can hold bits past the end
+        from_bytes(bytes, header.num_bits).ok_or_else(|| ArchiveError::CorruptChunk { chunk: header.entries.len() - 1 })
+    }
+}
+
+/// A compressed archive opened for random access.
+///
+/// Only the header and index are read up front; each chunk is read and
+/// decompressed on demand.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bitsets::{ CompressedArchive, DenseBitSet };
+///
+/// let bs = DenseBitSet::from_indices(4096, [5, 3000]);
+/// let mut bytes = Vec::new();
+/// bs.save_compressed_chunked(&mut bytes, 16).unwrap();
+///
+/// let mut archive = CompressedArchive::open(Cursor::new(bytes)).unwrap();
+/// assert_eq!(archive.chunks(), 4);
+/// assert!(archive.test(3000).unwrap());
+/// assert_eq!(archive.load_chunk(2).unwrap().to_indices(), vec![3000 - 2048]);
+/// ```
+#[derive(Debug)]
+pub struct CompressedArchive<R> {
+    reader: R,
+    header: Header,
+    payload_start: u64,
+}
+
+impl<R: Read + Seek> CompressedArchive<R> {
+    /// Reads the header and index of the archive starting at the current
+    /// position of `reader`
+    pub fn open(mut reader: R) -> Result<CompressedArchive<R>, ArchiveError> {
+        let header = Header::read(&mut reader)?;
+        let payload_start = reader.stream_position()?;
+        Ok(CompressedArchive { reader, header, payload_start })
+    }
+
+    /// Returns the number of bits in the archived set
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.header.num_bits
+    }
+
+    /// Returns the number of chunks
+    pub fn chunks(&self) -> usize {
+        self.header.entries.len()
+    }
+
+    /// Returns the number of bits in every chunk but possibly the last
+    pub fn chunk_bits(&self) -> usize {
+        self.header.chunk_words * 64
+    }
+
+    /// Decompresses chunk `i`, returning its bits as a set indexed from
+    /// the start of the chunk
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `chunks()`.
+    pub fn load_chunk(&mut self, i: usize) -> Result<DenseBitSet, ArchiveError> {
+        let entry = self.header.entries[i];
+        let stored = if entry.kind == ZEROS {
+            Vec::new()
+        } else {
+            self.reader.seek(SeekFrom::Start(self.payload_start + entry.offset))?;
+            read_stored(&mut self.reader, &entry)?
+        };
+        let bytes = self.header.decode(i, &stored)?;
+        let num_bits = (self.len() - i * self.chunk_bits()).min(self.chunk_bits());
+        from_bytes(bytes, num_bits).ok_or(ArchiveError::CorruptChunk { chunk: i })
+    }
+
+    /// Tests whether the ith bit is set, decompressing only its chunk
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `len()`.
+    pub fn test(&mut self, i: usize) -> Result<bool, ArchiveError> {
+        assert!(i < self.len(), "index {} is out of bounds for an archive of {} bits", i, self.len());
+        let chunk = self.load_chunk(i / self.chunk_bits())?;
+        Ok(chunk.test(i % self.chunk_bits()))
+    }
+
+    /// Returns the reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+// Compressed archive TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trips_mixed_chunks() {
+        let mut bs = DenseBitSet::from_vec_masked(vec![0; 4000usize.div_ceil(::BITS_PER_WORD)], 4000);
+        for i in (0..1000).filter(|i| i % 3 == 0) {
+            bs.set(i);
+        }
+        // incompressible noise in the last chunk
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for i in 3072..4000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if state & 1 != 0 {
+                bs.set(i);
+            }
+        }
+
+        let mut bytes = Vec::new();
+        bs.save_compressed_chunked(&mut bytes, 16).unwrap();
+        let kinds: Vec<u8> = (0..4).map(|i| bytes[20 + 9 * i]).collect();
+        assert_eq!(kinds, vec![ZSTD, ZEROS, ZEROS, RAW]);
+        assert_eq!(DenseBitSet::load_compressed(&bytes[..]).unwrap(), bs);
+
+        let mut archive = CompressedArchive::open(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 4000);
+        assert_eq!(archive.load_chunk(3).unwrap().len(), 4000 - 3072);
+        for i in (0..4000).step_by(37) {
+            assert_eq!(archive.test(i).unwrap(), bs.test(i));
+        }
+    }
+
+    #[test]
+    fn rejects_damaged_archives() {
+        let bs = DenseBitSet::from_indices(1024, (0..1024).filter(|i| i % 5 == 0));
+        let mut bytes = Vec::new();
+        bs.save_compressed_chunked(&mut bytes, 8).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(matches!(DenseBitSet::load_compressed(&bad_magic[..]), Err(ArchiveError::InvalidHeader)));
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(DenseBitSet::load_compressed(truncated), Err(ArchiveError::Io(_))));
+
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xff;
+        assert!(DenseBitSet::load_compressed(&corrupt[..]).is_err());
+    }
+
+    #[test]
+    fn rejects_headers_claiming_more_than_the_stream_holds() {
+        // 0xFFFFFFFF chunks of one word each, with no index behind them
+        let mut hostile = Vec::new();
+        hostile.extend_from_slice(MAGIC);
+        hostile.extend_from_slice(&(0xFFFF_FFFFu64 * 64).to_le_bytes());
+        hostile.extend_from_slice(&1u32.to_le_bytes());
+        hostile.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+
+        assert!(matches!(DenseBitSet::load_compressed(&hostile[..]), Err(ArchiveError::Io(_))));
+        assert!(matches!(CompressedArchive::open(Cursor::new(&hostile)), Err(ArchiveError::Io(_))));
+
+        // a single all-zero chunk that would decode to 32 GiB
+        let mut huge_chunk = Vec::new();
+        huge_chunk.extend_from_slice(MAGIC);
+        huge_chunk.extend_from_slice(&(0xFFFF_FFFFu64 * 64).to_le_bytes());
+        huge_chunk.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+        huge_chunk.extend_from_slice(&1u32.to_le_bytes());
+        huge_chunk.extend_from_slice(&[ZEROS, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert!(matches!(DenseBitSet::load_compressed(&huge_chunk[..]), Err(ArchiveError::InvalidHeader)));
+    }
+
+    #[test]
+    fn handles_empty_sets() {
+        let mut bytes = Vec::new();
+        DenseBitSet::with_capacity(0).save_compressed(&mut bytes).unwrap();
+        assert_eq!(DenseBitSet::load_compressed(&bytes[..]).unwrap().len(), 0);
+    }
+}
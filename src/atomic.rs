@@ -0,0 +1,305 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A fixed-capacity bit set that can be shared and mutated across threads,
+//! enabled by the `atomic` feature
+
+use std::fmt;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+use { get_bitmask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// A bit set over `AtomicUsize` words, mutated through `&self`.
+///
+/// Single-bit operations are atomic read-modify-writes on the word holding
+/// the bit: `set`, `remove` and `flip` use `AcqRel`, `test` uses `Acquire`.
+/// Operations spanning several words are not atomic as a whole; see
+/// `snapshot_ones` for what a reader can observe.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::sync::atomic::Ordering;
+/// use std::thread;
+/// use bitsets::AtomicBitSet;
+///
+/// let bs = Arc::new(AtomicBitSet::with_capacity(256));
+/// let handles: Vec<_> = (0..4).map(|t| {
+///     let bs = Arc::clone(&bs);
+///     thread::spawn(move || { bs.set(t * 64); })
+/// }).collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// let ones: Vec<usize> = bs.snapshot_ones(Ordering::Acquire).collect();
+/// assert_eq!(ones, vec![0, 64, 128, 192]);
+/// ```
+pub struct AtomicBitSet {
+    num_bits: usize,
+    bits: Vec<AtomicUsize>,
+}
+
+impl AtomicBitSet {
+    /// Creates an `AtomicBitSet` that can contain at least `num_bits` bits.
+    /// This will be rounded to the nearest word size that can accomodate
+    /// `num_bits` bits.
+    pub fn with_capacity(num_bits: usize) -> AtomicBitSet {
+        let words = num_bits.div_ceil(BITS_PER_WORD);
+
+        AtomicBitSet {
+            num_bits: words * BITS_PER_WORD,
+            bits: (0..words).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    /// returns the number of words in the underlying storage
+    pub fn words(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// returns the number of bits this set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        (self.bits[get_word_offset(i)].load(Ordering::Acquire) & get_bitmask(i)) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let bitmask = get_bitmask(i);
+        let prior = self.bits[get_word_offset(i)].fetch_or(bitmask, Ordering::AcqRel);
+        (prior & bitmask) == 0
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let bitmask = get_bitmask(i);
+        let prior = self.bits[get_word_offset(i)].fetch_and(!bitmask, Ordering::AcqRel);
+        (prior & bitmask) != 0
+    }
+
+    /// flips the value of the ith bit
+    pub fn flip(&self, i: usize) {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        self.bits[get_word_offset(i)].fetch_xor(get_bitmask(i), Ordering::AcqRel);
+    }
+
+    /// Copies the set into a `DenseBitSet`, loading each word once with
+    /// `order`. The copy has the same per-word guarantees as `snapshot_ones`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is `Release` or `AcqRel`, as for `AtomicUsize::load`.
+    pub fn snapshot(&self, order: Ordering) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(self.bits.iter().map(|word| word.load(order)).collect(), self.num_bits)
+    }
+
+    /// Returns an iterator over the indices of the set bits, loading each
+    /// word exactly once, with `order`, when the iteration reaches it.
+    ///
+    /// What a concurrent reader can observe:
+    ///
+    /// * every word is read by a single atomic load, so the indices from
+    ///   one word reflect one state of that word: a read-modify-write on it
+    ///   is seen completely or not at all;
+    /// * words are loaded in ascending order, and later words are loaded
+    ///   later, so mutations made while iterating may show up in words not
+    ///   reached yet but never in words already passed;
+    /// * there is no consistency across words: two bits set together in
+    ///   different words by another thread can be observed one without the
+    ///   other;
+    /// * with `Acquire` (or `SeqCst`), writes the mutating thread made
+    ///   before a `set` that is observed are also visible to the reader;
+    ///   `Relaxed` gives no such guarantee.
+    ///
+    /// Use `snapshot` first if the same frozen state must be walked more
+    /// than once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is `Release` or `AcqRel`, as for `AtomicUsize::load`.
+    pub fn snapshot_ones(&self, order: Ordering) -> AtomicOnesIterator<'_> {
+        AtomicOnesIterator {
+            bits: &self.bits,
+            order,
+            word_idx: 0,
+            current: self.bits.first().map_or(0, |word| word.load(order)),
+        }
+    }
+
+    /// Clears every bit. Each word is cleared atomically, the set as a
+    /// whole is not.
+    pub fn clear(&self) {
+        for word in &self.bits {
+            word.store(0, Ordering::Release);
+        }
+    }
+}
+
+impl From<DenseBitSet> for AtomicBitSet {
+    fn from(bs: DenseBitSet) -> AtomicBitSet {
+        AtomicBitSet {
+            num_bits: bs.num_bits,
+            bits: bs.bits.into_iter().map(AtomicUsize::new).collect(),
+        }
+    }
+}
+
+impl fmt::Debug for AtomicBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AtomicBitSet: ")?;
+        for word in &self.bits {
+            let word = word.load(Ordering::Acquire);
+            for bit in 0..BITS_PER_WORD {
+                write!(f, "{}", (word >> bit) & 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the set bits of an AtomicBitSet, loading one word at a
+/// time. See `AtomicBitSet::snapshot_ones`.
+pub struct AtomicOnesIterator<'a> {
+    bits: &'a [AtomicUsize],
+    order: Ordering,
+    word_idx: usize,
+    current: usize,
+}
+
+impl<'a> Iterator for AtomicOnesIterator<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            self.word_idx += 1;
+            if self.word_idx >= self.bits.len() {
+                return None;
+            }
+            self.current = self.bits[self.word_idx].load(self.order);
+        }
+
+        let i = self.word_idx * BITS_PER_WORD + self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(i)
+    }
+}
+
+// AtomicBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn can_set_and_remove() {
+        let bs = AtomicBitSet::with_capacity(100);
+
+        assert_eq!(bs.len(), 128);
+        assert!(bs.set(70));
+        assert!(!bs.set(70));
+        assert!(bs.test(70));
+
+        bs.flip(3);
+        assert!(bs.test(3));
+
+        assert!(bs.remove(70));
+        assert!(!bs.test(70));
+    }
+
+    #[test]
+    fn snapshot_matches_dense() {
+        let dense = DenseBitSet::from_indices(300, vec![1, 64, 65, 299]);
+        let bs = AtomicBitSet::from(dense.clone());
+
+        assert_eq!(bs.snapshot(Ordering::Relaxed), dense);
+        assert_eq!(bs.snapshot_ones(Ordering::SeqCst).collect::<Vec<_>>(), dense.to_indices());
+    }
+
+    #[test]
+    fn snapshot_keeps_exact_length() {
+        let dense = DenseBitSet::from_vec_masked(vec![0b101], 3);
+        let bs = AtomicBitSet::from(dense.clone());
+
+        assert_eq!(bs.snapshot(Ordering::Acquire).len(), 3);
+        assert_eq!(bs.snapshot(Ordering::Acquire), dense);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 is out of bounds for a set of 3 bits")]
+    fn rejects_set_past_len() {
+        AtomicBitSet::from(DenseBitSet::from_vec_masked(vec![0], 3)).set(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 is out of bounds for a set of 3 bits")]
+    fn rejects_flip_past_len() {
+        AtomicBitSet::from(DenseBitSet::from_vec_masked(vec![0], 3)).flip(3);
+    }
+
+    #[test]
+    fn snapshot_loads_each_word_once() {
+        let bs = AtomicBitSet::with_capacity(128);
+        bs.set(0);
+        bs.set(1);
+
+        let mut ones = bs.snapshot_ones(Ordering::Acquire);
+        assert_eq!(ones.next(), Some(0));
+
+        // the first word is already loaded, the second one is not
+        bs.remove(1);
+        bs.set(100);
+        assert_eq!(ones.collect::<Vec<_>>(), vec![1, 100]);
+    }
+
+    #[test]
+    fn concurrent_writers_are_all_seen() {
+        let bs = Arc::new(AtomicBitSet::with_capacity(4096));
+
+        let handles: Vec<_> = (0..4).map(|t| {
+            let bs = Arc::clone(&bs);
+            thread::spawn(move || {
+                for i in (t..4096).step_by(4) {
+                    bs.set(i);
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(bs.snapshot(Ordering::Acquire).count_ones(), 4096);
+    }
+}
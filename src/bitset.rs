@@ -0,0 +1,115 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `BitSet` trait, the API shared by the set backends
+
+use DenseBitSet;
+
+/// Operations every bit set backend supports, so code can be written once
+/// and run against whichever representation suits the data.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "sparse")] {
+/// use bitsets::{ BitSet, DenseBitSet, SparseBitSet };
+///
+/// fn mark_multiples<S: BitSet>(set: &mut S, n: usize) {
+///     for i in (0..set.len()).step_by(n) {
+///         set.set(i);
+///     }
+/// }
+///
+/// let mut dense = DenseBitSet::with_capacity(128);
+/// let mut sparse = SparseBitSet::new(128);
+/// mark_multiples(&mut dense, 7);
+/// mark_multiples(&mut sparse, 7);
+///
+/// assert_eq!(dense.count_ones(), sparse.count_ones());
+/// # }
+/// ```
+pub trait BitSet {
+    /// Returns the number of bits the set can accommodate
+    fn len(&self) -> usize;
+
+    /// Tests whether the ith bit is set
+    fn test(&self, i: usize) -> bool;
+
+    /// Sets the ith bit, returning true if it was not set previously
+    fn set(&mut self, i: usize) -> bool;
+
+    /// Clears the ith bit, returning true if it was set previously
+    fn remove(&mut self, i: usize) -> bool;
+
+    /// Returns the number of set bits
+    fn count_ones(&self) -> usize;
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    fn next_set_bit(&self, from: usize) -> Option<usize>;
+
+    /// Returns whether the set can hold no bits at all
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the indices of the set bits in ascending order
+    fn to_indices(&self) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(self.count_ones());
+        let mut from = 0;
+        while let Some(i) = self.next_set_bit(from) {
+            indices.push(i);
+            from = i + 1;
+        }
+        indices
+    }
+}
+
+impl BitSet for DenseBitSet {
+    fn len(&self) -> usize {
+        DenseBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        DenseBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        DenseBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        DenseBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        <DenseBitSet>::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        DenseBitSet::next_set_bit(self, from)
+    }
+
+    fn to_indices(&self) -> Vec<usize> {
+        DenseBitSet::to_indices(self)
+    }
+}
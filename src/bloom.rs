@@ -0,0 +1,248 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bloom filters over `DenseBitSet`, including a rotating, age-partitioned
+//! variant for "seen recently" dedupe
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+
+use DenseBitSet;
+
+/// returns the `num_hashes` bit positions of `item` in a filter of
+/// `num_bits` bits, derived by double hashing a single 64-bit hash
+fn positions<T: Hash + ?Sized>(item: &T, num_hashes: usize, num_bits: usize) -> impl Iterator<Item = usize> {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let h1 = hash & 0xffff_ffff;
+    let h2 = (hash >> 32) | 1;
+    (0..num_hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits as u64) as usize)
+}
+
+/// A Bloom filter: a set membership test with false positives but no
+/// false negatives, stored in a `DenseBitSet`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_rate(1000, 0.01);
+///
+/// filter.insert("apple");
+///
+/// assert!(filter.contains("apple"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BloomFilter {
+    bits: DenseBitSet,
+    num_hashes: usize,
+}
+
+impl BloomFilter {
+    /// Creates a filter of at least `num_bits` bits that sets `num_hashes`
+    /// bits per item
+    pub fn new(num_bits: usize, num_hashes: usize) -> BloomFilter {
+        assert!(num_bits > 0 && num_hashes > 0);
+
+        BloomFilter {
+            bits: DenseBitSet::with_capacity(num_bits),
+            num_hashes,
+        }
+    }
+
+    /// Creates a filter sized for `expected_items` items at the given
+    /// false positive rate, using the usual optimal size and hash count
+    pub fn with_rate(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        assert!(false_positive_rate > 0.0 && false_positive_rate < 1.0);
+
+        let ln2 = std::f64::consts::LN_2;
+        let items = expected_items.max(1) as f64;
+        let num_bits = (-items * false_positive_rate.ln() / (ln2 * ln2)).ceil() as usize;
+        let num_hashes = ((num_bits as f64 / items) * ln2).round().max(1.0) as usize;
+
+        BloomFilter::new(num_bits, num_hashes)
+    }
+
+    /// returns the number of bits in the filter
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// returns the number of bits set per item
+    pub fn num_hashes(&self) -> usize {
+        self.num_hashes
+    }
+
+    /// Adds `item` to the filter.
+    /// Returns true if `item` was definitely not present previously
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let mut added = false;
+        for i in positions(item, self.num_hashes, self.bits.len()) {
+            added |= self.bits.set(i);
+        }
+        added
+    }
+
+    /// Tests whether `item` may have been inserted. False positives are
+    /// possible, false negatives are not.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        positions(item, self.num_hashes, self.bits.len()).all(|i| self.bits.test(i))
+    }
+
+    /// Forgets every item, keeping the allocation
+    pub fn clear(&mut self) {
+        for word in &mut self.bits.bits {
+            *word = 0;
+        }
+    }
+
+    /// returns the bits of the filter
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+}
+
+/// A dedupe filter remembering items for a bounded number of generations.
+///
+/// Items are inserted into the current generation and looked up in all of
+/// them. `advance()` drops the oldest generation and reuses its memory for
+/// a new, empty current one, so an item is remembered for at least
+/// `generations - 1` and at most `generations` calls to `advance()` after
+/// its last insertion. Memory stays at `generations` filters.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::RotatingBloomFilter;
+///
+/// let mut seen = RotatingBloomFilter::new(2, 1024, 3);
+///
+/// seen.insert(&42);
+/// seen.advance();
+/// assert!(seen.contains(&42));
+///
+/// seen.advance();
+/// assert!(!seen.contains(&42));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RotatingBloomFilter {
+    generations: Vec<BloomFilter>,
+    current: usize,
+}
+
+impl RotatingBloomFilter {
+    /// Creates a filter of `generations` generations, each of at least
+    /// `num_bits` bits and `num_hashes` hashes per item
+    pub fn new(generations: usize, num_bits: usize, num_hashes: usize) -> RotatingBloomFilter {
+        assert!(generations > 0, "need at least one generation");
+
+        RotatingBloomFilter {
+            generations: vec![BloomFilter::new(num_bits, num_hashes); generations],
+            current: 0,
+        }
+    }
+
+    /// returns the number of generations
+    pub fn generations(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Adds `item` to the current generation.
+    /// Returns true if `item` was definitely not seen in any generation
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let seen = self.contains(item);
+        self.generations[self.current].insert(item);
+        !seen
+    }
+
+    /// Tests whether `item` may have been inserted in any live generation
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.generations.iter().any(|generation| generation.contains(item))
+    }
+
+    /// Starts a new generation, forgetting items only held by the oldest
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1) % self.generations.len();
+        self.generations[self.current].clear();
+    }
+
+    /// Forgets every item in every generation
+    pub fn clear(&mut self) {
+        for generation in &mut self.generations {
+            generation.clear();
+        }
+    }
+}
+
+// Bloom filter TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn has_no_false_negatives() {
+        let mut filter = BloomFilter::with_rate(500, 0.01);
+        for i in 0..500 {
+            filter.insert(&i);
+        }
+
+        assert!((0..500).all(|i| filter.contains(&i)));
+        let false_positives = (500..10500).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn can_clear_filter() {
+        let mut filter = BloomFilter::new(256, 3);
+
+        assert!(filter.insert("a"));
+        assert!(!filter.insert("a"));
+        filter.clear();
+        assert!(!filter.contains("a"));
+        assert_eq!(filter.as_bitset().count_ones(), 0);
+    }
+
+    #[test]
+    fn rotation_forgets_old_items() {
+        let mut seen = RotatingBloomFilter::new(3, 4096, 4);
+
+        assert!(seen.insert("first"));
+        seen.advance();
+        assert!(seen.insert("second"));
+        assert!(!seen.insert("first"));
+
+        seen.advance();
+        seen.advance();
+        // "first" was refreshed one generation after it was first inserted
+        assert!(seen.contains("first"));
+        assert!(seen.contains("second"));
+
+        seen.advance();
+        assert!(!seen.contains("first"));
+        assert!(!seen.contains("second"));
+    }
+}
@@ -0,0 +1,147 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! CLOCK page-replacement built on a `DenseBitSet` of reference bits
+
+use DenseBitSet;
+
+/// Tracks one reference bit per slot and picks eviction victims with the
+/// CLOCK (second chance) algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::ClockBitmap;
+///
+/// let mut clock = ClockBitmap::new(4);
+///
+/// clock.access(0);
+/// clock.access(1);
+///
+/// // slots 0 and 1 get a second chance, slot 2 is evicted
+/// assert_eq!(clock.next_victim(), Some(2));
+/// assert!(!clock.is_referenced(0));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClockBitmap {
+    referenced: DenseBitSet,
+    num_slots: usize,
+    hand: usize,
+}
+
+impl ClockBitmap {
+    /// Creates a clock over `num_slots` slots, none of them referenced
+    pub fn new(num_slots: usize) -> ClockBitmap {
+        ClockBitmap {
+            referenced: DenseBitSet::with_capacity(num_slots),
+            num_slots,
+            hand: 0,
+        }
+    }
+
+    /// returns the number of slots tracked by the clock
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_slots
+    }
+
+    /// returns the slot the hand currently points at
+    pub fn hand(&self) -> usize {
+        self.hand
+    }
+
+    /// Marks `slot` as recently used.
+    /// Returns true if the reference bit was not set previously
+    pub fn access(&mut self, slot: usize) -> bool {
+        assert!(slot < self.num_slots);
+        self.referenced.set(slot)
+    }
+
+    /// Tests whether `slot` has been used since the hand last passed it
+    pub fn is_referenced(&self, slot: usize) -> bool {
+        assert!(slot < self.num_slots);
+        self.referenced.test(slot)
+    }
+
+    /// Clears the reference bit of `slot`, e.g. when its page is invalidated
+    pub fn clear(&mut self, slot: usize) {
+        assert!(slot < self.num_slots);
+        self.referenced.remove(slot);
+    }
+
+    /// Sweeps the hand forward, clearing reference bits as it goes, and
+    /// returns the first slot found without one. The hand is left just past
+    /// the victim. Returns `None` only when the clock has no slots.
+    pub fn next_victim(&mut self) -> Option<usize> {
+        if self.num_slots == 0 {
+            return None;
+        }
+
+        // after one full revolution every bit has been cleared
+        loop {
+            let slot = self.hand;
+            self.hand = (self.hand + 1) % self.num_slots;
+
+            if !self.referenced.remove(slot) {
+                return Some(slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn evicts_unreferenced_slots_in_order() {
+        let mut clock = ClockBitmap::new(3);
+
+        assert_eq!(clock.next_victim(), Some(0));
+        assert_eq!(clock.next_victim(), Some(1));
+        assert_eq!(clock.next_victim(), Some(2));
+        assert_eq!(clock.next_victim(), Some(0));
+    }
+
+    #[test]
+    fn gives_referenced_slots_a_second_chance() {
+        let mut clock = ClockBitmap::new(3);
+        clock.access(0);
+        clock.access(1);
+        clock.access(2);
+
+        // full revolution clears everything, then slot 0 is chosen
+        assert_eq!(clock.next_victim(), Some(0));
+        assert_eq!(clock.hand(), 1);
+        assert!(!clock.is_referenced(1));
+
+        clock.access(1);
+        assert_eq!(clock.next_victim(), Some(2));
+    }
+
+    #[test]
+    fn empty_clock_has_no_victim() {
+        let mut clock = ClockBitmap::new(0);
+        assert_eq!(clock.next_victim(), None);
+    }
+}
@@ -0,0 +1,253 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Combining many `DenseBitSet`s at once
+//!
+//! Folding `n` sets pairwise streams the whole accumulator through the cache
+//! `n` times. These functions walk the inputs one block of words at a time
+//! instead, so the block of output being built stays in L1 while every input
+//! is applied to it.
+
+use { simd, DenseBitSet };
+
+/// words combined per block: 2 KiB of output on 64-bit targets
+const COMBINE_BLOCK_WORDS: usize = 256;
+
+/// Collects the inputs, checking they all share one length
+fn collect_sets<'a, I: IntoIterator<Item = &'a DenseBitSet>>(sets: I) -> Vec<&'a DenseBitSet> {
+    let sets: Vec<_> = sets.into_iter().collect();
+    if let Some(first) = sets.first() {
+        assert!(sets.iter().all(|set| set.len() == first.len()));
+    }
+    sets
+}
+
+/// Initializes the output from the first input, then applies `op` with
+/// every other input, one block of words at a time
+fn fold_blocks(sets: &[&DenseBitSet], op: fn(&mut [usize], &[usize])) -> Option<DenseBitSet> {
+    let (first, rest) = sets.split_first()?;
+    let mut output = (*first).clone();
+    let words = output.words();
+
+    for start in (0..words).step_by(COMBINE_BLOCK_WORDS) {
+        let end = (start + COMBINE_BLOCK_WORDS).min(words);
+        let block = &mut output.bits[start..end];
+        for set in rest {
+            op(block, &set.bits[start..end]);
+        }
+    }
+    Some(output)
+}
+
+/// Adds one to the bit-sliced counter of every position set in `word`,
+/// where `planes[p]` holds bit `p` of each position's count. Returns the
+/// carry out of the top plane.
+pub(crate) fn add_word(planes: &mut [usize], word: usize) -> usize {
+    let mut carry = word;
+    for plane in planes {
+        let sum = *plane ^ carry;
+        carry &= *plane;
+        *plane = sum;
+        if carry == 0 {
+            break;
+        }
+    }
+    carry
+}
+
+/// Returns the positions whose bit-sliced count is at least `k`, assuming
+/// `k` fits in the planes
+pub(crate) fn at_least(planes: &[usize], k: usize) -> usize {
+    // walk from the top plane down, tracking positions known to be
+    // greater than `k` and those equal to it so far
+    let (mut greater, mut equal) = (0, !0);
+    for (p, &plane) in planes.iter().enumerate().rev() {
+        if k >> p & 1 == 1 {
+            equal &= plane;
+        } else {
+            greater |= equal & plane;
+            equal &= !plane;
+        }
+    }
+    greater | equal
+}
+
+/// Returns the number of planes needed to count to `n`
+pub(crate) fn planes_for(n: usize) -> usize {
+    (0usize.leading_zeros() - n.leading_zeros()) as usize
+}
+
+impl DenseBitSet {
+    /// Returns the union of every set in `sets`, or `None` if there are
+    /// none. Panics if the sets differ in length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let sets = vec![
+    ///     DenseBitSet::from_indices(128, vec![1, 2]),
+    ///     DenseBitSet::from_indices(128, vec![2, 70]),
+    ///     DenseBitSet::from_indices(128, vec![2, 127]),
+    /// ];
+    ///
+    /// let union = DenseBitSet::union_many(&sets).unwrap();
+    /// let common = DenseBitSet::intersect_many(&sets).unwrap();
+    ///
+    /// assert_eq!(union.ones().collect::<Vec<_>>(), vec![1, 2, 70, 127]);
+    /// assert_eq!(common.ones().collect::<Vec<_>>(), vec![2]);
+    /// ```
+    pub fn union_many<'a, I: IntoIterator<Item = &'a DenseBitSet>>(sets: I) -> Option<DenseBitSet> {
+        let sets = collect_sets(sets);
+        profiled!(BulkOr, sets.len() * sets.first().map_or(0, |s| s.words()), fold_blocks(&sets, simd::or))
+    }
+
+    /// Returns the intersection of every set in `sets`, or `None` if there
+    /// are none. Panics if the sets differ in length.
+    pub fn intersect_many<'a, I: IntoIterator<Item = &'a DenseBitSet>>(sets: I) -> Option<DenseBitSet> {
+        let sets = collect_sets(sets);
+        profiled!(BulkAnd, sets.len() * sets.first().map_or(0, |s| s.words()), fold_blocks(&sets, simd::and))
+    }
+
+    /// Returns the set of indices held by at least `k` of `sets`, or `None`
+    /// if there are none. Panics if the sets differ in length.
+    ///
+    /// Counts are kept bit-sliced, one plane per bit of the count, so each
+    /// input costs a handful of word operations per word.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let votes = vec![
+    ///     DenseBitSet::from_indices(64, vec![1, 2, 3]),
+    ///     DenseBitSet::from_indices(64, vec![2, 3]),
+    ///     DenseBitSet::from_indices(64, vec![3, 4]),
+    /// ];
+    ///
+    /// let agreed = DenseBitSet::threshold_many(&votes, 2).unwrap();
+    /// assert_eq!(agreed.ones().collect::<Vec<_>>(), vec![2, 3]);
+    /// assert_eq!(DenseBitSet::majority_many(&votes), Some(agreed));
+    /// ```
+    pub fn threshold_many<'a, I: IntoIterator<Item = &'a DenseBitSet>>(sets: I, k: usize) -> Option<DenseBitSet> {
+        let sets = collect_sets(sets);
+        let mut output = DenseBitSet::clone(sets.first()?);
+        let words = output.words();
+
+        if k > sets.len() {
+            output.bits.iter_mut().for_each(|w| *w = 0);
+            return Some(output);
+        }
+
+        let planes = planes_for(sets.len());
+        let mut counts = vec![0; COMBINE_BLOCK_WORDS * planes];
+        for start in (0..words).step_by(COMBINE_BLOCK_WORDS) {
+            let end = (start + COMBINE_BLOCK_WORDS).min(words);
+            counts.iter_mut().for_each(|c| *c = 0);
+
+            for set in &sets {
+                for (counter, &word) in counts.chunks_exact_mut(planes).zip(&set.bits[start..end]) {
+                    add_word(counter, word);
+                }
+            }
+            for (out, counter) in output.bits[start..end].iter_mut().zip(counts.chunks_exact(planes)) {
+                *out = at_least(counter, k);
+            }
+        }
+        output.clear_padding();
+        Some(output)
+    }
+
+    /// Returns the set of indices held by more than half of `sets`, or
+    /// `None` if there are none
+    pub fn majority_many<'a, I: IntoIterator<Item = &'a DenseBitSet>>(sets: I) -> Option<DenseBitSet> {
+        let sets = collect_sets(sets);
+        let k = sets.len() / 2 + 1;
+        DenseBitSet::threshold_many(sets, k)
+    }
+}
+
+// Combine TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use BITS_PER_WORD;
+
+    fn multiples(len: usize, step: usize) -> DenseBitSet {
+        let mut set = DenseBitSet::from_vec_masked(vec![0; len.div_ceil(BITS_PER_WORD)], len);
+        for i in (0..len).step_by(step) {
+            set.set(i);
+        }
+        set
+    }
+
+    #[test]
+    fn many_matches_pairwise_fold() {
+        // spans several blocks plus a partial one
+        let len = 64 * (COMBINE_BLOCK_WORDS * 3 + 7);
+        let sets: Vec<_> = (1..8).map(|step| multiples(len, step)).collect();
+
+        let union = sets[1..].iter().fold(sets[0].clone(), |acc, s| acc.or(s));
+        let common = sets[1..].iter().fold(sets[0].clone(), |acc, s| acc.and(s));
+
+        assert_eq!(DenseBitSet::union_many(&sets), Some(union));
+        assert_eq!(DenseBitSet::intersect_many(sets.iter()), Some(common));
+    }
+
+    #[test]
+    fn threshold_matches_naive_counts() {
+        let len = 64 * (COMBINE_BLOCK_WORDS + 3) + 10;
+        let sets: Vec<_> = (1..12).map(|step| multiples(len, step)).collect();
+
+        for k in 0..14 {
+            let agreed = DenseBitSet::threshold_many(&sets, k).unwrap();
+            assert_eq!(agreed.len(), len);
+            for i in 0..len {
+                let votes = sets.iter().filter(|s| s.test(i)).count();
+                assert_eq!(agreed.test(i), votes >= k, "k = {}, i = {}", k, i);
+            }
+        }
+        // k = 0 must not leak bits past the length
+        assert_eq!(DenseBitSet::threshold_many(&sets, 0).unwrap().count_ones(), len);
+    }
+
+    #[test]
+    fn many_of_nothing_is_none() {
+        let sets: Vec<DenseBitSet> = Vec::new();
+
+        assert_eq!(DenseBitSet::union_many(&sets), None);
+        assert_eq!(DenseBitSet::intersect_many(&sets), None);
+        assert_eq!(DenseBitSet::threshold_many(&sets, 1), None);
+        assert_eq!(DenseBitSet::majority_many(&sets), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn many_rejects_mismatched_lengths() {
+        let sets = vec![multiples(64, 2), multiples(128, 2)];
+        DenseBitSet::union_many(&sets);
+    }
+}
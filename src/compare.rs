@@ -0,0 +1,268 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A harness comparing `DenseBitSet` with other bit set crates, enabled by
+//! the `compare` feature
+//!
+//! The same workloads drive `benches/compare.rs` (`cargo bench --features
+//! compare`), and can drive benchmarks of your own on your own hardware
+//! when choosing a backend.
+
+use bit_set;
+use fixedbitset::FixedBitSet;
+use roaring::RoaringBitmap;
+
+use DenseBitSet;
+
+/// set sizes, in bits, covered by the bundled benchmarks
+pub const BENCH_SIZES: [usize; 3] = [1 << 10, 1 << 16, 1 << 20];
+
+/// fractions of set bits covered by the bundled benchmarks
+pub const BENCH_DENSITIES: [f64; 4] = [0.001, 0.01, 0.1, 0.5];
+
+/// The operations every compared backend is measured on
+pub trait BenchBackend: Sized {
+    /// a short name for reports
+    const NAME: &'static str;
+
+    /// builds a set of `len` bits holding `indices`, which are ascending
+    fn build(len: usize, indices: &[usize]) -> Self;
+
+    /// tests membership of `i`
+    fn contains(&self, i: usize) -> bool;
+
+    /// counts the set bits
+    fn count(&self) -> usize;
+
+    /// counts the bits set in both sets
+    fn intersection_count(&self, other: &Self) -> usize;
+
+    /// builds the union of both sets
+    fn union(&self, other: &Self) -> Self;
+
+    /// visits every set bit in order, returning the sum of the indices
+    fn sum_ones(&self) -> usize;
+}
+
+/// Two pseudo-random sets of a given size and density, plus membership
+/// probes. Workloads are reproducible from their seed.
+#[derive(Clone, Debug)]
+pub struct BenchWorkload {
+    /// number of bits in each set
+    pub len: usize,
+    /// the fraction of set bits each set was drawn with
+    pub density: f64,
+    /// ascending indices of the first set
+    pub a: Vec<usize>,
+    /// ascending indices of the second set
+    pub b: Vec<usize>,
+    /// indices to test membership of, in random order
+    pub probes: Vec<usize>,
+}
+
+impl BenchWorkload {
+    /// Draws a workload: each bit is set with probability `density`, and
+    /// 1024 probes are spread over the whole range
+    pub fn new(len: usize, density: f64, seed: u64) -> BenchWorkload {
+        assert!(len > 0 && (0.0..=1.0).contains(&density), "invalid workload {} / {}", len, density);
+
+        let mut state = seed | 1;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let threshold = (density * u64::MAX as f64) as u64;
+        let mut draw = || (0..len).filter(|_| next() <= threshold).collect::<Vec<_>>();
+        let (a, b) = (draw(), draw());
+        let probes = (0..1024).map(|_| next() as usize % len).collect();
+        BenchWorkload { len, density, a, b, probes }
+    }
+
+    /// Builds both sets with backend `B`
+    pub fn build<B: BenchBackend>(&self) -> (B, B) {
+        (B::build(self.len, &self.a), B::build(self.len, &self.b))
+    }
+
+    /// Runs every operation once with backend `B`, returning the results.
+    /// All backends must agree on a workload.
+    pub fn checksum<B: BenchBackend>(&self) -> [usize; 5] {
+        let (a, b) = self.build::<B>();
+        [
+            self.probes.iter().filter(|&&i| a.contains(i)).count(),
+            a.count(),
+            a.intersection_count(&b),
+            a.union(&b).count(),
+            a.sum_ones(),
+        ]
+    }
+}
+
+impl BenchBackend for DenseBitSet {
+    const NAME: &'static str = "bitsets";
+
+    fn build(len: usize, indices: &[usize]) -> DenseBitSet {
+        DenseBitSet::from_indices(len, indices.iter().cloned())
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.test(i)
+    }
+
+    fn count(&self) -> usize {
+        self.count_ones()
+    }
+
+    fn intersection_count(&self, other: &DenseBitSet) -> usize {
+        DenseBitSet::intersection_count(self, other)
+    }
+
+    fn union(&self, other: &DenseBitSet) -> DenseBitSet {
+        self.or(other)
+    }
+
+    fn sum_ones(&self) -> usize {
+        self.ones().sum()
+    }
+}
+
+impl BenchBackend for FixedBitSet {
+    const NAME: &'static str = "fixedbitset";
+
+    fn build(len: usize, indices: &[usize]) -> FixedBitSet {
+        let mut set = FixedBitSet::with_capacity(len);
+        set.extend(indices.iter().cloned());
+        set
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        FixedBitSet::contains(self, i)
+    }
+
+    fn count(&self) -> usize {
+        self.count_ones(..)
+    }
+
+    fn intersection_count(&self, other: &FixedBitSet) -> usize {
+        FixedBitSet::intersection_count(self, other)
+    }
+
+    fn union(&self, other: &FixedBitSet) -> FixedBitSet {
+        let mut output = self.clone();
+        output.union_with(other);
+        output
+    }
+
+    fn sum_ones(&self) -> usize {
+        self.ones().sum()
+    }
+}
+
+impl BenchBackend for bit_set::BitSet {
+    const NAME: &'static str = "bit-set";
+
+    fn build(len: usize, indices: &[usize]) -> bit_set::BitSet {
+        let mut set = bit_set::BitSet::with_capacity(len);
+        for &i in indices {
+            set.insert(i);
+        }
+        set
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        bit_set::BitSet::contains(self, i)
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn intersection_count(&self, other: &bit_set::BitSet) -> usize {
+        self.intersection(other).count()
+    }
+
+    fn union(&self, other: &bit_set::BitSet) -> bit_set::BitSet {
+        let mut output = self.clone();
+        output.union_with(other);
+        output
+    }
+
+    fn sum_ones(&self) -> usize {
+        self.iter().sum()
+    }
+}
+
+impl BenchBackend for RoaringBitmap {
+    const NAME: &'static str = "roaring";
+
+    fn build(len: usize, indices: &[usize]) -> RoaringBitmap {
+        assert!(len <= 1 << 32, "roaring holds at most 2^32 bits");
+        RoaringBitmap::from_sorted_iter(indices.iter().map(|&i| i as u32)).unwrap()
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        i <= u32::MAX as usize && RoaringBitmap::contains(self, i as u32)
+    }
+
+    fn count(&self) -> usize {
+        self.len() as usize
+    }
+
+    fn intersection_count(&self, other: &RoaringBitmap) -> usize {
+        self.intersection_len(other) as usize
+    }
+
+    fn union(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        self | other
+    }
+
+    fn sum_ones(&self) -> usize {
+        self.iter().map(|i| i as usize).sum()
+    }
+}
+
+// Comparison harness TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn backends_agree() {
+        for &density in &BENCH_DENSITIES {
+            let workload = BenchWorkload::new(5000, density, 42);
+            let expected = workload.checksum::<DenseBitSet>();
+
+            assert_eq!(workload.checksum::<FixedBitSet>(), expected);
+            assert_eq!(workload.checksum::<bit_set::BitSet>(), expected);
+            assert_eq!(workload.checksum::<RoaringBitmap>(), expected);
+        }
+    }
+
+    #[test]
+    fn workloads_are_reproducible() {
+        let (w1, w2) = (BenchWorkload::new(1000, 0.1, 7), BenchWorkload::new(1000, 0.1, 7));
+        assert_eq!((&w1.a, &w1.b, &w1.probes), (&w2.a, &w2.b, &w2.probes));
+        assert!(w1.a.len() > 50 && w1.a.len() < 150);
+    }
+}
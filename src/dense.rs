@@ -0,0 +1,944 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The `DenseBitSet` type: storage, construction and single-bit access
+
+use std::fmt;
+use std::iter::Sum;
+
+use mask::word_and_mask_for;
+use simd;
+use slice;
+use word;
+use { get_low_mask, get_word_offset, BitSet, Word, BITS_PER_WORD };
+
+/// A dense bit set implemented over a `Vec` of words, `usize` by default
+///
+/// Bits past `len()` in the last word are always zero, so word-level
+/// popcounts and comparisons never see stray padding. Sets created with a
+/// capacity are rounded up to whole words; `from_vec_with_len` creates sets
+/// of any length.
+///
+/// The word type `W` may be any `Word`. `DenseBitSet<usize>`, plain
+/// `DenseBitSet`, has the full API: its vectorized kernels, rank and select
+/// indexes and `BitWords` operands all work on `usize` words. Narrower or
+/// wider words suit wasm32, small embedded targets and wide servers; sets
+/// over any `FixedWidthWord` have single-bit access, the set algebra
+/// between sets of the same word type, and lossless conversion to and from
+/// `DenseBitSet`, whose `to_le_bytes()` they share.
+#[derive(Clone)]
+pub struct DenseBitSet<W: Word = usize> {
+    pub(crate) num_bits: usize,
+    pub(crate) bits: Vec<W>,
+}
+
+impl<W: Word> DenseBitSet<W> {
+    /// Creates an empty set of exactly `num_bits` bits stored in words of
+    /// type `W`. Name the word type at the call, as in
+    /// `DenseBitSet::<u8>::new(20)`: the `usize` default does not apply to
+    /// function calls.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::<u8>::new(20);
+    /// bs.set(3);
+    /// bs.set(17);
+    ///
+    /// assert_eq!(bs.as_words(), &[0b1000, 0, 0b10]);
+    /// assert_eq!(bs.to_dense().to_indices(), vec![3, 17]);
+    /// ```
+    pub fn new(num_bits: usize) -> DenseBitSet<W> {
+        DenseBitSet { num_bits, bits: vec![W::ZERO; num_bits.div_ceil(W::BITS)] }
+    }
+
+    /// Reads bytes written by `to_le_bytes()` of a set with any word type,
+    /// keeping exactly `num_bits` bits. Returns `None` if there are too few
+    /// bytes.
+    pub fn from_le_bytes_with_len(bytes: &[u8], num_bits: usize) -> Option<DenseBitSet<W>> {
+        let bytes = bytes.get(..num_bits.div_ceil(8))?;
+        let mut bs = DenseBitSet { num_bits, bits: word::words_from_le_bytes(bytes) };
+        bs.clear_padding();
+        Some(bs)
+    }
+
+    /// Tests whether the ith bit is set
+    /// Returns true if is set, else false
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// 
+    /// let bs = DenseBitSet::with_capacity(64);
+    /// assert!(!bs.test(16));
+    /// ```
+    pub fn test(&self, i: usize) -> bool {
+        let (w, mask) = word_and_mask_for::<W>(i);
+        self.bits[w] & mask != W::ZERO
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// 
+    /// let mut bs = DenseBitSet::with_capacity(64);
+    /// 
+    /// let is_present = bs.test(32);
+    /// assert!(!is_present);
+    /// 
+    /// let first_time_set = bs.set(32);
+    /// assert!(first_time_set);
+    /// 
+    /// let is_present = bs.test(32);
+    /// assert!(is_present);
+    /// ```
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let (w, mask) = word_and_mask_for::<W>(i);
+        let prior = self.bits[w];
+
+        self.bits[w] = prior | mask;
+        prior & mask == W::ZERO
+    }
+
+    /// flips the value of the ith bit
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// 
+    /// let mut bs = DenseBitSet::with_capacity(64);
+    /// 
+    /// assert!(!bs.test(16));
+    /// assert!(!bs.test(24));
+    /// bs.set(46);
+    /// assert!(bs.test(46));
+    /// 
+    /// bs.flip(14);
+    /// bs.flip(24);
+    /// bs.flip(46);
+    /// 
+    /// assert!(bs.test(14));
+    /// assert!(bs.test(24));
+    /// assert!(!bs.test(46));
+    /// 
+    /// ```
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let (w, mask) = word_and_mask_for::<W>(i);
+        self.bits[w] = self.bits[w] ^ mask;
+    }
+
+    /// Removes `i` from the set, mirroring `HashSet::remove`.
+    /// Returns true if `i` was present previously
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_bits(0b100);
+    ///
+    /// assert!(bs.remove(2));
+    /// assert!(!bs.remove(2));
+    /// assert!(!bs.contains(2));
+    /// ```
+    pub fn remove(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let (w, mask) = word_and_mask_for::<W>(i);
+        let prior = self.bits[w];
+
+        self.bits[w] = prior & !mask;
+        prior & mask != W::ZERO
+    }
+
+    /// returns the number of elements in the underlying Vec<W>
+    pub fn words(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// returns the number of bits this set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns the set as little-endian bytes: bit `i` is stored in bit
+    /// `i % 8` of byte `i / 8`. The output holds one byte per 8 bits of
+    /// `len()` and does not depend on the platform word size or byte order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1_0000_0010);
+    /// let bytes = bs.to_le_bytes();
+    ///
+    /// assert_eq!(&bytes[..2], &[0b10, 0b1]);
+    /// assert_eq!(DenseBitSet::from_le_bytes(&bytes), bs);
+    /// ```
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        word::words_to_le_bytes(&self.bits, self.num_bits)
+    }
+
+    /// clears any bits past `len()` in the last word
+    #[inline]
+    pub(crate) fn clear_padding(&mut self) {
+        let used = self.num_bits % W::BITS;
+        if used != 0 {
+            if let Some(last) = self.bits.last_mut() {
+                *last = *last & W::low_mask(used);
+            }
+        }
+    }
+}
+
+impl DenseBitSet {
+    /// Creates a `DenseBitSet` that can contain at least `num_bits` bits.
+    /// This will be rounded to the nearest word size that can accomodate
+    /// `num_bits` bits.
+    /// 
+    /// # Examples
+    /// 
+    /// Basic usage:
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// 
+    /// let mut bs = DenseBitSet::with_capacity(128);
+    /// 
+    /// bs.set(1);
+    /// bs.set(2);
+    /// bs.set(3);
+    /// 
+    /// assert!(bs.test(1));
+    /// assert!(bs.test(2));
+    /// assert!(bs.test(3));
+    /// ```
+    pub fn with_capacity(num_bits: usize) -> DenseBitSet {
+        DenseBitSet::with_capacity_and_state(num_bits, 0)
+    }
+
+
+    /// Creates a `DenseBitSet` that can contain at least `num_bits` bits.
+    /// Each word of the underlying storage is initialized to `initial_state`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// 
+    /// // 11111111111111111111111111111111111111111111111111111111
+    /// let bs1 = DenseBitSet::with_capacity_and_state(64, std::usize::MAX);
+    ///
+    /// // 00000000000000000000000000000000000000000000000000000000
+    /// let bs2 = DenseBitSet::with_capacity_and_state(64, 0);
+    /// ```
+    pub fn with_capacity_and_state(num_bits: usize, initial_state: usize) -> DenseBitSet {
+        let full_words = num_bits / BITS_PER_WORD;
+        let remaining_bits = num_bits % BITS_PER_WORD;
+        let words_to_allocate = if remaining_bits > 0 {
+            full_words + 1
+        } else {
+            full_words
+        };
+
+        DenseBitSet {
+            bits: vec![initial_state; words_to_allocate],
+            num_bits: words_to_allocate * BITS_PER_WORD,
+        }
+    }
+
+    /// creates a single-word sized DenseBitSet initialized to `bit_pattern`.
+    ///
+    /// # Examples
+    /// 
+    /// ```
+    /// use bitsets::DenseBitSet;
+    /// let bs = DenseBitSet::from_bits(0b0101010101010101);
+    /// 
+    /// assert!(bs.test(0));
+    /// assert!(!bs.test(1));
+    /// ```
+    pub fn from_bits(bit_pattern: usize) -> DenseBitSet {
+        DenseBitSet::with_capacity_and_state(BITS_PER_WORD, bit_pattern)
+    }
+
+    /// Creates a DenseBitSet using the given Vec as the
+    /// underlying bits.
+    /// The words are platform dependent; use `from_le_bytes` for data
+    /// written on another machine.
+    pub fn from_vec(v: Vec<usize>) -> DenseBitSet {
+        DenseBitSet {
+            num_bits: BITS_PER_WORD * v.len(),
+            bits: v,
+        }
+    }
+
+    /// Creates a `DenseBitSet` of exactly `num_bits` bits using the given
+    /// Vec as the underlying bits.
+    /// Returns `None` unless `v` holds exactly the words needed for
+    /// `num_bits` bits and every bit past `num_bits` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_vec_with_len(vec![0b101], 3).unwrap();
+    /// assert_eq!(bs.len(), 3);
+    ///
+    /// assert!(DenseBitSet::from_vec_with_len(vec![0b1101], 3).is_none());
+    /// assert!(DenseBitSet::from_vec_with_len(vec![0, 0], 3).is_none());
+    /// ```
+    pub fn from_vec_with_len(v: Vec<usize>, num_bits: usize) -> Option<DenseBitSet> {
+        let bs = DenseBitSet { num_bits, bits: v };
+        if bs.bits.len() != num_bits.div_ceil(BITS_PER_WORD) {
+            return None;
+        }
+
+        match bs.bits.last() {
+            Some(last) if last & !bs.last_word_mask() != 0 => None,
+            _ => Some(bs),
+        }
+    }
+
+    /// Like `from_vec_with_len`, but clears any bits past `num_bits`
+    /// instead of rejecting them.
+    /// Panics unless `v` holds exactly the words needed for `num_bits` bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_vec_masked(vec![0b1101], 3);
+    ///
+    /// assert_eq!(bs.to_indices(), vec![0, 2]);
+    /// ```
+    pub fn from_vec_masked(v: Vec<usize>, num_bits: usize) -> DenseBitSet {
+        assert_eq!(v.len(), num_bits.div_ceil(BITS_PER_WORD));
+
+        let mut bs = DenseBitSet { num_bits, bits: v };
+        bs.clear_padding();
+        bs
+    }
+
+    /// Creates a `DenseBitSet` that can contain at least `num_bits` bits
+    /// with every index yielded by `indices` set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(128, vec![2, 64, 100]);
+    ///
+    /// assert_eq!(bs.len(), 128);
+    /// assert!(bs.test(64));
+    /// assert!(!bs.test(65));
+    /// ```
+    pub fn from_indices<I: IntoIterator<Item = usize>>(num_bits: usize, indices: I) -> DenseBitSet {
+        let mut bs = DenseBitSet::with_capacity(num_bits);
+        for i in indices {
+            bs.set(i);
+        }
+        bs
+    }
+
+    /// Sets the ith bit, first growing the set with zeroed words if `i` is
+    /// past `len()`, so ids can be recorded without knowing the largest in
+    /// advance. The set grows by whole words, so `len()` is rounded up.
+    /// Returns true if bit was not set previously
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut seen = DenseBitSet::with_capacity(0);
+    ///
+    /// assert!(seen.set_growing(100));
+    /// assert!(!seen.set_growing(100));
+    /// assert_eq!(seen.len(), 128);
+    /// ```
+    pub fn set_growing(&mut self, i: usize) -> bool {
+        self.grow_to(i + 1);
+        self.set(i)
+    }
+
+    /// Adds `i` to the set, mirroring `HashSet::insert`.
+    /// Returns true if `i` was not present previously
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(64);
+    ///
+    /// assert!(bs.insert(7));
+    /// assert!(!bs.insert(7));
+    /// assert!(bs.contains(7));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, i: usize) -> bool {
+        self.set(i)
+    }
+
+    /// Tests whether `i` is in the set, mirroring `HashSet::contains`
+    #[inline]
+    pub fn contains(&self, i: usize) -> bool {
+        self.test(i)
+    }
+
+    /// Clears every bit, keeping the allocation
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_bits(0b1011);
+    /// bs.clear_all();
+    ///
+    /// assert_eq!(bs.count_ones(), 0);
+    /// assert_eq!(bs.len(), 64);
+    /// ```
+    pub fn clear_all(&mut self) {
+        for word in &mut self.bits {
+            *word = 0;
+        }
+    }
+
+    /// Sets every bit, keeping the allocation
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(128);
+    /// bs.set_all();
+    ///
+    /// assert_eq!(bs.count_ones(), 128);
+    /// ```
+    pub fn set_all(&mut self) {
+        for word in &mut self.bits {
+            *word = !0;
+        }
+        self.clear_padding();
+    }
+
+    /// returns the number of set bits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1011);
+    /// assert_eq!(bs.count_ones(), 3);
+    /// ```
+    pub fn count_ones(&self) -> usize {
+        simd::popcount(&self.bits)
+    }
+
+    /// Returns the sum of `weights[i]` over every set bit `i`, i.e. the dot
+    /// product of the set as a 0/1 vector with `weights`.
+    ///
+    /// Panics if a set bit has no weight.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1010);
+    ///
+    /// assert_eq!(bs.weighted_count(&[1u32, 2, 4, 8]), 10);
+    /// assert_eq!(bs.weighted_count(&[0.5, 0.25, 0.0, 1.5]), 1.75);
+    /// ```
+    pub fn weighted_count<T: Copy + Sum<T>>(&self, weights: &[T]) -> T {
+        self.ones().map(|i| weights[i]).sum()
+    }
+
+    /// Returns the indices of all set bits, in ascending order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(128);
+    /// bs.set(100);
+    /// bs.set(3);
+    ///
+    /// assert_eq!(bs.to_indices(), vec![3, 100]);
+    /// ```
+    pub fn to_indices(&self) -> Vec<usize> {
+        self.ones().collect()
+    }
+
+    /// Tests a batch of indices, writing the answer for `queries[j]` to bit
+    /// `j` of `output`. Bits of `output` past `queries.len()` are cleared,
+    /// so the same output set can be reused across batches.
+    ///
+    /// Panics if `output` cannot hold `queries.len()` bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(128, vec![5, 70]);
+    /// let mut hits = DenseBitSet::with_capacity(3);
+    ///
+    /// bs.test_bits_into(&[70, 6, 5], &mut hits);
+    ///
+    /// assert_eq!(hits.to_indices(), vec![0, 2]);
+    /// ```
+    pub fn test_bits_into(&self, queries: &[usize], output: &mut DenseBitSet) {
+        assert!(queries.len() <= output.len());
+
+        let mut chunks = queries.chunks(BITS_PER_WORD);
+        for out in &mut output.bits {
+            *out = match chunks.next() {
+                Some(chunk) => chunk.iter().enumerate().fold(0, |word, (j, &i)| {
+                    word | ((self.test(i) as usize) << j)
+                }),
+                None => 0,
+            };
+        }
+    }
+
+    /// Returns a minimal byte representation of the set bits, suitable for
+    /// hashing and content-addressed deduplication.
+    ///
+    /// Words are written in little-endian order, bits past `len()` are
+    /// masked off and trailing zero bytes are dropped, so two sets containing
+    /// the same bits always produce identical bytes regardless of their
+    /// capacity or the platform word size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut small = DenseBitSet::with_capacity(64);
+    /// let mut large = DenseBitSet::with_capacity(1024);
+    /// small.set(3);
+    /// large.set(3);
+    ///
+    /// assert_eq!(small.canonical_bytes(), vec![0b1000]);
+    /// assert_eq!(small.canonical_bytes(), large.canonical_bytes());
+    /// assert!(DenseBitSet::with_capacity(256).canonical_bytes().is_empty());
+    /// ```
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_le_bytes();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes
+    }
+
+    /// Returns the set as big-endian bytes, i.e. the reverse of
+    /// `to_le_bytes()`: the set is written as one big-endian integer whose
+    /// least significant bit is bit 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1_0000_0010);
+    /// let bytes = bs.to_be_bytes();
+    ///
+    /// assert_eq!(&bytes[bytes.len() - 2..], &[0b1, 0b10]);
+    /// assert_eq!(DenseBitSet::from_be_bytes(&bytes), bs);
+    /// ```
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_le_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Creates a `DenseBitSet` from bytes written by `to_le_bytes()` on any
+    /// platform. The set can contain at least `8 * bytes.len()` bits.
+    pub fn from_le_bytes(bytes: &[u8]) -> DenseBitSet {
+        DenseBitSet::from_vec(word::words_from_le_bytes(bytes))
+    }
+
+    /// Creates a `DenseBitSet` from bytes written by `to_be_bytes()` on any
+    /// platform. The set can contain at least `8 * bytes.len()` bits.
+    pub fn from_be_bytes(bytes: &[u8]) -> DenseBitSet {
+        let mut bytes = bytes.to_vec();
+        bytes.reverse();
+        DenseBitSet::from_le_bytes(&bytes)
+    }
+
+    /// mask of the bits in the last word that lie within `len()`
+    #[inline]
+    pub(crate) fn last_word_mask(&self) -> usize {
+        get_low_mask(self.num_bits - get_word_offset(self.num_bits.saturating_sub(1)) * BITS_PER_WORD)
+    }
+
+    /// grows the set with zeroed words until it can hold `num_bits` bits
+    pub(crate) fn grow_to(&mut self, num_bits: usize) {
+        if num_bits > self.num_bits {
+            let words = num_bits.div_ceil(BITS_PER_WORD);
+            self.bits.resize(words, 0);
+            self.num_bits = words * BITS_PER_WORD;
+        }
+    }
+
+    /// Reads `len <= BITS_PER_WORD` bits starting at any bit offset,
+    /// returned in the low bits of a word
+    pub(crate) fn read_bits(&self, start: usize, len: usize) -> usize {
+        assert!(len <= BITS_PER_WORD && start + len <= self.num_bits);
+        slice::read_bits(&self.bits, start, len)
+    }
+
+    /// Writes the low `len <= BITS_PER_WORD` bits of `value` starting at
+    /// any bit offset, leaving the surrounding bits untouched
+    pub(crate) fn write_bits(&mut self, start: usize, len: usize, value: usize) {
+        assert!(len <= BITS_PER_WORD && start + len <= self.num_bits);
+        slice::write_bits(&mut self.bits, start, len, value)
+    }
+}
+
+impl PartialEq for DenseBitSet {
+    fn eq(&self, other: &DenseBitSet) -> bool {
+        self.num_bits == other.num_bits && simd::eq(&self.bits, &other.bits)
+    }
+}
+
+impl Eq for DenseBitSet {}
+
+/// `{:?}` summarizes the set as its length, popcount and first few set
+/// indices, so huge sets stay readable in logs. `{:#?}` dumps every bit.
+impl<W: Word> fmt::Debug for DenseBitSet<W> where DenseBitSet<W>: BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        /// set indices listed before the summary is cut short
+        const MAX_INDICES: usize = 16;
+
+        if f.alternate() {
+            write!(f, "DenseBitset: ")?;
+            for i in 0..self.len() {
+                write!(f, "{}", if self.test(i) { 1 } else { 0 })?;
+            }
+            return Ok(());
+        }
+
+        write!(f, "DenseBitSet {{ len: {}, ones: {}, first: [", self.len(), BitSet::count_ones(self))?;
+        let mut next = self.next_set_bit(0);
+        let mut listed = 0;
+        while let Some(i) = next {
+            if listed == MAX_INDICES {
+                write!(f, ", ..")?;
+                break;
+            }
+            if listed > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", i)?;
+            listed += 1;
+            next = self.next_set_bit(i + 1);
+        }
+        write!(f, "] }}")
+    }
+}
+
+// DenseBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use BYTES_PER_WORD;
+
+    #[test]
+    fn can_create() {
+        let bs = DenseBitSet::with_capacity(128);
+
+        assert!(bs.len() == 128);
+    }
+
+
+    #[test]
+    fn can_clear_and_set_all() {
+        let mut bs = DenseBitSet::from_indices(200, vec![3, 150]);
+
+        bs.set_all();
+        assert_eq!(bs.count_ones(), bs.len());
+        bs.clear_all();
+        assert_eq!(bs, DenseBitSet::with_capacity(200));
+        bs.set(7);
+        assert_eq!(bs.to_indices(), vec![7]);
+    }
+
+
+    #[test]
+    fn can_test_bits_into_reused_output() {
+        let bs = DenseBitSet::from_indices(256, (0..256).filter(|i| i % 3 == 0));
+        let mut output = DenseBitSet::with_capacity(256);
+        output.set_all();
+
+        let queries: Vec<usize> = (0..100).map(|j| j * 2).collect();
+        bs.test_bits_into(&queries, &mut output);
+
+        assert_eq!(output.to_indices(), (0..100).filter(|j| (j * 2) % 3 == 0).collect::<Vec<_>>());
+    }
+
+
+    #[test]
+    fn can_weight_set_bits() {
+        let bs = DenseBitSet::from_indices(200, vec![0, 64, 199]);
+        let weights: Vec<u64> = (0..200).collect();
+
+        assert_eq!(bs.weighted_count(&weights), 263);
+        assert_eq!(DenseBitSet::with_capacity(200).weighted_count(&weights), 0);
+    }
+
+
+    #[test]
+    fn keeps_padding_clear_for_odd_lengths() {
+        let mut bs = DenseBitSet::from_vec_masked(vec![!0, !0], BITS_PER_WORD + 5);
+        assert_eq!(bs.count_ones(), BITS_PER_WORD + 5);
+
+        bs.inplace_not();
+        assert_eq!(bs.count_ones(), 0);
+        bs.set_all();
+        assert_eq!(bs.count_ones(), BITS_PER_WORD + 5);
+        assert_eq!(bs.to_le_bytes().len(), BYTES_PER_WORD + 1);
+
+        let other = DenseBitSet::with_capacity_and_state(2 * BITS_PER_WORD, !0);
+        bs.clear_all();
+        bs.inplace_xor(&other);
+        assert_eq!(bs.ones().last(), Some(BITS_PER_WORD + 4));
+    }
+
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn can_set_and_test_bits() {
+        let mut bs = DenseBitSet::with_capacity(128);
+        assert_eq!(bs.test(0), false);
+        assert_eq!(bs.test(10), false);
+        assert_eq!(bs.test(30), false);
+        bs.set(0);
+        bs.set(10);
+        bs.set(30);
+        assert_eq!(bs.test(0), true);
+        assert_eq!(bs.test(10), true);
+        assert_eq!(bs.test(30), true);
+    }
+
+
+    #[test]
+    fn can_set_past_capacity_when_growing() {
+        let mut bs = DenseBitSet::with_capacity(64);
+        bs.set(3);
+
+        assert!(bs.set_growing(1000));
+        assert!(bs.set_growing(64));
+        assert!(!bs.set_growing(3));
+        assert_eq!(bs.len(), 1024);
+        assert_eq!(bs.to_indices(), vec![3, 64, 1000]);
+    }
+
+
+    #[test]
+    fn can_insert_and_remove() {
+        let mut bs = DenseBitSet::with_capacity(128);
+
+        assert!(bs.insert(70));
+        assert!(!bs.insert(70));
+        assert!(bs.contains(70));
+
+        assert!(bs.remove(70));
+        assert!(!bs.remove(70));
+        assert!(!bs.contains(70));
+    }
+
+
+    #[test]
+    #[should_panic(expected = "index 3 is out of bounds for a set of 3 bits")]
+    fn rejects_remove_past_len() {
+        DenseBitSet::from_vec_masked(vec![0], 3).remove(3);
+    }
+
+
+    #[test]
+    fn can_report_num_words() {
+        // initialize with non-aligned value
+        let bs = DenseBitSet::with_capacity(100);
+        assert_eq!(128, bs.len());
+        assert_eq!(2, bs.words());
+    }
+
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn can_clear_bits() {
+        let mut bs = DenseBitSet::with_capacity(64);
+
+        bs.set(45);
+        assert_eq!(bs.test(45), true);
+
+        bs.flip(45);
+        assert_eq!(bs.test(45), false);
+    }
+
+
+    #[test]
+    fn can_initialize_from_literal() {
+
+        // 1010101110101110101010101010000000000000000000000000000000000000
+        let bs = DenseBitSet::from_bits(0b0101010101010111010111010101);
+        assert!(bs.test(0));
+        assert!(!bs.test(1));
+        assert!(bs.test(2));
+        assert!(!bs.test(3));
+        assert!(bs.test(4));
+    }
+
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn can_compare() {
+        let A = DenseBitSet::from_bits(0b111000111);
+        let B = DenseBitSet::from_bits(0b111000111);
+        let C = DenseBitSet::from_bits(0b110111111);
+
+        assert!(A == B);
+        assert!(B == A);
+
+        assert!(A != C);
+        assert!(C != A);
+    }
+
+
+    #[test]
+    fn debug_summarizes_large_sets() {
+        let mut bs = DenseBitSet::with_capacity(10_000_000);
+        for i in (0..40).map(|i| i * 1000) {
+            bs.set(i);
+        }
+
+        let summary = format!("{:?}", bs);
+        assert_eq!(summary, "DenseBitSet { len: 10000000, ones: 40, first: [0, 1000, 2000, 3000, 4000, 5000, \
+            6000, 7000, 8000, 9000, 10000, 11000, 12000, 13000, 14000, 15000, ..] }");
+
+        let small = DenseBitSet::from_bits(0b1010);
+        assert_eq!(format!("{:?}", small), "DenseBitSet { len: 64, ones: 2, first: [1, 3] }");
+        assert_eq!(format!("{:#?}", small), format!("DenseBitset: 0101{}", "0".repeat(60)));
+    }
+
+
+    #[test]
+    fn canonical_bytes_ignore_capacity() {
+        let mut a = DenseBitSet::with_capacity(64);
+        let mut b = DenseBitSet::with_capacity(512);
+        a.set(9);
+        a.set(40);
+        b.set(9);
+        b.set(40);
+
+        assert!(a != b);
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        assert_eq!(a.canonical_bytes(), vec![0, 0b10, 0, 0, 0, 0b1]);
+
+        b.set(300);
+        assert!(a.canonical_bytes() != b.canonical_bytes());
+    }
+
+
+    #[test]
+    fn can_round_trip_bytes() {
+        let bs = DenseBitSet::from_indices(200, vec![0, 9, 63, 64, 130, 199]);
+
+        let le = bs.to_le_bytes();
+        assert_eq!(le.len(), bs.words() * BYTES_PER_WORD);
+        assert_eq!(le[1], 0b10);
+        assert_eq!(DenseBitSet::from_le_bytes(&le), bs);
+
+        let be = bs.to_be_bytes();
+        assert_eq!(be[be.len() - 2], 0b10);
+        assert_eq!(DenseBitSet::from_be_bytes(&be), bs);
+    }
+
+    #[test]
+    fn can_load_bytes_of_odd_length() {
+        // as written by a set of 24 bits
+        let bs = DenseBitSet::from_le_bytes(&[0, 0, 0b1000_0000]);
+        assert_eq!(bs.to_indices(), vec![23]);
+
+        let bs = DenseBitSet::from_be_bytes(&[0b1000_0000, 0, 0]);
+        assert_eq!(bs.to_indices(), vec![23]);
+    }
+
+    #[test]
+    fn can_read_and_write_unaligned_bits() {
+        let mut bs = DenseBitSet::with_capacity(256);
+
+        bs.write_bits(60, BITS_PER_WORD, !0);
+        assert_eq!(bs.count_ones(), BITS_PER_WORD);
+        assert!(!bs.test(59));
+        assert!(bs.test(60));
+        assert!(!bs.test(60 + BITS_PER_WORD));
+
+        bs.write_bits(62, 4, 0b0101);
+        assert_eq!(bs.read_bits(60, 8), 0b1101_0111);
+        assert_eq!(bs.read_bits(58, 4), 0b1100);
+        assert_eq!(bs.read_bits(0, 0), 0);
+    }
+
+    #[test]
+    fn can_list_set_indices() {
+        let mut bs = DenseBitSet::with_capacity(256);
+        assert!(bs.to_indices().is_empty());
+
+        for i in &[0, 63, 64, 127, 200, 255] {
+            bs.set(*i);
+        }
+        assert_eq!(bs.to_indices(), vec![0, 63, 64, 127, 200, 255]);
+        assert_eq!(bs.ones().count(), 6);
+    }
+
+
+    #[test]
+    fn can_create_from_indices() {
+        let bs = DenseBitSet::from_indices(100, vec![0, 99, 42]);
+
+        assert_eq!(bs.words(), 2);
+        assert_eq!(bs, vec![0, 42, 99].into_iter().collect());
+    }
+}
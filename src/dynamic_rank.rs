@@ -0,0 +1,219 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `DynamicRankBitSet`, which keeps rank and select fast under updates
+
+use rank::select_in_word;
+use { get_bit_offset, get_bitmask, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// A `DenseBitSet` with a Fenwick tree over the popcount of each word.
+///
+/// Every write adjusts O(log n) tree nodes, and `rank` and `select` walk
+/// O(log n) nodes, so updates and queries can interleave freely. Use
+/// `RankSelectBitSet` instead when the set rarely changes.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, DynamicRankBitSet };
+///
+/// let mut bs = DynamicRankBitSet::new(DenseBitSet::with_capacity(1024));
+/// bs.set(10);
+/// bs.set(500);
+/// assert_eq!(bs.rank(500), 1);
+///
+/// bs.set(20);
+/// assert_eq!(bs.rank(500), 2);
+/// assert_eq!(bs.select(1), Some(20));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DynamicRankBitSet {
+    bits: DenseBitSet,
+    // 1-based Fenwick tree: tree[n] sums the popcounts of words
+    // (n - lowbit(n), n]
+    tree: Vec<usize>,
+}
+
+impl DynamicRankBitSet {
+    /// Builds the tree over `bits` in linear time
+    pub fn new(bits: DenseBitSet) -> DynamicRankBitSet {
+        let mut tree = vec![0; bits.words() + 1];
+        for (w, word) in bits.bits.iter().enumerate() {
+            tree[w + 1] += word.count_ones() as usize;
+            let parent = (w + 1) + ((w + 1) & (w + 1).wrapping_neg());
+            if parent < tree.len() {
+                tree[parent] += tree[w + 1];
+            }
+        }
+        DynamicRankBitSet { bits, tree }
+    }
+
+    /// Borrows the underlying set
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the underlying set, dropping the tree
+    pub fn into_inner(self) -> DenseBitSet {
+        self.bits
+    }
+
+    /// returns the number of bits in the set
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words_rank(self.bits.words())
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        self.bits.test(i)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        let changed = self.bits.set(i);
+        if changed {
+            self.add(get_word_offset(i), 1);
+        }
+        changed
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        let changed = self.bits.remove(i);
+        if changed {
+            self.add(get_word_offset(i), usize::MAX);
+        }
+        changed
+    }
+
+    /// flips the value of the ith bit
+    pub fn flip(&mut self, i: usize) {
+        self.bits.flip(i);
+        let delta = if self.bits.bits[get_word_offset(i)] & get_bitmask(i) != 0 { 1 } else { usize::MAX };
+        self.add(get_word_offset(i), delta);
+    }
+
+    /// Returns the number of set bits strictly below `i`, as
+    /// `DenseBitSet::rank` does. Panics if `i > len()`.
+    pub fn rank(&self, i: usize) -> usize {
+        assert!(i <= self.len());
+
+        let w = get_word_offset(i);
+        let partial = match get_bit_offset(i) {
+            0 => 0,
+            offset => (self.bits.bits[w] & get_low_mask(offset)).count_ones() as usize,
+        };
+        self.words_rank(w) + partial
+    }
+
+    /// Returns the index of the `k`th (0-based) set bit, as
+    /// `DenseBitSet::select` does
+    pub fn select(&self, k: usize) -> Option<usize> {
+        if k >= self.count_ones() {
+            return None;
+        }
+
+        // descend the tree to the last prefix of whole words holding at most k ones
+        let mut w = 0;
+        let mut remaining = k;
+        let mut step = (self.tree.len() - 1).checked_next_power_of_two().unwrap_or(0);
+        while step > 0 {
+            if w + step < self.tree.len() && self.tree[w + step] <= remaining {
+                w += step;
+                remaining -= self.tree[w];
+            }
+            step >>= 1;
+        }
+        Some(w * BITS_PER_WORD + select_in_word(self.bits.bits[w], remaining))
+    }
+
+    /// number of set bits in words `0..w`
+    fn words_rank(&self, mut w: usize) -> usize {
+        let mut sum = 0;
+        while w > 0 {
+            sum += self.tree[w];
+            w &= w - 1;
+        }
+        sum
+    }
+
+    /// adds `delta` (wrapping, so `usize::MAX` subtracts one) to the count of word `w`
+    fn add(&mut self, w: usize, delta: usize) {
+        let mut n = w + 1;
+        while n < self.tree.len() {
+            self.tree[n] = self.tree[n].wrapping_add(delta);
+            n += n & n.wrapping_neg();
+        }
+    }
+}
+
+impl From<DenseBitSet> for DynamicRankBitSet {
+    fn from(bits: DenseBitSet) -> DynamicRankBitSet {
+        DynamicRankBitSet::new(bits)
+    }
+}
+
+// DynamicRankBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn matches_linear_rank_and_select_under_updates() {
+        let len = 3000;
+        let mut plain = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 7 == 0));
+        let mut dynamic = DynamicRankBitSet::new(plain.clone());
+
+        for step in 0..200 {
+            let i = (step * 389) % len;
+            match step % 3 {
+                0 => assert_eq!(dynamic.set(i), plain.set(i)),
+                1 => assert_eq!(dynamic.remove(i), plain.remove(i)),
+                _ => { dynamic.flip(i); plain.flip(i); }
+            }
+
+            let probe = (step * 131) % (len + 1);
+            assert_eq!(dynamic.rank(probe), plain.rank(probe));
+            assert_eq!(dynamic.select(step), plain.select(step));
+        }
+
+        assert_eq!(dynamic.count_ones(), plain.count_ones());
+        assert_eq!(dynamic, DynamicRankBitSet::new(plain));
+    }
+
+    #[test]
+    fn can_index_empty_set() {
+        let dynamic = DynamicRankBitSet::new(DenseBitSet::with_capacity(0));
+
+        assert_eq!(dynamic.count_ones(), 0);
+        assert_eq!(dynamic.select(0), None);
+    }
+}
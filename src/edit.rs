@@ -0,0 +1,282 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Positional edits of a `DenseBitSet` treated as a bit vector, shifting
+//! the bits after the edit point like `Vec::insert` and `Vec::remove`
+
+use { get_bit_offset, get_low_mask, get_word_offset, BitWords, DenseBitSet, BITS_PER_WORD };
+
+impl DenseBitSet {
+    /// Inserts a bit with `value` at position `i`, shifting every bit at or
+    /// above `i` up by one. The length grows by one. Panics if `i > len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_vec_masked(vec![0b1011], 4);
+    /// bs.insert_bit(1, false);
+    ///
+    /// assert_eq!(bs.len(), 5);
+    /// assert_eq!(bs.to_indices(), vec![0, 2, 4]);
+    ///
+    /// assert!(!bs.remove_bit(1));
+    /// assert_eq!(bs.to_indices(), vec![0, 1, 3]);
+    /// ```
+    pub fn insert_bit(&mut self, i: usize, value: bool) {
+        assert!(i <= self.num_bits);
+        self.push(false);
+
+        let idx = get_word_offset(i);
+        for w in (idx + 1..self.bits.len()).rev() {
+            self.bits[w] = (self.bits[w] << 1) | (self.bits[w - 1] >> (BITS_PER_WORD - 1));
+        }
+
+        let low = get_low_mask(get_bit_offset(i));
+        let word = self.bits[idx];
+        self.bits[idx] = (word & low) | ((word & !low) << 1) | ((value as usize) << get_bit_offset(i));
+        self.clear_padding();
+    }
+
+    /// Removes the bit at position `i` and returns it, shifting every bit
+    /// above `i` down by one. The length shrinks by one. Panics if
+    /// `i >= len()`.
+    pub fn remove_bit(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits);
+        let value = self.test(i);
+
+        let idx = get_word_offset(i);
+        let low = get_low_mask(get_bit_offset(i));
+        let word = self.bits[idx];
+        self.bits[idx] = (word & low) | ((word >> 1) & !low);
+        for w in idx + 1..self.bits.len() {
+            self.bits[w - 1] |= (self.bits[w] & 1) << (BITS_PER_WORD - 1);
+            self.bits[w] >>= 1;
+        }
+
+        let num_bits = self.num_bits - 1;
+        self.truncate(num_bits);
+        value
+    }
+
+    /// Appends the bits of `other` after the last bit of `self`, so bit `i`
+    /// of `other` becomes bit `len() + i`. `len()` need not be a multiple
+    /// of the word size; `other`'s words are shifted into place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut head = DenseBitSet::from_vec_masked(vec![0b101], 3);
+    /// let tail = DenseBitSet::from_vec_masked(vec![0b11], 2);
+    /// head.append(&tail);
+    ///
+    /// assert_eq!(head.len(), 5);
+    /// assert_eq!(head.to_indices(), vec![0, 2, 3, 4]);
+    /// assert_eq!(DenseBitSet::concat(&tail, &tail).to_indices(), vec![0, 1, 2, 3]);
+    /// ```
+    pub fn append<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let start = self.num_bits;
+        self.resize(start + other.bit_len(), false);
+
+        let base = get_word_offset(start);
+        let offset = get_bit_offset(start);
+        for (j, &word) in other.as_words().iter().enumerate() {
+            if offset == 0 {
+                self.bits[base + j] = word;
+                continue;
+            }
+            self.bits[base + j] |= word << offset;
+            if let Some(next) = self.bits.get_mut(base + j + 1) {
+                *next |= word >> (BITS_PER_WORD - offset);
+            }
+        }
+    }
+
+    /// Splits the set in two at `at`: `self` keeps bits `0..at` and the
+    /// returned set holds the rest, renumbered from 0, like `Vec::split_off`.
+    /// Panics if `at > len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut head = DenseBitSet::from_indices(128, vec![1, 70, 100]);
+    /// let tail = head.split_off(70);
+    ///
+    /// assert_eq!((head.len(), tail.len()), (70, 58));
+    /// assert_eq!(head.to_indices(), vec![1]);
+    /// assert_eq!(tail.to_indices(), vec![0, 30]);
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> DenseBitSet {
+        assert!(at <= self.num_bits);
+        let tail = self.extract(at..self.num_bits);
+        self.truncate(at);
+        tail
+    }
+
+    /// Returns copies of bits `0..at` and `at..len()`, the latter renumbered
+    /// from 0, leaving `self` untouched. Panics if `at > len()`.
+    pub fn split_at(&self, at: usize) -> (DenseBitSet, DenseBitSet) {
+        assert!(at <= self.num_bits);
+        (self.extract(0..at), self.extract(at..self.num_bits))
+    }
+
+    /// Returns a new set holding the bits of `a` followed by those of `b`
+    pub fn concat<A: BitWords + ?Sized, B: BitWords + ?Sized>(a: &A, b: &B) -> DenseBitSet {
+        let mut output = DenseBitSet::from_vec_masked(a.as_words().to_vec(), a.bit_len());
+        output.reserve(b.bit_len());
+        output.append(b);
+        output
+    }
+
+    /// Returns a new set holding `n` back-to-back copies of `self`, like
+    /// `slice::repeat`. Copies are stitched in by doubling, so the work is
+    /// proportional to the words of the output rather than to `n` times
+    /// the bits of the pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// // every third bit, tiled over 3000 bits
+    /// let pattern = DenseBitSet::from_vec_masked(vec![0b001], 3);
+    /// let mask = pattern.repeat(1000);
+    ///
+    /// assert_eq!(mask.len(), 3000);
+    /// assert_eq!(mask.count_ones(), 1000);
+    /// assert!(mask.test(2997) && !mask.test(2998));
+    /// ```
+    pub fn repeat(&self, n: usize) -> DenseBitSet {
+        let mut output = DenseBitSet::with_capacity(0);
+        output.reserve(self.num_bits * n);
+
+        let mut block = self.clone();
+        let mut remaining = n;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                output.append(&block);
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                let copy = block.clone();
+                block.append(&copy);
+            }
+        }
+        output
+    }
+}
+
+// Edit TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn to_bools(bs: &DenseBitSet) -> Vec<bool> {
+        (0..bs.len()).map(|i| bs.test(i)).collect()
+    }
+
+    fn from_bools(bools: &[bool]) -> DenseBitSet {
+        let mut bs = DenseBitSet::with_capacity(0);
+        bools.iter().for_each(|&b| bs.push(b));
+        bs
+    }
+
+    #[test]
+    fn insert_and_remove_match_vec() {
+        let mut naive: Vec<bool> = (0..190).map(|i| i % 5 == 0 || i % 11 == 3).collect();
+        let mut bs = from_bools(&naive);
+
+        for &(i, value) in &[(0, true), (63, true), (64, false), (100, true), (193, true), (128, false)] {
+            naive.insert(i, value);
+            bs.insert_bit(i, value);
+            assert_eq!(to_bools(&bs), naive);
+        }
+        assert_eq!(bs.len(), 196);
+
+        for &i in &[195, 0, 63, 64, 127, 128, 50] {
+            assert_eq!(bs.remove_bit(i), naive.remove(i));
+            assert_eq!(to_bools(&bs), naive);
+        }
+        assert_eq!(bs.count_ones(), naive.iter().filter(|&&b| b).count());
+    }
+
+    #[test]
+    fn append_stitches_unaligned_lengths() {
+        for &(a_len, b_len) in &[(0, 70), (64, 64), (3, 130), (100, 29), (127, 1), (65, 0)] {
+            let a: Vec<bool> = (0..a_len).map(|i| i % 3 == 0).collect();
+            let b: Vec<bool> = (0..b_len).map(|i| i % 4 != 1).collect();
+
+            let mut joined = from_bools(&a);
+            joined.append(&from_bools(&b));
+
+            let expected: Vec<bool> = a.iter().chain(&b).cloned().collect();
+            assert_eq!(to_bools(&joined), expected);
+            assert_eq!(joined, DenseBitSet::concat(&from_bools(&a), &from_bools(&b)));
+            assert_eq!(joined.count_ones(), expected.iter().filter(|&&v| v).count());
+        }
+    }
+
+    #[test]
+    fn split_then_append_round_trips() {
+        let bools: Vec<bool> = (0..300).map(|i| i % 7 == 2 || i % 5 == 0).collect();
+        let whole = from_bools(&bools);
+
+        for &at in &[0, 1, 63, 64, 65, 130, 299, 300] {
+            let (head, tail) = whole.split_at(at);
+            assert_eq!(to_bools(&head), &bools[..at]);
+            assert_eq!(to_bools(&tail), &bools[at..]);
+
+            let mut split = whole.clone();
+            assert_eq!(split.split_off(at), tail);
+            assert_eq!(split, head);
+
+            split.append(&tail);
+            assert_eq!(split, whole);
+        }
+    }
+
+    #[test]
+    fn repeat_matches_naive_tiling() {
+        for &(len, n) in &[(12, 1000), (64, 5), (1, 130), (100, 3), (7, 0), (0, 9)] {
+            let pattern: Vec<bool> = (0..len).map(|i| i % 5 == 1 || i == len - 1).collect();
+            let tiled = from_bools(&pattern).repeat(n);
+
+            let expected: Vec<bool> = (0..n).flat_map(|_| pattern.iter().cloned()).collect();
+            assert_eq!(to_bools(&tiled), expected);
+            assert_eq!(tiled.count_ones(), expected.iter().filter(|&&v| v).count());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_rejects_out_of_bounds() {
+        let mut bs = DenseBitSet::from_vec_masked(vec![0], 10);
+        bs.remove_bit(10);
+    }
+}
@@ -0,0 +1,270 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `EliasFanoSet`, a compressed representation for sparse sorted sets
+
+use rank::select_in_word;
+use { get_bit_offset, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// number of ones between two entries of the select directory
+const SELECT_SAMPLE: usize = 64;
+
+/// A sparse set of indices below a fixed universe, stored with Elias–Fano
+/// coding in about `2 + log2(universe / len)` bits per element.
+///
+/// Each index is split into low bits, packed verbatim, and high bits,
+/// stored in unary as one set bit per element in an upper bit array. A
+/// directory records where every 64th element's bit lies in the upper
+/// array, so `select` starts from the nearest entry and only scans the
+/// words up to the element it wants. The upper array averages at least one
+/// set bit per two bits, so that is a couple of words on average and
+/// `select` runs in constant time whatever the length. The set cannot be
+/// modified once built.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::EliasFanoSet;
+///
+/// let set = EliasFanoSet::from_sorted(1 << 30, vec![5, 1000, 70_000, 900_000_000]);
+///
+/// assert_eq!(set.len(), 4);
+/// assert_eq!(set.select(2), Some(70_000));
+/// assert!(set.contains(1000));
+/// assert!(!set.contains(1001));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EliasFanoSet {
+    universe: usize,
+    len: usize,
+    low_len: usize,
+    lower: DenseBitSet,
+    upper: DenseBitSet,
+    samples: Vec<usize>,
+}
+
+impl EliasFanoSet {
+    /// Encodes `indices`, which must be strictly increasing and below `universe`
+    pub fn from_sorted<I: IntoIterator<Item = usize>>(universe: usize, indices: I) -> EliasFanoSet {
+        let indices: Vec<usize> = indices.into_iter().collect();
+        let len = indices.len();
+        let low_len = if len == 0 || universe <= len {
+            0
+        } else {
+            (universe / len).ilog2() as usize
+        };
+
+        let mut lower = DenseBitSet::with_capacity(len * low_len);
+        let mut upper = DenseBitSet::with_capacity(len + (universe >> low_len) + 1);
+        let mut samples = Vec::with_capacity(len.div_ceil(SELECT_SAMPLE));
+        let mut prev = None;
+        for (k, &i) in indices.iter().enumerate() {
+            assert!(i < universe, "index {} is outside the universe of {}", i, universe);
+            assert!(prev.is_none_or(|p| p < i), "indices must be strictly increasing");
+            prev = Some(i);
+
+            lower.write_bits(k * low_len, low_len, i);
+            let pos = (i >> low_len) + k;
+            upper.set(pos);
+            if k.is_multiple_of(SELECT_SAMPLE) {
+                samples.push(pos);
+            }
+        }
+
+        EliasFanoSet { universe, len, low_len, lower, upper, samples }
+    }
+
+    /// returns the number of elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// returns true if the set holds no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the exclusive upper bound on the indices
+    pub fn universe(&self) -> usize {
+        self.universe
+    }
+
+    /// returns the number of bits of encoded data, excluding the select index
+    pub fn size_in_bits(&self) -> usize {
+        self.len * self.low_len + self.upper.len()
+    }
+
+    /// Returns the `k`th (0-based) smallest index, or `None` if `k >= len()`
+    pub fn select(&self, k: usize) -> Option<usize> {
+        if k >= self.len {
+            return None;
+        }
+
+        let high = self.select_upper(k) - k;
+        Some((high << self.low_len) | self.lower.read_bits(k * self.low_len, self.low_len))
+    }
+
+    /// position in the upper array of the `k`th element's bit, `k < len`
+    fn select_upper(&self, k: usize) -> usize {
+        let from = self.samples[k / SELECT_SAMPLE];
+        let mut remaining = k % SELECT_SAMPLE;
+        let mut w = get_word_offset(from);
+        let mut word = self.upper.bits[w] & (!0 << get_bit_offset(from));
+        loop {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                return w * BITS_PER_WORD + select_in_word(word, remaining);
+            }
+            remaining -= ones;
+            w += 1;
+            word = self.upper.bits[w];
+        }
+    }
+
+    /// Returns whether `i` is in the set, by binary search over `select`
+    pub fn contains(&self, i: usize) -> bool {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.select(mid).unwrap() {
+                x if x == i => return true,
+                x if x < i => lo = mid + 1,
+                _ => hi = mid,
+            }
+        }
+        false
+    }
+
+    /// Returns an iterator over the indices in ascending order
+    pub fn iter(&self) -> EliasFanoIter<'_> {
+        EliasFanoIter { set: self, upper: self.upper.ones(), k: 0 }
+    }
+
+    /// Decodes the set into a `DenseBitSet` that can hold `universe()` bits
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_indices(self.universe, self.iter())
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for EliasFanoSet {
+    fn from(bs: &'a DenseBitSet) -> EliasFanoSet {
+        EliasFanoSet::from_sorted(bs.len(), bs.ones())
+    }
+}
+
+/// Iterator over the indices of an `EliasFanoSet` in ascending order
+pub struct EliasFanoIter<'a> {
+    set: &'a EliasFanoSet,
+    upper: ::DenseOnesIterator<'a>,
+    k: usize,
+}
+
+impl<'a> Iterator for EliasFanoIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let pos = self.upper.next()?;
+        let low_len = self.set.low_len;
+        let i = ((pos - self.k) << low_len) | self.set.lower.read_bits(self.k * low_len, low_len);
+        self.k += 1;
+        Some(i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.len - self.k;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> IntoIterator for &'a EliasFanoSet {
+    type Item = usize;
+    type IntoIter = EliasFanoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// EliasFanoSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_round_trip_dense() {
+        let bs = DenseBitSet::from_indices(5000, (0..5000).filter(|i| i % 97 == 3 || *i == 4999));
+        let ef = EliasFanoSet::from(&bs);
+
+        assert_eq!(ef.len(), bs.count_ones());
+        assert_eq!(ef.iter().collect::<Vec<_>>(), bs.to_indices());
+        assert_eq!(ef.to_dense(), bs);
+        for (k, i) in bs.ones().enumerate() {
+            assert_eq!(ef.select(k), Some(i));
+            assert!(ef.contains(i));
+            assert!(!ef.contains(i + 1));
+        }
+    }
+
+    #[test]
+    fn stays_small_over_a_huge_universe() {
+        let universe = 1 << 31;
+        let indices: Vec<usize> = (0..10_000).map(|k| k * 214_000 + k % 7).collect();
+        let ef = EliasFanoSet::from_sorted(universe, indices.clone());
+
+        // 2 + log2(universe / len) bits per element, rounded up
+        assert!(ef.size_in_bits() <= indices.len() * 20);
+        assert_eq!(ef.iter().collect::<Vec<_>>(), indices);
+        assert_eq!(ef.select(9_999), Some(indices[9_999]));
+    }
+
+    #[test]
+    fn can_select_across_many_buckets() {
+        // several elements per high-bit bucket, then long runs of empty
+        // buckets, so directory entries fall mid-word and scans cross words
+        let mut indices: Vec<usize> = (0..3000).map(|k| (k / 3) * 64 + k % 3).collect();
+        indices.extend((0..700).map(|k| 1_000_000 + k * 4096));
+        let ef = EliasFanoSet::from_sorted(1 << 22, indices.clone());
+
+        assert_eq!(ef.samples.len(), indices.len().div_ceil(SELECT_SAMPLE));
+        for (k, &i) in indices.iter().enumerate() {
+            assert_eq!(ef.select(k), Some(i));
+        }
+        assert_eq!(ef.select(indices.len()), None);
+    }
+
+    #[test]
+    fn can_encode_empty_and_full_sets() {
+        let empty = EliasFanoSet::from_sorted(100, vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.select(0), None);
+
+        let full = EliasFanoSet::from_sorted(10, 0..10);
+        assert_eq!(full.iter().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_unsorted_indices() {
+        EliasFanoSet::from_sorted(100, vec![5, 3]);
+    }
+}
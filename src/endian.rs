@@ -0,0 +1,218 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Byte-order normalization for everything that reads or writes raw bytes
+//!
+//! The byte formats are always little-endian. Words are converted through
+//! a `ByteOrder` rather than with bare `to_le_bytes` calls, and native
+//! memory is reinterpreted only through `native_to_le`, which borrows on
+//! little-endian hosts and swaps on big-endian ones such as s390x or
+//! powerpc. Both orders are plain types, so tests can run the big-endian
+//! paths on any host.
+
+use std::borrow::Cow;
+use std::slice;
+
+use word::Word;
+use { DenseBitSet, BYTES_PER_WORD };
+
+/// The order of the bytes within a word
+pub(crate) trait ByteOrder {
+    /// whether this is little-endian, the order of every byte format
+    const IS_LITTLE: bool;
+
+    /// appends the bytes of `word` in this order
+    fn write<W: Word>(word: W, out: &mut Vec<u8>);
+
+    /// reads a word from up to `W::BYTES` bytes in this order, the missing
+    /// bytes being the most significant ones
+    fn read<W: Word>(chunk: &[u8]) -> W;
+}
+
+/// least significant byte first
+pub(crate) struct LittleEndian;
+
+/// most significant byte first
+#[cfg_attr(target_endian = "little", allow(dead_code))]
+pub(crate) struct BigEndian;
+
+/// the byte order of the host
+#[cfg(target_endian = "little")]
+pub(crate) type NativeEndian = LittleEndian;
+#[cfg(target_endian = "big")]
+pub(crate) type NativeEndian = BigEndian;
+
+impl ByteOrder for LittleEndian {
+    const IS_LITTLE: bool = true;
+
+    #[inline]
+    fn write<W: Word>(word: W, out: &mut Vec<u8>) {
+        word.write_le(out);
+    }
+
+    #[inline]
+    fn read<W: Word>(chunk: &[u8]) -> W {
+        W::read_le(chunk)
+    }
+}
+
+impl ByteOrder for BigEndian {
+    const IS_LITTLE: bool = false;
+
+    #[inline]
+    fn write<W: Word>(word: W, out: &mut Vec<u8>) {
+        let start = out.len();
+        word.write_le(out);
+        out[start..].reverse();
+    }
+
+    #[inline]
+    fn read<W: Word>(chunk: &[u8]) -> W {
+        let reversed: Vec<u8> = chunk.iter().rev().cloned().collect();
+        W::read_le(&reversed)
+    }
+}
+
+/// Writes the first `num_bits` bits of `words` in byte order `O`, one byte
+/// per 8 bits. Bits past `num_bits` are written as zero. A partial last
+/// word only makes sense little-endian, where the low bytes come first;
+/// big-endian callers pass whole words.
+pub(crate) fn words_to_bytes<O: ByteOrder, W: Word>(words: &[W], num_bits: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * W::BYTES);
+    let full_words = num_bits / W::BITS;
+
+    for (i, word) in words.iter().enumerate() {
+        let word = if i < full_words { *word } else { *word & W::low_mask(num_bits - i * W::BITS) };
+        O::write(word, &mut bytes);
+        if i >= full_words {
+            break;
+        }
+    }
+
+    bytes.truncate(num_bits.div_ceil(8));
+    bytes
+}
+
+/// Reads bytes in byte order `O` into as many words as needed to hold them
+pub(crate) fn words_from_bytes<O: ByteOrder, W: Word>(bytes: &[u8]) -> Vec<W> {
+    bytes.chunks(W::BYTES).map(O::read).collect()
+}
+
+/// Returns the little-endian bytes of words of `word_bytes` bytes each,
+/// laid out in `native` as a host of byte order `O` stores them. A
+/// little-endian host's memory is already in that layout and is borrowed.
+pub(crate) fn native_to_le<O: ByteOrder>(native: &[u8], word_bytes: usize) -> Cow<'_, [u8]> {
+    assert!(native.len().is_multiple_of(word_bytes), "{} bytes is not a whole number of words", native.len());
+    if O::IS_LITTLE {
+        return Cow::Borrowed(native);
+    }
+    let mut bytes = native.to_vec();
+    for word in bytes.chunks_mut(word_bytes) {
+        word.reverse();
+    }
+    Cow::Owned(bytes)
+}
+
+impl DenseBitSet {
+    /// Returns the bytes of `to_le_bytes()`, borrowing the set's own memory
+    /// where the host is little-endian and converting where it is not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ DenseBitSet, DenseBitSetView };
+    ///
+    /// let bs = DenseBitSet::from_indices(100, vec![3, 99]);
+    /// let bytes = bs.as_le_bytes();
+    ///
+    /// assert_eq!(&bytes[..], &bs.to_le_bytes()[..]);
+    /// assert!(DenseBitSetView::new(&bytes, bs.len()).unwrap().test(99));
+    /// ```
+    pub fn as_le_bytes(&self) -> Cow<'_, [u8]> {
+        // a [usize] is a valid, contiguous [u8] of the same size
+        let native = unsafe {
+            slice::from_raw_parts(self.bits.as_ptr() as *const u8, self.bits.len() * BYTES_PER_WORD)
+        };
+        let len = self.num_bits.div_ceil(8);
+        match native_to_le::<NativeEndian>(native, BYTES_PER_WORD) {
+            Cow::Borrowed(bytes) => Cow::Borrowed(&bytes[..len]),
+            Cow::Owned(mut bytes) => {
+                bytes.truncate(len);
+                Cow::Owned(bytes)
+            }
+        }
+    }
+}
+
+// Byte order TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn orders_mirror_each_other() {
+        let words: Vec<u32> = vec![0x0102_0304, 0xa0b0_c0d0];
+
+        assert_eq!(words_to_bytes::<LittleEndian, u32>(&words, 64), vec![4, 3, 2, 1, 0xd0, 0xc0, 0xb0, 0xa0]);
+        assert_eq!(words_to_bytes::<BigEndian, u32>(&words, 64), vec![1, 2, 3, 4, 0xa0, 0xb0, 0xc0, 0xd0]);
+
+        for &len in &[0, 1, 3, 4, 7, 8] {
+            let bytes: Vec<u8> = (1..=len as u8).collect();
+            let le: Vec<u32> = words_from_bytes::<LittleEndian, u32>(&bytes);
+            assert_eq!(words_to_bytes::<LittleEndian, u32>(&le, len * 8), bytes);
+            if len % 4 == 0 {
+                let be: Vec<u32> = words_from_bytes::<BigEndian, u32>(&bytes);
+                assert_eq!(words_to_bytes::<BigEndian, u32>(&be, len * 8), bytes);
+            }
+        }
+        assert_eq!(words_from_bytes::<BigEndian, u32>(&[1, 2]), vec![0x0102]);
+    }
+
+    #[test]
+    fn simulated_big_endian_host_normalizes() {
+        let bs = DenseBitSet::from_indices(192, vec![0, 9, 63, 64, 130, 191]);
+        let le = bs.to_le_bytes();
+
+        // the memory a big-endian host holds the same words in
+        let be_memory = words_to_bytes::<BigEndian, u64>(&words_from_bytes::<LittleEndian, u64>(&le), 192);
+        assert_ne!(be_memory, le);
+        assert_eq!(native_to_le::<BigEndian>(&be_memory, 8), le);
+
+        // and a 32-bit big-endian host
+        let be_memory = words_to_bytes::<BigEndian, u32>(&words_from_bytes::<LittleEndian, u32>(&le), 192);
+        assert_eq!(native_to_le::<BigEndian>(&be_memory, 4), le);
+
+        assert!(matches!(native_to_le::<LittleEndian>(&le, 8), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn as_le_bytes_matches_to_le_bytes() {
+        for &len in &[0usize, 1, 63, 64, 65, 200] {
+            let mut bs = DenseBitSet::from_vec_masked(vec![0; len.div_ceil(::BITS_PER_WORD)], len);
+            for i in (0..len).step_by(7) {
+                bs.set(i);
+            }
+            assert_eq!(&bs.as_le_bytes()[..], &bs.to_le_bytes()[..]);
+        }
+    }
+}
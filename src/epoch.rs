@@ -0,0 +1,281 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A concurrent bit set that grows without blocking readers, enabled by
+//! the `crossbeam-epoch` feature
+
+use std::sync::atomic::{ AtomicBool, AtomicUsize, Ordering };
+use std::sync::Mutex;
+
+use crossbeam_epoch::{ self as epoch, Atomic, Guard, Owned, Shared };
+
+use { get_bitmask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// one generation of storage
+struct Buffer {
+    words: Box<[AtomicUsize]>,
+    /// set once a grower has started copying this buffer out
+    frozen: AtomicBool,
+    /// writers currently applying an update to this buffer
+    writers: AtomicUsize,
+}
+
+impl Buffer {
+    fn new(words: usize) -> Buffer {
+        Buffer { words: (0..words).map(|_| AtomicUsize::new(0)).collect(), frozen: AtomicBool::new(false), writers: AtomicUsize::new(0) }
+    }
+
+    fn len(&self) -> usize {
+        self.words.len() * BITS_PER_WORD
+    }
+}
+
+/// An `AtomicBitSet` that can grow while other threads read and write it.
+///
+/// Growing allocates a larger word array, copies the old one into it and
+/// publishes it with a single pointer swap. Every operation pins the
+/// current epoch for its duration, and the old array is freed only once
+/// no pinned thread can still hold it, so readers never block and never
+/// see a dangling buffer.
+///
+/// Writers register with the array they update, and a grower freezes the
+/// array and waits for the registered writers to finish before copying it.
+/// Every write therefore lands in exactly one array and is copied forward,
+/// so `set` and `remove` stay linearizable however they interleave with
+/// growth. A writer that finds its array frozen waits for the grower to
+/// publish the new one and writes there instead. `set` grows the set on
+/// demand; readers never wait.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use bitsets::GrowableAtomicBitSet;
+///
+/// let bs = Arc::new(GrowableAtomicBitSet::with_capacity(64));
+/// let handles: Vec<_> = (0..4).map(|t| {
+///     let bs = Arc::clone(&bs);
+///     thread::spawn(move || { bs.set(t * 10_000); })
+/// }).collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// assert!(bs.len() > 30_000);
+/// assert_eq!(bs.snapshot().to_indices(), vec![0, 10_000, 20_000, 30_000]);
+/// ```
+pub struct GrowableAtomicBitSet {
+    current: Atomic<Buffer>,
+    grow_lock: Mutex<()>,
+}
+
+impl GrowableAtomicBitSet {
+    /// Creates a set that can contain at least `num_bits` bits before it
+    /// first grows, rounded up to whole words
+    pub fn with_capacity(num_bits: usize) -> GrowableAtomicBitSet {
+        GrowableAtomicBitSet {
+            current: Atomic::new(Buffer::new(num_bits.div_ceil(BITS_PER_WORD))),
+            grow_lock: Mutex::new(()),
+        }
+    }
+
+    fn load<'g>(&self, guard: &'g Guard) -> (Shared<'g, Buffer>, &'g Buffer) {
+        let shared = self.current.load(Ordering::SeqCst, guard);
+        // the buffer is only freed through defer_destroy once no guard can see it
+        (shared, unsafe { shared.deref() })
+    }
+
+    /// returns the number of bits the set can accommodate without growing
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        let guard = &epoch::pin();
+        self.load(guard).1.len()
+    }
+
+    /// Tests whether the ith bit is set. Bits past `len()` read as clear.
+    pub fn test(&self, i: usize) -> bool {
+        let guard = &epoch::pin();
+        let (_, buffer) = self.load(guard);
+        buffer.words.get(get_word_offset(i))
+            .is_some_and(|word| word.load(Ordering::Acquire) & get_bitmask(i) != 0)
+    }
+
+    /// Sets the ith bit, growing the set first if `i` is past `len()`.
+    /// Returns true if bit was not set previously
+    pub fn set(&self, i: usize) -> bool {
+        let guard = &epoch::pin();
+        if i >= self.load(guard).1.len() {
+            self.grow(i + 1);
+        }
+        let prior = self.apply(guard, |word| word.fetch_or(get_bitmask(i), Ordering::SeqCst), i);
+        prior & get_bitmask(i) == 0
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&self, i: usize) -> bool {
+        let guard = &epoch::pin();
+        if i >= self.load(guard).1.len() {
+            return false;
+        }
+        let prior = self.apply(guard, |word| word.fetch_and(!get_bitmask(i), Ordering::SeqCst), i);
+        prior & get_bitmask(i) != 0
+    }
+
+    /// Runs `op` once on the word holding bit `i` in a buffer no grower is
+    /// copying, and returns its result
+    fn apply<F: Fn(&AtomicUsize) -> usize>(&self, guard: &Guard, op: F, i: usize) -> usize {
+        loop {
+            let (_, buffer) = self.load(guard);
+
+            // register before checking for a grower, which freezes before
+            // checking for writers: with both sides SeqCst, either this
+            // writer sees the freeze or the grower waits for this write
+            buffer.writers.fetch_add(1, Ordering::SeqCst);
+            if !buffer.frozen.load(Ordering::SeqCst) {
+                let prior = op(&buffer.words[get_word_offset(i)]);
+                buffer.writers.fetch_sub(1, Ordering::SeqCst);
+                return prior;
+            }
+
+            // a grower is copying this buffer; retry once it publishes the next
+            buffer.writers.fetch_sub(1, Ordering::SeqCst);
+            ::std::hint::spin_loop();
+        }
+    }
+
+    /// Grows the set to hold at least `num_bits` bits, at least doubling
+    /// its length. Does nothing if it is already that long.
+    pub fn grow(&self, num_bits: usize) {
+        let _lock = self.grow_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let guard = &epoch::pin();
+        let (old, buffer) = self.load(guard);
+        if buffer.len() >= num_bits {
+            return;
+        }
+
+        let words = num_bits.div_ceil(BITS_PER_WORD).max(2 * buffer.words.len());
+        let next = Buffer::new(words);
+        buffer.frozen.store(true, Ordering::SeqCst);
+        while buffer.writers.load(Ordering::SeqCst) != 0 {
+            ::std::hint::spin_loop();
+        }
+        for (to, from) in next.words.iter().zip(buffer.words.iter()) {
+            to.store(from.load(Ordering::SeqCst), Ordering::Relaxed);
+        }
+
+        self.current.store(Owned::new(next), Ordering::SeqCst);
+        unsafe { guard.defer_destroy(old) };
+    }
+
+    /// Returns the number of set bits, loading each word once
+    pub fn count_ones(&self) -> usize {
+        let guard = &epoch::pin();
+        self.load(guard).1.words.iter().map(|word| word.load(Ordering::Acquire).count_ones() as usize).sum()
+    }
+
+    /// Copies the set into a `DenseBitSet`, loading each word once
+    pub fn snapshot(&self) -> DenseBitSet {
+        let guard = &epoch::pin();
+        DenseBitSet::from_vec(self.load(guard).1.words.iter().map(|word| word.load(Ordering::Acquire)).collect())
+    }
+}
+
+impl Drop for GrowableAtomicBitSet {
+    fn drop(&mut self) {
+        // no other thread can reach the set anymore
+        unsafe {
+            let guard = epoch::unprotected();
+            drop(self.current.load(Ordering::Relaxed, guard).into_owned());
+        }
+    }
+}
+
+// GrowableAtomicBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn grows_on_demand() {
+        let bs = GrowableAtomicBitSet::with_capacity(10);
+        assert_eq!(bs.len(), BITS_PER_WORD);
+        assert!(!bs.test(1000));
+        assert!(!bs.remove(1000));
+
+        assert!(bs.set(1000));
+        assert!(!bs.set(1000));
+        assert!(bs.len() > 1000);
+        assert!(bs.set(3));
+
+        bs.grow(10);
+        assert_eq!(bs.snapshot().to_indices(), vec![3, 1000]);
+        assert!(bs.remove(3));
+        assert_eq!(bs.count_ones(), 1);
+    }
+
+    #[test]
+    fn concurrent_writes_survive_growth() {
+        let bs = Arc::new(GrowableAtomicBitSet::with_capacity(1));
+        let handles: Vec<_> = (0..4).map(|t| {
+            let bs = Arc::clone(&bs);
+            thread::spawn(move || {
+                for i in (t..20_000).step_by(4) {
+                    bs.set(i);
+                    assert!(bs.test(i));
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(bs.count_ones(), 20_000);
+    }
+
+    #[test]
+    fn mixed_writes_survive_growth() {
+        let bs = Arc::new(GrowableAtomicBitSet::with_capacity(1));
+        let handles: Vec<_> = (0..4).map(|t| {
+            let bs = Arc::clone(&bs);
+            thread::spawn(move || {
+                for i in (t..20_000).step_by(4) {
+                    assert!(bs.set(i));
+                    if i % 3 != 0 {
+                        assert!(bs.remove(i));
+                        assert!(!bs.test(i));
+                    }
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let expected: Vec<_> = (0..20_000).filter(|i| i % 3 == 0).collect();
+        assert_eq!(bs.snapshot().to_indices(), expected);
+    }
+}
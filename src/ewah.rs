@@ -0,0 +1,381 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `EwahBitSet`, a word-aligned run-length compressed bitset
+
+use std::cmp;
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// bits of a marker word holding the length of its run
+const RUN_LEN_BITS: usize = BITS_PER_WORD / 2;
+/// bits of a marker word holding the number of literals that follow it
+const LITERALS_BITS: usize = BITS_PER_WORD - 1 - RUN_LEN_BITS;
+const MAX_RUN_LEN: usize = (1 << RUN_LEN_BITS) - 1;
+const MAX_LITERALS: usize = (1 << LITERALS_BITS) - 1;
+
+fn marker(fill: bool, run_len: usize, literals: usize) -> usize {
+    fill as usize | run_len << 1 | literals << (1 + RUN_LEN_BITS)
+}
+
+fn marker_fill(marker: usize) -> bool {
+    marker & 1 == 1
+}
+
+fn marker_run_len(marker: usize) -> usize {
+    (marker >> 1) & MAX_RUN_LEN
+}
+
+fn marker_literals(marker: usize) -> usize {
+    marker >> (1 + RUN_LEN_BITS)
+}
+
+/// A run-length compressed bitset in the EWAH format.
+///
+/// The set is a sequence of marker words, each describing a run of empty
+/// or full words followed by a number of literal words stored verbatim.
+/// Sets are built by appending whole words, and `and`, `or` and `xor` walk
+/// both operands in their compressed form without materializing either.
+/// Lengths are always a multiple of the word size; sets of different
+/// lengths combine as if the shorter one were padded with zeros.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, EwahBitSet };
+///
+/// let mut a = EwahBitSet::new();
+/// a.append_run(false, 1000);
+/// a.append_word(0b1010);
+///
+/// let b = EwahBitSet::from(&DenseBitSet::from_indices(1 << 20, vec![5, 1000 * 64 + 1]));
+///
+/// assert_eq!(a.and(&b).ones().collect::<Vec<_>>(), vec![1000 * 64 + 1]);
+/// assert!(a.size_in_words() < 4);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct EwahBitSet {
+    words: Vec<usize>,
+    num_words: usize,
+    last_marker: usize,
+}
+
+/// A stretch of uncompressed words
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Segment {
+    Run(bool, usize),
+    Literal(usize),
+}
+
+/// Walks the segments of an `EwahBitSet`, reading past its end as zeros
+struct Cursor<'a> {
+    words: &'a [usize],
+    pos: usize,
+    fill: bool,
+    run_left: usize,
+    literals_left: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(words: &'a [usize]) -> Cursor<'a> {
+        Cursor { words, pos: 0, fill: false, run_left: 0, literals_left: 0 }
+    }
+
+    /// returns the current segment, or `None` past the last word
+    fn peek(&mut self) -> Option<Segment> {
+        while self.run_left == 0 && self.literals_left == 0 {
+            let &m = self.words.get(self.pos)?;
+            self.pos += 1;
+            self.fill = marker_fill(m);
+            self.run_left = marker_run_len(m);
+            self.literals_left = marker_literals(m);
+        }
+
+        if self.run_left > 0 {
+            Some(Segment::Run(self.fill, self.run_left))
+        } else {
+            Some(Segment::Literal(self.words[self.pos]))
+        }
+    }
+
+    /// skips `n` words, which may span several segments
+    fn skip(&mut self, mut n: usize) {
+        while n > 0 {
+            match self.peek() {
+                None => return,
+                Some(Segment::Run(_, len)) => {
+                    let k = cmp::min(len, n);
+                    self.run_left -= k;
+                    n -= k;
+                }
+                Some(Segment::Literal(_)) => {
+                    self.pos += 1;
+                    self.literals_left -= 1;
+                    n -= 1;
+                }
+            }
+        }
+    }
+}
+
+impl EwahBitSet {
+    /// Creates an empty set of length 0
+    pub fn new() -> EwahBitSet {
+        EwahBitSet { words: vec![0], num_words: 0, last_marker: 0 }
+    }
+
+    /// Returns the number of bits, always a multiple of the word size
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_words * BITS_PER_WORD
+    }
+
+    /// Returns the number of words in the compressed form
+    pub fn size_in_words(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Appends `n` words that are all zeros or, if `fill` is set, all ones
+    pub fn append_run(&mut self, fill: bool, mut n: usize) {
+        self.num_words += n;
+        while n > 0 {
+            let m = self.words[self.last_marker];
+            let extends = marker_literals(m) == 0
+                && (marker_run_len(m) == 0 || marker_fill(m) == fill)
+                && marker_run_len(m) < MAX_RUN_LEN;
+
+            if extends {
+                let k = cmp::min(n, MAX_RUN_LEN - marker_run_len(m));
+                self.words[self.last_marker] = marker(fill, marker_run_len(m) + k, 0);
+                n -= k;
+            } else {
+                self.last_marker = self.words.len();
+                self.words.push(marker(fill, 0, 0));
+            }
+        }
+    }
+
+    /// Appends one word, compressing it into a run if it is all zeros or
+    /// all ones
+    pub fn append_word(&mut self, word: usize) {
+        if word == 0 || word == !0 {
+            return self.append_run(word != 0, 1);
+        }
+
+        let m = self.words[self.last_marker];
+        if marker_literals(m) == MAX_LITERALS {
+            self.last_marker = self.words.len();
+            self.words.push(marker(false, 0, 1));
+        } else {
+            self.words[self.last_marker] = marker(marker_fill(m), marker_run_len(m), marker_literals(m) + 1);
+        }
+        self.words.push(word);
+        self.num_words += 1;
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.segments()
+            .map(|(_, segment)| match segment {
+                Segment::Run(fill, len) => fill as usize * len * BITS_PER_WORD,
+                Segment::Literal(word) => word.count_ones() as usize,
+            })
+            .sum()
+    }
+
+    /// Returns an iterator over the indices of set bits, in increasing order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.segments().flat_map(|(start, segment)| {
+            let (first, end, word) = match segment {
+                Segment::Run(true, len) => (start * BITS_PER_WORD, (start + len) * BITS_PER_WORD, !0),
+                Segment::Run(false, _) => (0, 0, 0),
+                Segment::Literal(word) => (start * BITS_PER_WORD, (start + 1) * BITS_PER_WORD, word),
+            };
+            (first..end).filter(move |&i| word >> (i % BITS_PER_WORD) & 1 == 1)
+        })
+    }
+
+    /// Decompresses into a `DenseBitSet` of the same length
+    pub fn to_dense(&self) -> DenseBitSet {
+        let mut words = Vec::with_capacity(self.num_words);
+        for (_, segment) in self.segments() {
+            match segment {
+                Segment::Run(fill, len) => words.extend((0..len).map(|_| if fill { !0 } else { 0 })),
+                Segment::Literal(word) => words.push(word),
+            }
+        }
+        DenseBitSet::from_vec_masked(words, self.len())
+    }
+
+    /// Returns the bitwise-and of `self` and `other`
+    pub fn and(&self, other: &EwahBitSet) -> EwahBitSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Returns the bitwise-or of `self` and `other`
+    pub fn or(&self, other: &EwahBitSet) -> EwahBitSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Returns the bitwise-xor of `self` and `other`
+    pub fn xor(&self, other: &EwahBitSet) -> EwahBitSet {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// yields each segment along with the index of its first word
+    fn segments(&self) -> impl Iterator<Item = (usize, Segment)> + '_ {
+        let mut cursor = Cursor::new(&self.words);
+        let mut start = 0;
+        ::std::iter::from_fn(move || {
+            let segment = cursor.peek()?;
+            let len = match segment {
+                Segment::Run(_, len) => len,
+                Segment::Literal(_) => 1,
+            };
+            cursor.skip(len);
+            start += len;
+            Some((start - len, segment))
+        })
+    }
+
+    fn combine(&self, other: &EwahBitSet, op: fn(usize, usize) -> usize) -> EwahBitSet {
+        let total = cmp::max(self.num_words, other.num_words);
+        let (mut a, mut b) = (Cursor::new(&self.words), Cursor::new(&other.words));
+        let mut output = EwahBitSet::new();
+
+        while output.num_words < total {
+            let left = total - output.num_words;
+            let sa = a.peek().unwrap_or(Segment::Run(false, left));
+            let sb = b.peek().unwrap_or(Segment::Run(false, left));
+
+            // a run that decides the result on its own lets the other side
+            // be skipped however it is encoded
+            let decisive = |fill: bool| {
+                let w = if fill { !0 } else { 0 };
+                if op(w, 0) == op(w, !0) && op(0, w) == op(!0, w) { Some(op(w, 0) != 0) } else { None }
+            };
+
+            let (run, n) = match (sa, sb) {
+                (Segment::Run(fa, na), Segment::Run(fb, nb)) => {
+                    let fill = op(if fa { !0 } else { 0 }, if fb { !0 } else { 0 }) != 0;
+                    (Some(fill), cmp::min(na, nb))
+                }
+                (Segment::Run(fill, n), _) | (_, Segment::Run(fill, n)) if decisive(fill).is_some() => (decisive(fill), n),
+                _ => (None, 1),
+            };
+            let n = cmp::min(n, left);
+
+            match run {
+                Some(fill) => output.append_run(fill, n),
+                None => output.append_word(op(word_at(sa), word_at(sb))),
+            }
+            a.skip(n);
+            b.skip(n);
+        }
+
+        output
+    }
+}
+
+/// the first uncompressed word of a segment
+fn word_at(segment: Segment) -> usize {
+    match segment {
+        Segment::Run(fill, _) => if fill { !0 } else { 0 },
+        Segment::Literal(word) => word,
+    }
+}
+
+impl Default for EwahBitSet {
+    fn default() -> EwahBitSet {
+        EwahBitSet::new()
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for EwahBitSet {
+    fn from(bits: &'a DenseBitSet) -> EwahBitSet {
+        let mut output = EwahBitSet::new();
+        for &word in &bits.bits {
+            output.append_word(word);
+        }
+        output
+    }
+}
+
+// EwahBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn sample(len: usize, salt: usize) -> DenseBitSet {
+        // long empty and full stretches broken up by scattered literals
+        DenseBitSet::from_indices(len, (0..len).filter(|i| match (i / 1000 + salt) % 4 {
+            0 => false,
+            1 => true,
+            _ => (i * 7 + salt).is_multiple_of(13),
+        }))
+    }
+
+    #[test]
+    fn round_trips_through_dense() {
+        let bs = sample(100 * BITS_PER_WORD, 0);
+        let ewah = EwahBitSet::from(&bs);
+
+        assert_eq!(ewah.to_dense(), bs);
+        assert_eq!(ewah.count_ones(), bs.count_ones());
+        assert_eq!(ewah.ones().collect::<Vec<_>>(), bs.ones().collect::<Vec<_>>());
+        assert!(ewah.size_in_words() < bs.words());
+    }
+
+    #[test]
+    fn ops_match_dense() {
+        let len = 100 * BITS_PER_WORD;
+        for salt in 0..4 {
+            let (a, b) = (sample(len, 0), sample(len, salt + 1));
+            let (ea, eb) = (EwahBitSet::from(&a), EwahBitSet::from(&b));
+
+            assert_eq!(ea.and(&eb).to_dense(), a.and(&b));
+            assert_eq!(ea.or(&eb).to_dense(), a.or(&b));
+            assert_eq!(ea.xor(&eb).to_dense(), a.xor(&b));
+        }
+    }
+
+    #[test]
+    fn shorter_operand_reads_as_zeros() {
+        let a = EwahBitSet::from(&DenseBitSet::from_indices(10 * BITS_PER_WORD, vec![1, 5 * BITS_PER_WORD]));
+        let b = EwahBitSet::from(&DenseBitSet::from_indices(2 * BITS_PER_WORD, vec![1, 2]));
+
+        assert_eq!(a.or(&b).len(), 10 * BITS_PER_WORD);
+        assert_eq!(a.or(&b).ones().collect::<Vec<_>>(), vec![1, 2, 5 * BITS_PER_WORD]);
+        assert_eq!(b.and(&a).ones().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn long_runs_split_across_markers() {
+        let mut ewah = EwahBitSet::new();
+        ewah.append_run(true, MAX_RUN_LEN + 3);
+        ewah.append_word(0b110);
+
+        assert_eq!(ewah.size_in_words(), 3);
+        assert_eq!(ewah.count_ones(), (MAX_RUN_LEN + 3) * BITS_PER_WORD + 2);
+    }
+}
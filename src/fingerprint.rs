@@ -0,0 +1,143 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Rolling fingerprints over sliding windows of bits
+
+use DenseBitSet;
+
+/// modulus of the rolling hash, the Mersenne prime 2^61 - 1
+const MODULUS: u64 = (1 << 61) - 1;
+/// base of the rolling hash polynomial
+const BASE: u64 = 0x1f3d_5b79_a3c4_e1d7 % MODULUS;
+
+#[inline]
+fn mul_mod(a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % MODULUS as u128) as u64
+}
+
+/// Iterator over the fingerprints of every window of a fixed number of
+/// bits, returned by `DenseBitSet::rolling_fingerprint`
+pub struct RollingFingerprints<'a> {
+    bs: &'a DenseBitSet,
+    window_len: usize,
+    // BASE^window_len, the weight of the bit leaving the window
+    top_weight: u64,
+    hash: u64,
+    next_start: usize,
+}
+
+impl<'a> Iterator for RollingFingerprints<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let start = self.next_start;
+        if start + self.window_len > self.bs.len() {
+            return None;
+        }
+
+        if start == 0 {
+            for i in 0..self.window_len {
+                self.hash = (mul_mod(self.hash, BASE) + self.bs.test(i) as u64) % MODULUS;
+            }
+        } else {
+            let leaving = if self.bs.test(start - 1) { self.top_weight } else { 0 };
+            let entering = self.bs.test(start + self.window_len - 1) as u64;
+            self.hash = (mul_mod(self.hash, BASE) + MODULUS - leaving + entering) % MODULUS;
+        }
+
+        self.next_start += 1;
+        Some(self.hash)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.bs.len() + 1).saturating_sub(self.next_start + self.window_len);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for RollingFingerprints<'a> {}
+
+impl DenseBitSet {
+    /// Returns the fingerprint of every window of `window_len` consecutive
+    /// bits, in order of the window's first bit. Fingerprints are a
+    /// polynomial hash of the window's bits modulo 2^61 - 1, updated in
+    /// constant time per step. Equal windows always get equal
+    /// fingerprints; different windows collide with probability about
+    /// `window_len / 2^61`.
+    ///
+    /// Panics if `window_len` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1011_0000_1011);
+    /// let prints: Vec<u64> = bs.rolling_fingerprint(4).collect();
+    ///
+    /// assert_eq!(prints.len(), 61);
+    /// assert_eq!(prints[0], prints[8]);
+    /// assert_ne!(prints[0], prints[1]);
+    /// ```
+    pub fn rolling_fingerprint(&self, window_len: usize) -> RollingFingerprints<'_> {
+        assert!(window_len > 0);
+
+        let top_weight = (0..window_len).fold(1, |w, _| mul_mod(w, BASE));
+        RollingFingerprints { bs: self, window_len, top_weight, hash: 0, next_start: 0 }
+    }
+}
+
+// Fingerprint TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rolling_matches_recomputed_windows() {
+        let bs = DenseBitSet::from_indices(300, (0..300).filter(|i| (i * 7) % 11 < 4));
+
+        for &window in &[1, 5, 64, 100] {
+            let rolled: Vec<u64> = bs.rolling_fingerprint(window).collect();
+            assert_eq!(rolled.len(), bs.len() - window + 1);
+
+            for (start, &print) in rolled.iter().enumerate() {
+                let mut window_bits = DenseBitSet::with_capacity(window);
+                for i in 0..window {
+                    if bs.test(start + i) {
+                        window_bits.set(i);
+                    }
+                }
+                let direct = window_bits.rolling_fingerprint(window).next().unwrap();
+                assert_eq!(print, direct);
+            }
+        }
+    }
+
+    #[test]
+    fn window_longer_than_set_is_empty() {
+        let bs = DenseBitSet::with_capacity(64);
+
+        assert_eq!(bs.rolling_fingerprint(65).count(), 0);
+        assert_eq!(bs.rolling_fingerprint(64).len(), 1);
+    }
+}
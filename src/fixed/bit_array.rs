@@ -0,0 +1,267 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `BitArray`, a fixed-size `Copy` bit set stored inline
+
+use std::borrow::Cow;
+
+use { get_bitmask, get_word_offset, BitSet, BitWords, DenseBitSet, DenseOnesIterator, BITS_PER_WORD };
+
+/// A bit set of exactly `WORDS * usize::BITS` bits held in an inline array.
+///
+/// The set lives on the stack, is `Copy`, and needs no pointer chasing to
+/// reach its bits. The size is given in words because stable Rust cannot
+/// yet size an array from a bit count. Unlike `StaticBitSet` it mirrors the
+/// `DenseBitSet` API, panicking on out-of-bounds indices.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::BitArray;
+///
+/// let mut a = BitArray::<2>::new();
+/// a.set(3);
+/// a.set(100);
+///
+/// let b = a;
+/// a.flip(3);
+///
+/// assert_eq!(a.or(&b).ones().collect::<Vec<_>>(), vec![3, 100]);
+/// assert_eq!(a.xor(&b).count_ones(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BitArray<const WORDS: usize> {
+    words: [usize; WORDS],
+}
+
+impl<const WORDS: usize> BitArray<WORDS> {
+    /// The number of bits in the array
+    pub const LEN: usize = WORDS * BITS_PER_WORD;
+
+    /// Creates an array with every bit clear
+    pub const fn new() -> BitArray<WORDS> {
+        BitArray { words: [0; WORDS] }
+    }
+
+    /// Creates an array from its words, bit 0 being the lowest bit of the first word
+    pub const fn from_words(words: [usize; WORDS]) -> BitArray<WORDS> {
+        BitArray { words }
+    }
+
+    /// Returns the number of bits in the array
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        Self::LEN
+    }
+
+    /// Returns the words holding the bits
+    pub fn as_words(&self) -> &[usize; WORDS] {
+        &self.words
+    }
+
+    /// Tests whether the ith bit is set
+    #[inline]
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < Self::LEN, "index {} is out of bounds for an array of {} bits", i, Self::LEN);
+        self.words[get_word_offset(i)] & get_bitmask(i) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    #[inline]
+    pub fn set(&mut self, i: usize) -> bool {
+        let was_clear = !self.test(i);
+        self.words[get_word_offset(i)] |= get_bitmask(i);
+        was_clear
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    #[inline]
+    pub fn remove(&mut self, i: usize) -> bool {
+        let was_set = self.test(i);
+        self.words[get_word_offset(i)] &= !get_bitmask(i);
+        was_set
+    }
+
+    /// Flips the ith bit
+    #[inline]
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < Self::LEN, "index {} is out of bounds for an array of {} bits", i, Self::LEN);
+        self.words[get_word_offset(i)] ^= get_bitmask(i);
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> DenseOnesIterator<'_> {
+        DenseOnesIterator::new(&self.words, Self::LEN)
+    }
+
+    /// In-place bitwise-not
+    pub fn inplace_not(&mut self) {
+        for word in &mut self.words {
+            *word = !*word;
+        }
+    }
+
+    /// In-place bitwise-and with `other`
+    pub fn inplace_and(&mut self, other: &BitArray<WORDS>) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= *other;
+        }
+    }
+
+    /// In-place bitwise-or with `other`
+    pub fn inplace_or(&mut self, other: &BitArray<WORDS>) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= *other;
+        }
+    }
+
+    /// In-place bitwise-xor with `other`
+    pub fn inplace_xor(&mut self, other: &BitArray<WORDS>) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word ^= *other;
+        }
+    }
+
+    /// Returns the bitwise-not of `self`
+    pub fn not(&self) -> BitArray<WORDS> {
+        let mut output = *self;
+        output.inplace_not();
+        output
+    }
+
+    /// Returns the bitwise-and of `self` and `other`
+    pub fn and(&self, other: &BitArray<WORDS>) -> BitArray<WORDS> {
+        let mut output = *self;
+        output.inplace_and(other);
+        output
+    }
+
+    /// Returns the bitwise-or of `self` and `other`
+    pub fn or(&self, other: &BitArray<WORDS>) -> BitArray<WORDS> {
+        let mut output = *self;
+        output.inplace_or(other);
+        output
+    }
+
+    /// Returns the bitwise-xor of `self` and `other`
+    pub fn xor(&self, other: &BitArray<WORDS>) -> BitArray<WORDS> {
+        let mut output = *self;
+        output.inplace_xor(other);
+        output
+    }
+
+    /// Copies the bits into a `DenseBitSet`
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec(self.words.to_vec())
+    }
+}
+
+impl<const WORDS: usize> Default for BitArray<WORDS> {
+    fn default() -> BitArray<WORDS> {
+        BitArray::new()
+    }
+}
+
+impl<const WORDS: usize> BitWords for BitArray<WORDS> {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        Cow::Borrowed(&self.words)
+    }
+
+    fn bit_len(&self) -> usize {
+        Self::LEN
+    }
+}
+
+impl<const WORDS: usize> BitSet for BitArray<WORDS> {
+    fn len(&self) -> usize {
+        Self::LEN
+    }
+
+    fn test(&self, i: usize) -> bool {
+        BitArray::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        BitArray::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        BitArray::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        BitArray::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        self.ones().find(|&i| i >= from)
+    }
+}
+
+// BitArray TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn ops_match_dense() {
+        let mut a = BitArray::<3>::new();
+        let mut b = BitArray::<3>::new();
+        for i in (0..BitArray::<3>::LEN).filter(|i| i % 3 == 0) {
+            a.set(i);
+        }
+        for i in (0..BitArray::<3>::LEN).filter(|i| i % 5 == 0) {
+            b.set(i);
+        }
+        let (da, db) = (a.to_dense(), b.to_dense());
+
+        assert_eq!(a.and(&b).to_dense(), da.and(&db));
+        assert_eq!(a.or(&b).to_dense(), da.or(&db));
+        assert_eq!(a.xor(&b).to_dense(), da.xor(&db));
+        assert_eq!(a.not().count_ones(), BitArray::<3>::LEN - a.count_ones());
+        assert_eq!(a.ones().collect::<Vec<_>>(), da.to_indices());
+    }
+
+    #[test]
+    fn is_copy_and_default() {
+        let mut a = BitArray::<1>::default();
+        let b = a;
+        a.set(0);
+        assert!(a.test(0) && !b.test(0));
+        assert!(a.remove(0));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_out_of_bounds() {
+        BitArray::<1>::new().set(BITS_PER_WORD);
+    }
+}
@@ -0,0 +1,281 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `StaticBitSet`, a fixed-capacity `const` set with no heap and no panics
+
+#[cfg(feature = "std")]
+use DenseBitSet;
+use BITS_PER_WORD;
+
+/// A bit set of `WORDS` inline words, for code paths where allocating or
+/// panicking is not allowed, and for sets built at compile time.
+///
+/// Every operation is a `const fn`, never allocates and never panics:
+/// an index past the capacity makes the operation return `None` and leave
+/// the set untouched. Each call runs in time bounded by `WORDS`, with no
+/// locks or hidden state. The type is not atomic; a set shared between
+/// threads still needs a lock around `&mut` access.
+///
+/// `BitArray` has the same inline layout but mirrors the `DenseBitSet`
+/// API instead: it panics on out-of-bounds indices, has iterators, and
+/// implements `BitWords` and `BitSet`, whose trait methods cannot be
+/// `const`. Use `StaticBitSet` for statics and no-panic code, `BitArray`
+/// for everything else. `StaticBitSet` is the one type that needs neither
+/// `std` nor an allocator: build with `default-features = false` and the
+/// `fixed` feature to use it from `no_std` code.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::StaticBitSet;
+///
+/// static READY: StaticBitSet<1> = {
+///     let mut set = StaticBitSet::new();
+///     set.set(3);
+///     set
+/// };
+///
+/// let mut pending = READY;
+/// assert_eq!(pending.set(5), Some(false));
+/// assert_eq!(pending.set(1000), None);
+/// assert_eq!(pending.count_ones(), 2);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct StaticBitSet<const WORDS: usize> {
+    words: [usize; WORDS],
+}
+
+impl<const WORDS: usize> StaticBitSet<WORDS> {
+    /// The number of bits the set holds
+    pub const CAPACITY: usize = WORDS * BITS_PER_WORD;
+
+    /// Creates a set with every bit clear
+    pub const fn new() -> StaticBitSet<WORDS> {
+        StaticBitSet { words: [0; WORDS] }
+    }
+
+    /// Creates a set from its words, bit 0 being the lowest bit of the first word
+    pub const fn from_words(words: [usize; WORDS]) -> StaticBitSet<WORDS> {
+        StaticBitSet { words }
+    }
+
+    /// Returns the words backing the set
+    pub const fn as_words(&self) -> &[usize; WORDS] {
+        &self.words
+    }
+
+    /// Returns the number of bits the set holds
+    pub const fn capacity(&self) -> usize {
+        Self::CAPACITY
+    }
+
+    /// Returns whether the ith bit is set, or `None` if `i` is out of bounds
+    pub const fn test(&self, i: usize) -> Option<bool> {
+        if i >= Self::CAPACITY {
+            return None;
+        }
+        Some(self.words[i / BITS_PER_WORD] >> (i % BITS_PER_WORD) & 1 == 1)
+    }
+
+    /// Sets the ith bit, returning its previous value, or `None` if `i` is
+    /// out of bounds
+    pub const fn set(&mut self, i: usize) -> Option<bool> {
+        let was = self.test(i);
+        if was.is_some() {
+            self.words[i / BITS_PER_WORD] |= 1 << (i % BITS_PER_WORD);
+        }
+        was
+    }
+
+    /// Clears the ith bit, returning its previous value, or `None` if `i`
+    /// is out of bounds
+    pub const fn remove(&mut self, i: usize) -> Option<bool> {
+        let was = self.test(i);
+        if was.is_some() {
+            self.words[i / BITS_PER_WORD] &= !(1 << (i % BITS_PER_WORD));
+        }
+        was
+    }
+
+    /// Flips the ith bit, returning its previous value, or `None` if `i` is
+    /// out of bounds
+    pub const fn flip(&mut self, i: usize) -> Option<bool> {
+        let was = self.test(i);
+        if was.is_some() {
+            self.words[i / BITS_PER_WORD] ^= 1 << (i % BITS_PER_WORD);
+        }
+        was
+    }
+
+    /// Clears every bit
+    pub const fn clear_all(&mut self) {
+        self.words = [0; WORDS];
+    }
+
+    /// Returns the number of set bits
+    pub const fn count_ones(&self) -> usize {
+        let mut count = 0;
+        let mut w = 0;
+        while w < WORDS {
+            count += self.words[w].count_ones() as usize;
+            w += 1;
+        }
+        count
+    }
+
+    /// Returns whether no bit is set
+    pub const fn is_empty(&self) -> bool {
+        let mut w = 0;
+        while w < WORDS {
+            if self.words[w] != 0 {
+                return false;
+            }
+            w += 1;
+        }
+        true
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    pub const fn next_set_bit(&self, from: usize) -> Option<usize> {
+        if from >= Self::CAPACITY {
+            return None;
+        }
+
+        let mut w = from / BITS_PER_WORD;
+        let mut word = self.words[w] & (!0 << (from % BITS_PER_WORD));
+        loop {
+            if word != 0 {
+                return Some(w * BITS_PER_WORD + word.trailing_zeros() as usize);
+            }
+            w += 1;
+            if w == WORDS {
+                return None;
+            }
+            word = self.words[w];
+        }
+    }
+
+    /// Returns the bitwise-or of `self` and `other`
+    pub const fn union(&self, other: &StaticBitSet<WORDS>) -> StaticBitSet<WORDS> {
+        let mut words = self.words;
+        let mut w = 0;
+        while w < WORDS {
+            words[w] |= other.words[w];
+            w += 1;
+        }
+        StaticBitSet { words }
+    }
+
+    /// Returns the bitwise-and of `self` and `other`
+    pub const fn intersection(&self, other: &StaticBitSet<WORDS>) -> StaticBitSet<WORDS> {
+        let mut words = self.words;
+        let mut w = 0;
+        while w < WORDS {
+            words[w] &= other.words[w];
+            w += 1;
+        }
+        StaticBitSet { words }
+    }
+
+    /// Returns the bits of `self` that are not set in `other`
+    pub const fn difference(&self, other: &StaticBitSet<WORDS>) -> StaticBitSet<WORDS> {
+        let mut words = self.words;
+        let mut w = 0;
+        while w < WORDS {
+            words[w] &= !other.words[w];
+            w += 1;
+        }
+        StaticBitSet { words }
+    }
+}
+
+impl<const WORDS: usize> Default for StaticBitSet<WORDS> {
+    fn default() -> StaticBitSet<WORDS> {
+        StaticBitSet::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, const WORDS: usize> From<&'a StaticBitSet<WORDS>> for DenseBitSet {
+    fn from(set: &'a StaticBitSet<WORDS>) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(set.words.to_vec(), StaticBitSet::<WORDS>::CAPACITY)
+    }
+}
+
+// StaticBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const PERIPHERALS: StaticBitSet<2> = {
+        let mut set = StaticBitSet::new();
+        set.set(0);
+        set.set(BITS_PER_WORD + 1);
+        set.flip(BITS_PER_WORD + 1);
+        set.set(BITS_PER_WORD + 2);
+        set
+    };
+
+    #[test]
+    fn builds_in_const_context() {
+        const COUNT: usize = PERIPHERALS.count_ones();
+        const NEXT: Option<usize> = PERIPHERALS.next_set_bit(1);
+
+        assert_eq!(COUNT, 2);
+        assert_eq!(NEXT, Some(BITS_PER_WORD + 2));
+        assert_eq!(PERIPHERALS.test(BITS_PER_WORD + 1), Some(false));
+    }
+
+    #[test]
+    fn out_of_bounds_is_none_and_harmless() {
+        let mut set = PERIPHERALS;
+        let cap = StaticBitSet::<2>::CAPACITY;
+
+        assert_eq!(set.set(cap), None);
+        assert_eq!(set.remove(usize::MAX), None);
+        assert_eq!(set.flip(cap), None);
+        assert_eq!(set.test(cap), None);
+        assert_eq!(set.next_set_bit(cap), None);
+        assert_eq!(set, PERIPHERALS);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = StaticBitSet::<1>::from_words([0b1100]);
+        let b = StaticBitSet::<1>::from_words([0b1010]);
+
+        assert_eq!(a.union(&b).as_words(), &[0b1110]);
+        assert_eq!(a.intersection(&b).as_words(), &[0b1000]);
+        assert_eq!(a.difference(&b).as_words(), &[0b0100]);
+        assert!(StaticBitSet::<0>::new().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn converts_to_dense() {
+        let a = StaticBitSet::<1>::from_words([0b1100]);
+
+        assert_eq!(DenseBitSet::from(&a).to_indices(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,379 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Formatting traits for `DenseBitSet`
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::{ Deref, DerefMut };
+
+use { DenseBitSet, BITS_PER_WORD };
+
+impl DenseBitSet {
+    /// Renders the set as a number in base `2^bits_per_digit`, most
+    /// significant digit first and without leading zeros.
+    fn digits(&self, bits_per_digit: usize, upper: bool) -> String {
+        let num_digits = self.len().div_ceil(bits_per_digit);
+        let mut digits = String::with_capacity(num_digits);
+
+        for digit in (0..num_digits).rev() {
+            let start = digit * bits_per_digit;
+            let len = bits_per_digit.min(self.len() - start);
+            let value = self.read_bits(start, len) as u32;
+            if value == 0 && digits.is_empty() {
+                continue;
+            }
+
+            let c = std::char::from_digit(value, 1 << bits_per_digit).unwrap();
+            digits.push(if upper { c.to_ascii_uppercase() } else { c });
+        }
+
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        digits
+    }
+}
+
+/// Formats the set as a binary number, bit 0 last, like an integer:
+/// leading zeros are dropped unless a zero-padded width is requested, and
+/// the alternate flag `{:#b}` adds a `0b` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = "0b1010".parse().unwrap();
+///
+/// assert_eq!(format!("{:b}", bs), "1010");
+/// assert_eq!(format!("{:#010b}", bs), "0b00001010");
+/// ```
+impl fmt::Binary for DenseBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0b", &self.digits(1, false))
+    }
+}
+
+/// Formats the set as a hexadecimal number, like `Binary` does in base 2;
+/// the alternate flag `{:#x}` adds a `0x` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = "0xbeef".parse().unwrap();
+///
+/// assert_eq!(format!("{:x}", bs), "beef");
+/// assert_eq!(format!("{:#x}", bs), "0xbeef");
+/// ```
+impl fmt::LowerHex for DenseBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.digits(4, false))
+    }
+}
+
+/// Like `LowerHex`, with upper-case digits.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = "0xbeef".parse().unwrap();
+///
+/// assert_eq!(format!("{:#X}", bs), "0xBEEF");
+/// ```
+impl fmt::UpperHex for DenseBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.digits(4, true))
+    }
+}
+
+/// Formats the set as the indices of its set bits, e.g. `{3, 17, 42}`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs = DenseBitSet::from_indices(4096, vec![3, 17, 42]);
+///
+/// assert_eq!(bs.to_string(), "{3, 17, 42}");
+/// assert_eq!(DenseBitSet::with_capacity(64).to_string(), "{}");
+/// ```
+impl fmt::Display for DenseBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (n, i) in self.ones().enumerate() {
+            if n > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", i)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Layout options for `DenseBitSet::display_with`, which writes the set
+/// as a string of `0`s and `1`s.
+///
+/// The default writes bit 0 first with no separators, the same as `Debug`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ BitFormat, DenseBitSet };
+///
+/// let bs = DenseBitSet::from_bits(0b1010_0011);
+/// let format = BitFormat::new().msb_first(true).group(4, "_");
+///
+/// assert!(bs.display_with(&format).to_string().ends_with("0000_1010_0011"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BitFormat {
+    group: usize,
+    separator: String,
+    msb_first: bool,
+    word_separator: Option<String>,
+}
+
+impl BitFormat {
+    /// Creates the default format: bit 0 first, no separators
+    pub fn new() -> BitFormat {
+        BitFormat::default()
+    }
+
+    /// Writes `separator` between groups of `size` bits. Groups are aligned
+    /// to bit indices, so a group always starts at a multiple of `size`.
+    /// A size of 0 disables grouping.
+    pub fn group(mut self, size: usize, separator: &str) -> BitFormat {
+        self.group = size;
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Writes the highest bit first, as hardware register dumps do,
+    /// instead of bit 0 first
+    pub fn msb_first(mut self, msb_first: bool) -> BitFormat {
+        self.msb_first = msb_first;
+        self
+    }
+
+    /// Writes `separator` between storage words, in place of the group
+    /// separator
+    pub fn word_separator(mut self, separator: &str) -> BitFormat {
+        self.word_separator = Some(separator.to_string());
+        self
+    }
+
+    /// the separator to write at the boundary before bit `i`, counted from bit 0
+    fn separator_before(&self, i: usize) -> Option<&str> {
+        match self.word_separator {
+            Some(ref separator) if i.is_multiple_of(BITS_PER_WORD) => Some(separator),
+            _ if self.group > 0 && i.is_multiple_of(self.group) => Some(&self.separator),
+            _ => None,
+        }
+    }
+}
+
+/// A `DenseBitSet` paired with a `BitFormat`, returned by
+/// `DenseBitSet::display_with`
+pub struct DisplayWith<'a> {
+    bs: &'a DenseBitSet,
+    format: &'a BitFormat,
+}
+
+impl<'a> fmt::Display for DisplayWith<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.bs.len();
+        for n in 0..len {
+            let i = if self.format.msb_first { len - 1 - n } else { n };
+            // the boundary between this bit and the previous one printed
+            let boundary = if self.format.msb_first { i + 1 } else { i };
+            if n > 0 {
+                if let Some(separator) = self.format.separator_before(boundary) {
+                    f.write_str(separator)?;
+                }
+            }
+            f.write_str(if self.bs.test(i) { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+impl DenseBitSet {
+    /// Returns an adapter that displays the set as bits laid out by `format`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ BitFormat, DenseBitSet };
+    ///
+    /// let bs = DenseBitSet::from_bits(0b0110);
+    /// let format = BitFormat::new().group(2, " ");
+    ///
+    /// assert!(bs.display_with(&format).to_string().starts_with("01 10 00"));
+    /// ```
+    pub fn display_with<'a>(&'a self, format: &'a BitFormat) -> DisplayWith<'a> {
+        DisplayWith { bs: self, format }
+    }
+}
+
+/// Debug-formats a `DenseBitSet` in set notation, collapsing runs of
+/// three or more set bits into half-open ranges: `{3, 7, 100..120, 4096}`.
+///
+/// `DebugSet(&bs)` borrows a set for a single `{:?}`. Holding the set
+/// itself, as in `DebugSet<DenseBitSet>`, makes a field that derefs to the
+/// set and keeps `#[derive(Debug)]` on the surrounding struct readable.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DebugSet, DenseBitSet };
+///
+/// let bs = DenseBitSet::from_indices(256, (100..120).chain([3, 7, 8, 200]));
+/// assert_eq!(format!("{:?}", DebugSet(&bs)), "{3, 7, 8, 100..120, 200}");
+///
+/// #[derive(Debug)]
+/// struct Segment {
+///     name: &'static str,
+///     members: DebugSet<DenseBitSet>,
+/// }
+///
+/// let mut segment = Segment { name: "premium", members: DebugSet(DenseBitSet::with_capacity(64)) };
+/// segment.members.set(5);
+/// assert_eq!(format!("{:?}", segment), r#"Segment { name: "premium", members: {5} }"#);
+/// ```
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct DebugSet<T>(pub T);
+
+impl<T: Borrow<DenseBitSet>> fmt::Debug for DebugSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (n, run) in self.0.borrow().runs().enumerate() {
+            if n > 0 {
+                write!(f, ", ")?;
+            }
+            match run.len() {
+                1 => write!(f, "{}", run.start)?,
+                2 => write!(f, "{}, {}", run.start, run.start + 1)?,
+                _ => write!(f, "{:?}", run)?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl<T: Borrow<DenseBitSet>> Deref for DebugSet<T> {
+    type Target = DenseBitSet;
+
+    fn deref(&self) -> &DenseBitSet {
+        self.0.borrow()
+    }
+}
+
+impl DerefMut for DebugSet<DenseBitSet> {
+    fn deref_mut(&mut self) -> &mut DenseBitSet {
+        &mut self.0
+    }
+}
+
+impl From<DenseBitSet> for DebugSet<DenseBitSet> {
+    fn from(bs: DenseBitSet) -> DebugSet<DenseBitSet> {
+        DebugSet(bs)
+    }
+}
+
+// Formatting TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_format_binary() {
+        let bs = DenseBitSet::from_bits(0b1011);
+
+        assert_eq!(format!("{:b}", bs), "1011");
+        assert_eq!(format!("{:b}", bs).parse::<DenseBitSet>().unwrap(), bs);
+        assert_eq!(format!("{:b}", DenseBitSet::with_capacity(128)), "0");
+    }
+
+    #[test]
+    fn can_format_hex() {
+        let bs = DenseBitSet::from_indices(128, vec![0, 127]);
+        let hex = format!("{:#x}", bs);
+
+        assert_eq!(hex, format!("0x8{}1", "0".repeat(30)));
+        assert_eq!(hex.parse::<DenseBitSet>().unwrap(), bs);
+        assert_eq!(format!("{:X}", DenseBitSet::from_bits(0xab)), "AB");
+    }
+
+    #[test]
+    fn can_display_indices() {
+        let bs = DenseBitSet::from_indices(256, vec![0, 64, 255]);
+
+        assert_eq!(format!("{}", bs), "{0, 64, 255}");
+        assert_eq!(format!("{}", DenseBitSet::from_bits(0b100)), "{2}");
+    }
+
+    #[test]
+    fn respects_width() {
+        let bs = DenseBitSet::from_bits(0x1f);
+
+        assert_eq!(format!("{:>6x}", bs), "    1f");
+        assert_eq!(format!("{:#08x}", bs), "0x00001f");
+        assert_eq!(format!("{:08b}", bs), "00011111");
+    }
+
+    #[test]
+    fn can_display_with_format() {
+        let bs = DenseBitSet::from_indices(128, vec![0, 127]);
+        let plain = bs.display_with(&BitFormat::new()).to_string();
+
+        assert_eq!(plain, format!("{:#?}", bs)["DenseBitset: ".len()..]);
+
+        let msb = BitFormat::new().msb_first(true).group(8, "_").word_separator(" | ");
+        let dump = bs.display_with(&msb).to_string();
+        let bytes = BITS_PER_WORD / 8;
+
+        assert!(dump.starts_with("10000000_"));
+        assert!(dump.ends_with("_00000001"));
+        assert_eq!(dump.matches(" | ").count(), bs.words() - 1);
+        assert_eq!(dump.matches('_').count(), bs.words() * (bytes - 1));
+    }
+
+    #[test]
+    fn debug_set_collapses_runs() {
+        let empty = DenseBitSet::with_capacity(64);
+        assert_eq!(format!("{:?}", DebugSet(&empty)), "{}");
+
+        let bs = DenseBitSet::from_indices(192, (60..70).chain([0, 1, 190, 191]));
+        assert_eq!(format!("{:?}", DebugSet(&bs)), "{0, 1, 60..70, 190, 191}");
+
+        let nested = vec![DebugSet(bs.clone()), DebugSet(empty)];
+        assert_eq!(format!("{:?}", nested), "[{0, 1, 60..70, 190, 191}, {}]");
+        assert_eq!(nested[0].count_ones(), bs.count_ones());
+    }
+}
@@ -0,0 +1,284 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `HierarchicalBitSet`, a layered bitset for scans over huge sparse domains
+
+use { get_bit_offset, get_bitmask, get_word_offset, BitSet, DenseBitSet, BITS_PER_WORD };
+
+/// A bit set with summary layers above its words: bit `j` of a layer is set
+/// when word `j` of the layer below is nonzero. The top layer is a single
+/// word.
+///
+/// `first_set` and `next_set_bit` climb the layers until they find a
+/// nonzero word and then descend, so they read a few words per layer
+/// instead of scanning the empty words in between. With 64-bit words,
+/// four layers cover 2^24 words, or about a billion bits. Writes update
+/// at most one word per layer.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::HierarchicalBitSet;
+///
+/// let mut bs = HierarchicalBitSet::new(1 << 30);
+/// bs.set(17);
+/// bs.set(900_000_000);
+///
+/// assert_eq!(bs.first_set(), Some(17));
+/// assert_eq!(bs.next_set_bit(18), Some(900_000_000));
+/// assert_eq!(bs.ones().count(), 2);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HierarchicalBitSet {
+    /// `layers[0]` holds the bits themselves
+    layers: Vec<Vec<usize>>,
+    num_bits: usize,
+}
+
+impl HierarchicalBitSet {
+    /// Creates an empty set of `num_bits` bits
+    pub fn new(num_bits: usize) -> HierarchicalBitSet {
+        let mut layers = vec![vec![0; num_bits.div_ceil(BITS_PER_WORD)]];
+        while layers[layers.len() - 1].len() > 1 {
+            let words = layers[layers.len() - 1].len().div_ceil(BITS_PER_WORD);
+            layers.push(vec![0; words]);
+        }
+        HierarchicalBitSet { layers, num_bits }
+    }
+
+    /// Returns the number of bits the set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns the number of layers, including the bits themselves
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        self.layers[0][get_word_offset(i)] & get_bitmask(i) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        if self.layers[0][get_word_offset(i)] & get_bitmask(i) != 0 {
+            return false;
+        }
+
+        let mut pos = i;
+        for layer in &mut self.layers {
+            let word = &mut layer[get_word_offset(pos)];
+            let was_empty = *word == 0;
+            *word |= get_bitmask(pos);
+            if !was_empty {
+                break;
+            }
+            pos = get_word_offset(pos);
+        }
+        true
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        if self.layers[0][get_word_offset(i)] & get_bitmask(i) == 0 {
+            return false;
+        }
+
+        let mut pos = i;
+        for layer in &mut self.layers {
+            let word = &mut layer[get_word_offset(pos)];
+            *word &= !get_bitmask(pos);
+            if *word != 0 {
+                break;
+            }
+            pos = get_word_offset(pos);
+        }
+        true
+    }
+
+    /// Returns whether no bit is set, by looking at the top layer only
+    pub fn none(&self) -> bool {
+        self.layers[self.layers.len() - 1].iter().all(|&word| word == 0)
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.layers[0].iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns the index of the lowest set bit, or `None` if no bit is set
+    pub fn first_set(&self) -> Option<usize> {
+        self.next_set_bit(0)
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    pub fn next_set_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.num_bits {
+            return None;
+        }
+
+        // climb until some word holds a set bit at or after `pos`
+        let mut layer = 0;
+        let mut pos = from;
+        loop {
+            let w = get_word_offset(pos);
+            let word = *self.layers[layer].get(w)? & !0 << get_bit_offset(pos);
+            if word != 0 {
+                pos = w * BITS_PER_WORD + word.trailing_zeros() as usize;
+                break;
+            }
+            layer += 1;
+            if layer == self.layers.len() {
+                return None;
+            }
+            pos = w + 1;
+        }
+
+        // and follow the lowest set bit back down
+        while layer > 0 {
+            layer -= 1;
+            pos = pos * BITS_PER_WORD + self.layers[layer][pos].trailing_zeros() as usize;
+        }
+        Some(pos)
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> HierarchicalOnes<'_> {
+        HierarchicalOnes { set: self, next: 0 }
+    }
+
+    /// Copies the bits into a `DenseBitSet`
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(self.layers[0].clone(), self.num_bits)
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for HierarchicalBitSet {
+    fn from(bits: &'a DenseBitSet) -> HierarchicalBitSet {
+        let mut set = HierarchicalBitSet::new(bits.len());
+        set.layers[0].copy_from_slice(&bits.bits);
+        for layer in 1..set.layers.len() {
+            let (below, above) = set.layers.split_at_mut(layer);
+            for (w, &word) in below[layer - 1].iter().enumerate() {
+                if word != 0 {
+                    above[0][get_word_offset(w)] |= get_bitmask(w);
+                }
+            }
+        }
+        set
+    }
+}
+
+/// An iterator over the indices of the set bits of a HierarchicalBitSet
+#[derive(Clone, Debug)]
+pub struct HierarchicalOnes<'a> {
+    set: &'a HierarchicalBitSet,
+    next: usize,
+}
+
+impl<'a> Iterator for HierarchicalOnes<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.set.next_set_bit(self.next)?;
+        self.next = i + 1;
+        Some(i)
+    }
+}
+
+impl BitSet for HierarchicalBitSet {
+    fn len(&self) -> usize {
+        HierarchicalBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        HierarchicalBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        HierarchicalBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        HierarchicalBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        HierarchicalBitSet::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        HierarchicalBitSet::next_set_bit(self, from)
+    }
+}
+
+// HierarchicalBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn layers_cover_the_set() {
+        assert_eq!(HierarchicalBitSet::new(0).depth(), 1);
+        assert_eq!(HierarchicalBitSet::new(BITS_PER_WORD).depth(), 1);
+        assert_eq!(HierarchicalBitSet::new(BITS_PER_WORD + 1).depth(), 2);
+        assert_eq!(HierarchicalBitSet::new(BITS_PER_WORD * BITS_PER_WORD + 1).depth(), 3);
+        assert_eq!(HierarchicalBitSet::new(0).first_set(), None);
+    }
+
+    #[test]
+    fn scans_match_dense() {
+        let len = BITS_PER_WORD * BITS_PER_WORD * 3 + 5;
+        let dense = DenseBitSet::from_indices(len, vec![0, 1, 63, 64, 4000, 4096, 8191, len - 1]);
+        let set = HierarchicalBitSet::from(&dense);
+
+        assert_eq!(set.ones().collect::<Vec<_>>(), dense.to_indices());
+        for from in (0..len).step_by(37) {
+            assert_eq!(set.next_set_bit(from), dense.next_set_bit(from));
+        }
+        assert_eq!(set.to_dense(), dense);
+    }
+
+    #[test]
+    fn removes_clear_summaries() {
+        let mut set = HierarchicalBitSet::new(1 << 20);
+        set.set(5);
+        set.set(700_000);
+        assert!(set.remove(5));
+        assert!(!set.remove(5));
+        assert_eq!(set.first_set(), Some(700_000));
+
+        set.remove(700_000);
+        assert!(set.none());
+        assert!(set.layers.iter().all(|layer| layer.iter().all(|&word| word == 0)));
+    }
+}
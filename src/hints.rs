@@ -0,0 +1,270 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Per-region occupancy hints that let bit searches skip uniform regions
+
+use { get_bit_offset, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// default number of words summarized by one hint
+const DEFAULT_REGION_WORDS: usize = 64;
+
+/// A `DenseBitSet` with "all zero" and "all one" flags for each region of
+/// words, so `next_set_bit` jumps over empty regions instead of reading
+/// every word.
+///
+/// The flags are hints: a set flag is always accurate, but a region can be
+/// uniform without its flag set. Writes through this type clear the flags
+/// of the region they touch and mark it stale; `refresh_hints()` rescans
+/// only the stale regions. Searches are correct at any time and get faster
+/// after a refresh.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, HintedBitSet };
+///
+/// let mut bs = HintedBitSet::new(DenseBitSet::with_capacity(1 << 20));
+/// bs.set(900_000);
+/// bs.refresh_hints();
+///
+/// assert_eq!(bs.next_set_bit(0), Some(900_000));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HintedBitSet {
+    bits: DenseBitSet,
+    region_words: usize,
+    empty: DenseBitSet,
+    full: DenseBitSet,
+    stale: DenseBitSet,
+}
+
+impl HintedBitSet {
+    /// Wraps `bits` with one hint per 64 words
+    pub fn new(bits: DenseBitSet) -> HintedBitSet {
+        HintedBitSet::with_region_words(bits, DEFAULT_REGION_WORDS)
+    }
+
+    /// Wraps `bits` with one hint per `region_words` words. Smaller
+    /// regions skip more precisely but cost more to refresh.
+    pub fn with_region_words(bits: DenseBitSet, region_words: usize) -> HintedBitSet {
+        assert!(region_words > 0);
+
+        let regions = bits.words().div_ceil(region_words);
+        let mut hinted = HintedBitSet {
+            bits,
+            region_words,
+            empty: DenseBitSet::with_capacity(regions),
+            full: DenseBitSet::with_capacity(regions),
+            stale: DenseBitSet::with_capacity(regions),
+        };
+        hinted.stale.set_range(..regions);
+        hinted.refresh_hints();
+        hinted
+    }
+
+    /// Borrows the underlying set
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the underlying set, dropping the hints
+    pub fn into_inner(self) -> DenseBitSet {
+        self.bits
+    }
+
+    /// returns the number of words summarized by one hint
+    pub fn region_words(&self) -> usize {
+        self.region_words
+    }
+
+    /// returns the number of regions whose hints are out of date
+    pub fn stale_regions(&self) -> usize {
+        self.stale.count_ones()
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        self.bits.test(i)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        self.invalidate(i);
+        self.bits.set(i)
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        self.invalidate(i);
+        self.bits.remove(i)
+    }
+
+    /// flips the value of the ith bit
+    pub fn flip(&mut self, i: usize) {
+        self.invalidate(i);
+        self.bits.flip(i)
+    }
+
+    /// Recomputes the hints of every stale region
+    pub fn refresh_hints(&mut self) {
+        let stale = self.stale.to_indices();
+        for region in stale {
+            let start = region * self.region_words;
+            let end = (start + self.region_words).min(self.bits.words());
+            let words = &self.bits.bits[start..end];
+
+            if words.iter().all(|&word| word == 0) {
+                self.empty.set(region);
+            }
+            // the trailing region is full if all of its bits within len() are set
+            let last_mask = if end == self.bits.words() { self.bits.last_word_mask() } else { !0 };
+            if let Some((last, rest)) = words.split_last() {
+                if *last == last_mask && rest.iter().all(|&word| word == !0) {
+                    self.full.set(region);
+                }
+            }
+            self.stale.remove(region);
+        }
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, skipping
+    /// regions hinted empty
+    pub fn next_set_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.bits.len() {
+            return None;
+        }
+
+        let words = &self.bits.bits;
+        let mut w = get_word_offset(from);
+        let mut word = words[w] & !get_low_mask(get_bit_offset(from));
+        loop {
+            if word != 0 {
+                return Some(w * BITS_PER_WORD + word.trailing_zeros() as usize);
+            }
+
+            w += 1;
+            while w < words.len() && w.is_multiple_of(self.region_words) && self.empty.test(w / self.region_words) {
+                w += self.region_words;
+            }
+            if w >= words.len() {
+                return None;
+            }
+            word = words[w];
+        }
+    }
+
+    /// Returns the index of the lowest clear bit at or after `from`, skipping
+    /// regions hinted full
+    pub fn next_clear_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.bits.len() {
+            return None;
+        }
+
+        let words = &self.bits.bits;
+        let mut w = get_word_offset(from);
+        let mut word = !words[w] & !get_low_mask(get_bit_offset(from));
+        loop {
+            if word != 0 {
+                let i = w * BITS_PER_WORD + word.trailing_zeros() as usize;
+                return Some(i).filter(|&i| i < self.bits.len());
+            }
+
+            w += 1;
+            while w < words.len() && w.is_multiple_of(self.region_words) && self.full.test(w / self.region_words) {
+                w += self.region_words;
+            }
+            if w >= words.len() {
+                return None;
+            }
+            word = !words[w];
+        }
+    }
+
+    /// drops the hints of the region holding bit `i`
+    fn invalidate(&mut self, i: usize) {
+        let region = get_word_offset(i) / self.region_words;
+        self.empty.remove(region);
+        self.full.remove(region);
+        self.stale.set(region);
+    }
+}
+
+impl From<DenseBitSet> for HintedBitSet {
+    fn from(bits: DenseBitSet) -> HintedBitSet {
+        HintedBitSet::new(bits)
+    }
+}
+
+// HintedBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn searches_agree_with_plain_set() {
+        let indices = vec![3, 64 * 70, 64 * 70 + 1, 64 * 300 + 5];
+        let plain = DenseBitSet::from_indices(64 * 400, indices.clone());
+        let mut hinted = HintedBitSet::with_region_words(plain.clone(), 16);
+
+        for from in (0..plain.len()).step_by(97) {
+            assert_eq!(hinted.next_set_bit(from), plain.next_set_bit(from));
+        }
+
+        // stale hints stay correct
+        hinted.set(64 * 200);
+        hinted.remove(3);
+        assert_eq!(hinted.stale_regions(), 2);
+        assert_eq!(hinted.next_set_bit(0), Some(64 * 70));
+        assert_eq!(hinted.next_set_bit(64 * 70 + 2), Some(64 * 200));
+
+        hinted.refresh_hints();
+        assert_eq!(hinted.stale_regions(), 0);
+        assert_eq!(hinted.next_set_bit(64 * 70 + 2), Some(64 * 200));
+    }
+
+    #[test]
+    fn can_skip_full_regions() {
+        let mut plain = DenseBitSet::from_vec_masked(vec![!0; 100], 100 * BITS_PER_WORD - 3);
+        plain.remove(80 * BITS_PER_WORD + 7);
+        let hinted = HintedBitSet::with_region_words(plain.clone(), 8);
+
+        for from in (0..plain.len()).step_by(61) {
+            assert_eq!(hinted.next_clear_bit(from), plain.next_clear_bit(from));
+        }
+        assert_eq!(hinted.next_clear_bit(0), Some(80 * BITS_PER_WORD + 7));
+        assert_eq!(hinted.next_clear_bit(80 * BITS_PER_WORD + 8), None);
+    }
+
+    #[test]
+    fn can_hint_full_regions() {
+        let mut bs = DenseBitSet::with_capacity(BITS_PER_WORD * 10);
+        bs.set_range(..BITS_PER_WORD * 4);
+        let hinted = HintedBitSet::with_region_words(bs, 4);
+
+        assert!(hinted.full.test(0));
+        assert!(!hinted.full.test(1));
+        assert!(hinted.empty.test(1));
+    }
+}
@@ -0,0 +1,320 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `HybridBitSet`, switching between dense and sparse storage by density
+
+use { get_word_offset, BitSet, DenseBitSet, SparseBitSet, BITS_PER_WORD };
+
+/// estimated words of memory per word stored in a `SparseBitSet`, covering
+/// the key, the value and hash table slack
+const SPARSE_WORD_COST: usize = 4;
+
+#[derive(Clone, Debug)]
+enum Repr {
+    Dense(DenseBitSet),
+    Sparse(SparseBitSet),
+}
+
+/// A bit set that picks dense or sparse storage on its own.
+///
+/// The set tracks how many of its words are nonzero. It converts to dense
+/// storage once the sparse form would take more memory than the dense
+/// one, and back to sparse once it would take less than half. The gap
+/// between the two thresholds keeps a set hovering near the boundary from
+/// converting on every write.
+///
+/// Equality ignores storage: a dense and a sparse set holding the same
+/// bits are equal.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ BitSet, HybridBitSet };
+///
+/// let mut set = HybridBitSet::new(1 << 12);
+/// set.set(100);
+/// assert!(!set.is_dense());
+///
+/// for i in (0..1 << 12).step_by(50) {
+///     set.set(i);
+/// }
+/// assert!(set.is_dense());
+/// ```
+#[derive(Clone, Debug)]
+pub struct HybridBitSet {
+    repr: Repr,
+    nonzero_words: usize,
+}
+
+impl HybridBitSet {
+    /// Creates an empty set of `num_bits` bits, stored sparsely
+    pub fn new(num_bits: usize) -> HybridBitSet {
+        HybridBitSet { repr: Repr::Sparse(SparseBitSet::new(num_bits)), nonzero_words: 0 }
+    }
+
+    /// Returns whether the set is currently stored densely
+    pub fn is_dense(&self) -> bool {
+        match self.repr {
+            Repr::Dense(_) => true,
+            Repr::Sparse(_) => false,
+        }
+    }
+
+    /// Returns the number of bits the set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self.repr {
+            Repr::Dense(ref bits) => bits.len(),
+            Repr::Sparse(ref bits) => bits.len(),
+        }
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        match self.repr {
+            Repr::Dense(ref bits) => bits.test(i),
+            Repr::Sparse(ref bits) => bits.test(i),
+        }
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        let changed = match self.repr {
+            Repr::Dense(ref mut bits) => {
+                self.nonzero_words += (bits.bits[get_word_offset(i)] == 0) as usize;
+                bits.set(i)
+            }
+            Repr::Sparse(ref mut bits) => {
+                let before = bits.stored_words();
+                let changed = bits.set(i);
+                self.nonzero_words += bits.stored_words() - before;
+                changed
+            }
+        };
+        self.rebalance();
+        changed
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        let changed = match self.repr {
+            Repr::Dense(ref mut bits) => {
+                let changed = bits.remove(i);
+                self.nonzero_words -= (changed && bits.bits[get_word_offset(i)] == 0) as usize;
+                changed
+            }
+            Repr::Sparse(ref mut bits) => {
+                let before = bits.stored_words();
+                let changed = bits.remove(i);
+                self.nonzero_words -= before - bits.stored_words();
+                changed
+            }
+        };
+        self.rebalance();
+        changed
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        match self.repr {
+            Repr::Dense(ref bits) => bits.count_ones(),
+            Repr::Sparse(ref bits) => bits.count_ones(),
+        }
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    pub fn next_set_bit(&self, from: usize) -> Option<usize> {
+        match self.repr {
+            Repr::Dense(ref bits) => bits.next_set_bit(from),
+            Repr::Sparse(ref bits) => bits.next_set_bit(from),
+        }
+    }
+
+    /// Returns the indices of the set bits in ascending order
+    pub fn to_indices(&self) -> Vec<usize> {
+        match self.repr {
+            Repr::Dense(ref bits) => bits.to_indices(),
+            Repr::Sparse(ref bits) => bits.to_indices(),
+        }
+    }
+
+    /// Returns the set as a `DenseBitSet`, converting if it is sparse
+    pub fn into_dense(self) -> DenseBitSet {
+        match self.repr {
+            Repr::Dense(bits) => bits,
+            Repr::Sparse(bits) => bits.to_dense(),
+        }
+    }
+
+    /// converts to whichever storage the current density calls for
+    fn rebalance(&mut self) {
+        let words = self.len().div_ceil(BITS_PER_WORD);
+        let sparse_cost = self.nonzero_words * SPARSE_WORD_COST;
+
+        let repr = match self.repr {
+            Repr::Sparse(ref bits) if sparse_cost > words => Repr::Dense(bits.to_dense()),
+            Repr::Dense(ref bits) if sparse_cost * 2 < words => Repr::Sparse(SparseBitSet::from(bits)),
+            _ => return,
+        };
+        self.repr = repr;
+    }
+}
+
+impl PartialEq for HybridBitSet {
+    fn eq(&self, other: &HybridBitSet) -> bool {
+        if self.len() != other.len() || self.nonzero_words != other.nonzero_words {
+            return false;
+        }
+        match (&self.repr, &other.repr) {
+            (Repr::Dense(a), Repr::Dense(b)) => a == b,
+            (Repr::Sparse(a), Repr::Sparse(b)) => a == b,
+            (Repr::Dense(dense), Repr::Sparse(sparse)) | (Repr::Sparse(sparse), Repr::Dense(dense)) => {
+                dense.count_ones() == sparse.count_ones() && sparse.to_indices().into_iter().all(|i| dense.test(i))
+            }
+        }
+    }
+}
+
+impl Eq for HybridBitSet {}
+
+impl From<DenseBitSet> for HybridBitSet {
+    fn from(bits: DenseBitSet) -> HybridBitSet {
+        let nonzero_words = bits.bits.iter().filter(|&&word| word != 0).count();
+        let mut set = HybridBitSet { repr: Repr::Dense(bits), nonzero_words };
+        set.rebalance();
+        set
+    }
+}
+
+impl BitSet for HybridBitSet {
+    fn len(&self) -> usize {
+        HybridBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        HybridBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        HybridBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        HybridBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        HybridBitSet::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        HybridBitSet::next_set_bit(self, from)
+    }
+
+    fn to_indices(&self) -> Vec<usize> {
+        HybridBitSet::to_indices(self)
+    }
+}
+
+// HybridBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn switches_with_hysteresis() {
+        let words = 64;
+        let mut set = HybridBitSet::new(words * BITS_PER_WORD);
+
+        // dense once more than words / SPARSE_WORD_COST words are occupied
+        for w in 0..=words / SPARSE_WORD_COST {
+            assert!(!set.is_dense());
+            set.set(w * BITS_PER_WORD);
+        }
+        assert!(set.is_dense());
+
+        // and sparse again only below half of that
+        let low = words / (2 * SPARSE_WORD_COST) - 1;
+        for w in (low..=words / SPARSE_WORD_COST).rev() {
+            assert!(set.is_dense());
+            set.remove(w * BITS_PER_WORD);
+        }
+        assert!(!set.is_dense());
+        assert_eq!(set.to_indices(), (0..low).map(|w| w * BITS_PER_WORD).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn matches_dense_across_conversions() {
+        let len = 40 * BITS_PER_WORD;
+        let mut hybrid = HybridBitSet::new(len);
+        let mut dense = DenseBitSet::from_vec_masked(vec![0; 40], len);
+
+        for step in 0..2000 {
+            let i = (step * 7919) % len;
+            if step % 3 == 0 {
+                assert_eq!(hybrid.remove(i), dense.remove(i));
+            } else {
+                assert_eq!(hybrid.set(i), dense.set(i));
+            }
+            assert_eq!(hybrid.count_ones(), dense.count_ones());
+        }
+        assert_eq!(hybrid.next_set_bit(100), dense.next_set_bit(100));
+        assert_eq!(hybrid.into_dense(), dense);
+    }
+
+    #[test]
+    fn equality_ignores_storage() {
+        let words = 64;
+        let mut dense = HybridBitSet::new(words * BITS_PER_WORD);
+        for w in 0..=words / SPARSE_WORD_COST {
+            dense.set(w * BITS_PER_WORD);
+        }
+        // still dense thanks to hysteresis
+        for w in 8..=words / SPARSE_WORD_COST {
+            dense.remove(w * BITS_PER_WORD);
+        }
+        assert!(dense.is_dense());
+
+        let mut sparse = HybridBitSet::new(words * BITS_PER_WORD);
+        for w in 0..8 {
+            sparse.set(w * BITS_PER_WORD);
+        }
+        assert!(!sparse.is_dense());
+
+        assert_eq!(dense, sparse);
+        assert_eq!(sparse, dense);
+        sparse.set(1);
+        assert_ne!(dense, sparse);
+        assert_ne!(sparse, dense);
+    }
+
+    #[test]
+    fn picks_storage_on_conversion() {
+        let full = DenseBitSet::with_capacity_and_state(1024, !0);
+        assert!(HybridBitSet::from(full).is_dense());
+        assert!(!HybridBitSet::from(DenseBitSet::from_indices(1024, vec![3])).is_dense());
+    }
+}
@@ -0,0 +1,312 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! An allocator of integer IDs with configurable reuse policies
+
+use std::collections::VecDeque;
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// When an `IdAllocator` may hand out a freed ID again
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ReusePolicy {
+    /// reuse the most recently freed ID first, keeping the ID space dense
+    Lifo,
+    /// reuse the oldest freed ID, and only once at least this many IDs
+    /// have been freed after it, so stale references to it go quiet first
+    FifoAfterQuarantine(usize),
+    /// never reuse an ID; new IDs always come from the high-water mark
+    Never,
+}
+
+/// Counters describing an `IdAllocator`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IdAllocatorStats {
+    /// IDs currently allocated
+    pub live: usize,
+    /// one past the highest ID ever handed out, less any compacted away
+    pub high_water: usize,
+    /// freed IDs that may be handed out again
+    pub reusable: usize,
+    /// freed IDs that may not be handed out yet, or ever under `Never`
+    pub tombstones: usize,
+    /// calls to `allocate` that succeeded
+    pub allocations: u64,
+    /// allocations that handed out a previously freed ID
+    pub reuses: u64,
+    /// calls to `free` that released a live ID
+    pub frees: u64,
+}
+
+/// Hands out `usize` IDs, tracking live IDs and tombstones in bit sets.
+///
+/// IDs are stable: a live ID is never renumbered, and compaction only
+/// gives up free IDs at the top of the range. Freed IDs are reused
+/// according to the `ReusePolicy`; IDs that may not be reused yet are
+/// tombstones.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ IdAllocator, ReusePolicy };
+///
+/// let mut ids = IdAllocator::new(ReusePolicy::FifoAfterQuarantine(1));
+/// let (a, b, c) = (ids.allocate(), ids.allocate(), ids.allocate());
+///
+/// ids.free(a);
+/// assert!(ids.is_tombstone(a));
+/// assert_eq!(ids.allocate(), 3);
+///
+/// // freeing b ends a's quarantine
+/// ids.free(b);
+/// assert_eq!(ids.allocate(), a);
+/// assert!(ids.is_live(c));
+/// ```
+#[derive(Clone, Debug)]
+pub struct IdAllocator {
+    policy: ReusePolicy,
+    live: DenseBitSet,
+    tombstones: DenseBitSet,
+    /// freed IDs in the order they were freed
+    freed: VecDeque<usize>,
+    stats: IdAllocatorStats,
+}
+
+impl IdAllocator {
+    /// Creates an allocator with no IDs handed out
+    pub fn new(policy: ReusePolicy) -> IdAllocator {
+        IdAllocator {
+            policy,
+            live: DenseBitSet::with_capacity(0),
+            tombstones: DenseBitSet::with_capacity(0),
+            freed: VecDeque::new(),
+            stats: IdAllocatorStats::default(),
+        }
+    }
+
+    /// Returns the reuse policy
+    pub fn policy(&self) -> ReusePolicy {
+        self.policy
+    }
+
+    /// Returns whether `id` is allocated
+    pub fn is_live(&self, id: usize) -> bool {
+        id < self.stats.high_water && self.live.test(id)
+    }
+
+    /// Returns whether `id` was freed and may not be handed out yet
+    pub fn is_tombstone(&self, id: usize) -> bool {
+        id < self.stats.high_water && self.tombstones.test(id)
+    }
+
+    /// Borrows the set of live IDs
+    pub fn live(&self) -> &DenseBitSet {
+        &self.live
+    }
+
+    /// Returns the allocator's counters
+    pub fn stats(&self) -> IdAllocatorStats {
+        self.stats
+    }
+
+    /// Hands out an ID: a reusable freed ID if the policy allows one,
+    /// otherwise the next ID above the high-water mark
+    pub fn allocate(&mut self) -> usize {
+        let reused = match self.policy {
+            ReusePolicy::Lifo => self.freed.pop_back(),
+            ReusePolicy::FifoAfterQuarantine(_) if self.stats.reusable > 0 => self.freed.pop_front(),
+            _ => None,
+        };
+
+        let id = match reused {
+            Some(id) => {
+                self.stats.reusable -= 1;
+                self.stats.reuses += 1;
+                id
+            }
+            None => {
+                let id = self.stats.high_water;
+                self.stats.high_water += 1;
+                self.live.grow_to(id + 1);
+                self.tombstones.grow_to(id + 1);
+                id
+            }
+        };
+        self.live.set(id);
+        self.stats.live += 1;
+        self.stats.allocations += 1;
+        id
+    }
+
+    /// Frees `id`. Returns false, doing nothing, if `id` is not live.
+    pub fn free(&mut self, id: usize) -> bool {
+        if !self.is_live(id) {
+            return false;
+        }
+        self.live.remove(id);
+        self.stats.live -= 1;
+        self.stats.frees += 1;
+
+        match self.policy {
+            ReusePolicy::Lifo => {
+                self.freed.push_back(id);
+                self.stats.reusable += 1;
+            }
+            ReusePolicy::FifoAfterQuarantine(quarantine) => {
+                self.freed.push_back(id);
+                self.tombstones.set(id);
+                self.stats.tombstones += 1;
+                // the ID freed `quarantine` frees before this one is now clear
+                if self.freed.len() > quarantine + self.stats.reusable {
+                    let released = self.freed[self.stats.reusable];
+                    self.tombstones.remove(released);
+                    self.stats.tombstones -= 1;
+                    self.stats.reusable += 1;
+                }
+            }
+            ReusePolicy::Never => {
+                self.tombstones.set(id);
+                self.stats.tombstones += 1;
+            }
+        }
+        true
+    }
+
+    /// Lowers the high-water mark past every reusable ID at the top of the
+    /// range and shrinks the tracking sets to match. Live IDs and
+    /// tombstones are kept, so no ID is renumbered and no quarantine is
+    /// cut short. Returns the number of IDs given up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ IdAllocator, ReusePolicy };
+    ///
+    /// let mut ids = IdAllocator::new(ReusePolicy::Lifo);
+    /// let all: Vec<usize> = (0..100).map(|_| ids.allocate()).collect();
+    /// for &id in &all[10..] {
+    ///     ids.free(id);
+    /// }
+    ///
+    /// assert_eq!(ids.compact(), 90);
+    /// assert_eq!(ids.stats().high_water, 10);
+    /// assert_eq!(ids.allocate(), 10);
+    /// ```
+    pub fn compact(&mut self) -> usize {
+        let old = self.stats.high_water;
+        let mut top = old;
+        while top > 0 && !self.live.test(top - 1) && !self.tombstones.test(top - 1) {
+            top -= 1;
+        }
+        if top == old {
+            return 0;
+        }
+
+        // reusable IDs sit at the front of `freed`, ahead of the quarantined ones
+        let reusable = self.stats.reusable;
+        let quarantined = self.freed.split_off(reusable);
+        self.freed.retain(|&id| id < top);
+        self.stats.reusable = self.freed.len();
+        self.freed.extend(quarantined);
+
+        self.stats.high_water = top;
+        let num_bits = top.div_ceil(BITS_PER_WORD) * BITS_PER_WORD;
+        for set in [&mut self.live, &mut self.tombstones] {
+            set.truncate(num_bits);
+            set.shrink_to_fit();
+        }
+        old - top
+    }
+}
+
+// IdAllocator TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn lifo_reuses_latest() {
+        let mut ids = IdAllocator::new(ReusePolicy::Lifo);
+        for _ in 0..5 {
+            ids.allocate();
+        }
+        assert!(ids.free(1));
+        assert!(ids.free(3));
+        assert!(!ids.free(3));
+        assert!(!ids.free(99));
+
+        assert_eq!(ids.allocate(), 3);
+        assert_eq!(ids.allocate(), 1);
+        assert_eq!(ids.allocate(), 5);
+
+        let stats = ids.stats();
+        assert_eq!((stats.live, stats.high_water, stats.reusable, stats.tombstones), (6, 6, 0, 0));
+        assert_eq!((stats.allocations, stats.reuses, stats.frees), (8, 2, 2));
+    }
+
+    #[test]
+    fn fifo_waits_out_quarantine() {
+        let mut ids = IdAllocator::new(ReusePolicy::FifoAfterQuarantine(2));
+        let all: Vec<usize> = (0..6).map(|_| ids.allocate()).collect();
+        for &id in &all[..3] {
+            ids.free(id);
+        }
+        assert_eq!(ids.stats().reusable, 1);
+        assert!(!ids.is_tombstone(0) && ids.is_tombstone(1) && ids.is_tombstone(2));
+
+        assert_eq!(ids.allocate(), 0);
+        assert_eq!(ids.allocate(), 6);
+        ids.free(5);
+        assert_eq!(ids.allocate(), 1);
+    }
+
+    #[test]
+    fn never_retires_ids() {
+        let mut ids = IdAllocator::new(ReusePolicy::Never);
+        let a = ids.allocate();
+        ids.free(a);
+
+        assert_eq!(ids.allocate(), 1);
+        assert!(ids.is_tombstone(a));
+        assert_eq!(ids.compact(), 0);
+        assert_eq!(ids.stats().tombstones, 1);
+    }
+
+    #[test]
+    fn compaction_keeps_tombstones_and_live_ids() {
+        let mut ids = IdAllocator::new(ReusePolicy::FifoAfterQuarantine(1));
+        let all: Vec<usize> = (0..200).map(|_| ids.allocate()).collect();
+        for &id in &all[50..] {
+            ids.free(id);
+        }
+        // 199 is the last freed, still quarantined
+        assert_eq!(ids.compact(), 0);
+
+        ids.free(0);
+        assert_eq!(ids.compact(), 150);
+        assert_eq!(ids.stats().high_water, 50);
+        assert!(ids.is_tombstone(0) && ids.is_live(49));
+        assert_eq!(ids.stats().reusable, 0);
+        assert_eq!(ids.allocate(), 50);
+    }
+}
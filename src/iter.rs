@@ -0,0 +1,759 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Iterators over `DenseBitSet` and the std collection traits built on them
+
+use std::iter::{ ExactSizeIterator, FromIterator, Iterator, Peekable };
+use std::ops::Range;
+
+use { get_bit_offset, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+impl DenseBitSet {
+    /// Returns an iterator over the indices of the set bits, in ascending
+    /// order. Words are scanned whole, so sparse sets are cheap to walk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b10010);
+    /// let ones: Vec<usize> = bs.ones().collect();
+    ///
+    /// assert_eq!(ones, vec![1, 4]);
+    /// ```
+    pub fn ones(&self) -> DenseOnesIterator<'_> {
+        DenseOnesIterator::new(&self.bits, self.num_bits)
+    }
+
+    /// Returns an iterator over the maximal runs of consecutive set bits,
+    /// in ascending order. Runs are found by jumping between set and clear
+    /// bits a word at a time, so long runs cost one read per word.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(200, vec![1, 2, 3, 10, 63, 64, 65, 199]);
+    ///
+    /// assert_eq!(bs.runs().collect::<Vec<_>>(), vec![1..4, 10..11, 63..66, 199..200]);
+    /// ```
+    pub fn runs(&self) -> DenseRunsIterator<'_> {
+        DenseRunsIterator::new(self, true)
+    }
+
+    /// Returns an iterator over the indices `i` of the set bits with
+    /// `i % step == phase`, in ascending order. For steps up to the word
+    /// size each word is masked with a precomputed stride mask, so the
+    /// filtered-out bits are never decoded; larger steps test only the
+    /// matching indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero or `phase >= step`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// // sample flags of four interleaved channels
+    /// let flags = DenseBitSet::from_indices(64, vec![0, 1, 5, 9, 10, 13]);
+    ///
+    /// assert_eq!(flags.ones_matching(4, 1).collect::<Vec<_>>(), vec![1, 5, 9, 13]);
+    /// ```
+    pub fn ones_matching(&self, step: usize, phase: usize) -> DenseOnesMatching<'_> {
+        assert!(step > 0, "step must be non-zero");
+        assert!(phase < step, "phase {} must be less than step {}", phase, step);
+
+        let masks: Vec<usize> = if step <= BITS_PER_WORD {
+            // the mask pattern repeats every lcm(step, BITS_PER_WORD) bits
+            let mut period = step;
+            while !period.is_multiple_of(BITS_PER_WORD) {
+                period += step;
+            }
+            (0..period / BITS_PER_WORD)
+                .map(|w| {
+                    let first = (phase + step - (w * BITS_PER_WORD) % step) % step;
+                    (first..BITS_PER_WORD).step_by(step).fold(0, |mask, j| mask | 1 << j)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let current = match (self.bits.first(), masks.first()) {
+            (Some(word), Some(mask)) => word & mask,
+            _ => 0,
+        };
+        DenseOnesMatching { bits: self, step, masks, word_idx: 0, current, next: phase }
+    }
+
+    /// Returns an iterator over groups of set bits, splitting wherever more
+    /// than `max_gap` clear bits separate two set bits. Each group is a
+    /// `Vec` of indices in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// // event timestamps, clustered when at most 2 ticks apart
+    /// let events = DenseBitSet::from_indices(100, vec![3, 4, 7, 20, 22, 60]);
+    /// let groups: Vec<Vec<usize>> = events.ones_grouped_by_gap(2).collect();
+    ///
+    /// assert_eq!(groups, vec![vec![3, 4, 7], vec![20, 22], vec![60]]);
+    /// ```
+    pub fn ones_grouped_by_gap(&self, max_gap: usize) -> DenseOnesGroups<'_> {
+        DenseOnesGroups { ones: self.ones().peekable(), max_gap }
+    }
+
+    /// Returns an iterator over the indices of the set bits for which
+    /// `predicate` returns true, in ascending order. Clear bits are skipped
+    /// a word at a time and never reach the predicate, and nothing is
+    /// decoded past the point where the caller stops iterating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let active = DenseBitSet::from_indices(64, vec![1, 4, 9, 16, 25]);
+    /// let ages: Vec<u32> = (0..64).map(|i| i * 3).collect();
+    ///
+    /// let mut adults = active.ones_where(|i| ages[i] >= 18);
+    /// assert_eq!(adults.next(), Some(9));
+    /// assert_eq!(adults.collect::<Vec<_>>(), vec![16, 25]);
+    /// ```
+    pub fn ones_where<P: FnMut(usize) -> bool>(&self, predicate: P) -> DenseOnesWhere<'_, P> {
+        DenseOnesWhere { ones: self.ones(), predicate }
+    }
+
+    /// Returns the number of set bits for which `predicate` returns true
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(128, 0..100);
+    /// assert_eq!(bs.count_ones_where(|i| i % 7 == 0), 15);
+    /// ```
+    pub fn count_ones_where<P: FnMut(usize) -> bool>(&self, predicate: P) -> usize {
+        self.ones_where(predicate).count()
+    }
+
+    /// Returns an iterator over the indices set in exactly one of `self`
+    /// and `other`, in ascending order. Each pair of words is xored and
+    /// scanned on the fly, so no intermediate set is built. As with the
+    /// inclusion relations, the shorter set reads as zero past its end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let before = DenseBitSet::from_indices(128, vec![3, 64, 100]);
+    /// let after = DenseBitSet::from_indices(256, vec![3, 65, 100, 200]);
+    ///
+    /// assert_eq!(before.symmetric_difference(&after).collect::<Vec<_>>(), vec![64, 65, 200]);
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a DenseBitSet) -> DenseSymmetricDifference<'a> {
+        let mut iter = DenseSymmetricDifference { a: &self.bits, b: &other.bits, word_idx: 0, current: 0 };
+        iter.current = iter.word(0);
+        iter
+    }
+
+    /// Returns a cursor handing out the indices of the set bits in batches
+    /// of up to `chunk_size`, decoded into a buffer that is reused between
+    /// batches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b1011101);
+    /// let mut chunks = bs.ones_chunks(2);
+    ///
+    /// assert_eq!(chunks.next_chunk(), Some(&[0, 2][..]));
+    /// assert_eq!(chunks.next_chunk(), Some(&[3, 4][..]));
+    /// assert_eq!(chunks.next_chunk(), Some(&[6][..]));
+    /// assert_eq!(chunks.next_chunk(), None);
+    /// ```
+    pub fn ones_chunks(&self, chunk_size: usize) -> DenseOnesChunks<'_> {
+        assert!(chunk_size > 0, "chunk_size must be non-zero");
+
+        DenseOnesChunks {
+            ones: self.ones(),
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size),
+        }
+    }
+}
+
+/// An iterator for DenseBitSet
+/// Allows the caller to iterate over each bit as a bool
+#[derive(Clone, Eq, PartialEq)]
+pub struct DenseBitIterator<'a> {
+    collection: &'a DenseBitSet,
+    index: usize
+}
+
+impl<'a> Iterator for DenseBitIterator<'a> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.collection.len() {
+            let result = self.collection.test(self.index);
+            self.index += 1;
+
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for DenseBitIterator<'a> {
+    /// The size of the bitset is known at the time of creation
+    fn len(&self) -> usize {
+        self.collection.len()
+    }
+}
+
+impl<'a> IntoIterator for &'a DenseBitSet {
+    type Item = bool;
+    type IntoIter = DenseBitIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        DenseBitIterator {
+            collection: self,
+            index: 0
+        }
+    }
+}
+
+/// An iterator over the indices of the set bits of a DenseBitSet
+#[derive(Clone, Eq, PartialEq)]
+pub struct DenseOnesIterator<'a> {
+    /// the words from the one holding the first bit iterated over
+    bits: &'a [usize],
+    /// offset of the first bit iterated over within `bits[0]`
+    shift: usize,
+    num_bits: usize,
+    word_idx: usize,
+    current: usize,
+}
+
+impl<'a> DenseOnesIterator<'a> {
+    /// iterates over the set bits among the first `num_bits` bits of `bits`
+    pub(crate) fn new(bits: &'a [usize], num_bits: usize) -> DenseOnesIterator<'a> {
+        DenseOnesIterator::over_range(bits, 0, num_bits)
+    }
+
+    /// iterates over the set bits among bits `start..start + num_bits` of
+    /// `bits`, renumbered from 0
+    pub(crate) fn over_range(bits: &'a [usize], start: usize, num_bits: usize) -> DenseOnesIterator<'a> {
+        let end = (start + num_bits).div_ceil(BITS_PER_WORD).min(bits.len());
+        let bits = &bits[get_word_offset(start).min(end)..end];
+        let mut iter = DenseOnesIterator { bits, shift: get_bit_offset(start), num_bits, word_idx: 0, current: 0 };
+        iter.current = iter.load(0);
+        iter
+    }
+
+    /// word `w` of the iterated bits, as if they started on a word boundary
+    #[inline]
+    fn load(&self, w: usize) -> usize {
+        let low = self.bits.get(w).map_or(0, |&word| word >> self.shift);
+        match self.bits.get(w + 1) {
+            Some(&high) if self.shift != 0 => low | high << (BITS_PER_WORD - self.shift),
+            _ => low,
+        }
+    }
+}
+
+impl<'a> Iterator for DenseOnesIterator<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            self.word_idx += 1;
+            if self.word_idx >= self.bits.len() {
+                return None;
+            }
+            self.current = self.load(self.word_idx);
+        }
+
+        let i = self.word_idx * BITS_PER_WORD + self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+
+        if i < self.num_bits {
+            Some(i)
+        } else {
+            self.current = 0;
+            None
+        }
+    }
+}
+
+/// An iterator over the maximal runs of set, or of clear, bits of a DenseBitSet
+#[derive(Clone, Debug)]
+pub struct DenseRunsIterator<'a> {
+    bits: &'a DenseBitSet,
+    pos: usize,
+    ones: bool,
+}
+
+impl<'a> DenseRunsIterator<'a> {
+    /// iterates over the runs of set bits, or of clear bits if `ones` is false
+    pub(crate) fn new(bits: &'a DenseBitSet, ones: bool) -> DenseRunsIterator<'a> {
+        DenseRunsIterator { bits, pos: 0, ones }
+    }
+}
+
+impl<'a> Iterator for DenseRunsIterator<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = if self.ones {
+            let start = self.bits.next_set_bit(self.pos)?;
+            (start, self.bits.next_clear_bit(start))
+        } else {
+            let start = self.bits.next_clear_bit(self.pos)?;
+            (start, self.bits.next_set_bit(start))
+        };
+
+        let end = end.unwrap_or(self.bits.len());
+        self.pos = end;
+        Some(start..end)
+    }
+}
+
+/// An iterator over the indices where two DenseBitSets differ
+#[derive(Clone, Debug)]
+pub struct DenseSymmetricDifference<'a> {
+    a: &'a [usize],
+    b: &'a [usize],
+    word_idx: usize,
+    current: usize,
+}
+
+impl<'a> DenseSymmetricDifference<'a> {
+    fn word(&self, w: usize) -> usize {
+        self.a.get(w).cloned().unwrap_or(0) ^ self.b.get(w).cloned().unwrap_or(0)
+    }
+}
+
+impl<'a> Iterator for DenseSymmetricDifference<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let words = self.a.len().max(self.b.len());
+        while self.current == 0 {
+            self.word_idx += 1;
+            if self.word_idx >= words {
+                return None;
+            }
+            self.current = self.word(self.word_idx);
+        }
+
+        let i = self.word_idx * BITS_PER_WORD + self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(i)
+    }
+}
+
+/// An iterator over the indices of the set bits of a DenseBitSet that fall
+/// on a given stride
+#[derive(Clone, Debug)]
+pub struct DenseOnesMatching<'a> {
+    bits: &'a DenseBitSet,
+    step: usize,
+    /// one stride mask per word of the repeating pattern, or empty when
+    /// the step is longer than a word
+    masks: Vec<usize>,
+    word_idx: usize,
+    current: usize,
+    /// the next candidate index when there are no masks
+    next: usize,
+}
+
+impl<'a> Iterator for DenseOnesMatching<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.masks.is_empty() {
+            while self.next < self.bits.len() {
+                let i = self.next;
+                self.next += self.step;
+                if self.bits.test(i) {
+                    return Some(i);
+                }
+            }
+            return None;
+        }
+
+        while self.current == 0 {
+            self.word_idx += 1;
+            if self.word_idx >= self.bits.bits.len() {
+                return None;
+            }
+            self.current = self.bits.bits[self.word_idx] & self.masks[self.word_idx % self.masks.len()];
+        }
+
+        let i = self.word_idx * BITS_PER_WORD + self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(i)
+    }
+}
+
+/// An iterator over groups of nearby set bits of a DenseBitSet
+#[derive(Clone)]
+pub struct DenseOnesGroups<'a> {
+    ones: Peekable<DenseOnesIterator<'a>>,
+    max_gap: usize,
+}
+
+impl<'a> Iterator for DenseOnesGroups<'a> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = vec![self.ones.next()?];
+        while let Some(&i) = self.ones.peek() {
+            if i - group[group.len() - 1] - 1 > self.max_gap {
+                break;
+            }
+            group.push(i);
+            self.ones.next();
+        }
+        Some(group)
+    }
+}
+
+/// An iterator over the set bits of a DenseBitSet that satisfy a predicate
+#[derive(Clone)]
+pub struct DenseOnesWhere<'a, P> {
+    ones: DenseOnesIterator<'a>,
+    predicate: P,
+}
+
+impl<'a, P: FnMut(usize) -> bool> Iterator for DenseOnesWhere<'a, P> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &mut self.predicate;
+        self.ones.find(|&i| predicate(i))
+    }
+}
+
+/// Batches the indices of the set bits of a DenseBitSet.
+///
+/// This is not an `Iterator`: each batch borrows the internal buffer, which
+/// is overwritten by the following call to `next_chunk()`.
+#[derive(Clone)]
+pub struct DenseOnesChunks<'a> {
+    ones: DenseOnesIterator<'a>,
+    chunk_size: usize,
+    buffer: Vec<usize>,
+}
+
+impl<'a> DenseOnesChunks<'a> {
+    /// Decodes the next batch of indices. Every batch but the last holds
+    /// exactly `chunk_size` indices; returns `None` once all are consumed.
+    pub fn next_chunk(&mut self) -> Option<&[usize]> {
+        self.buffer.clear();
+        self.buffer.extend(self.ones.by_ref().take(self.chunk_size));
+
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(&self.buffer)
+        }
+    }
+}
+
+/// Collects bit indices into a set just large enough to hold the
+/// largest index.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = vec![1, 5, 70].into_iter().collect();
+///
+/// assert!(bs.test(5));
+/// assert!(bs.test(70));
+/// assert!(bs.len() >= 71);
+/// ```
+impl FromIterator<usize> for DenseBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let indices: Vec<usize> = iter.into_iter().collect();
+        let num_bits = indices.iter().max().map_or(0, |max| max + 1);
+
+        DenseBitSet::from_indices(num_bits, indices)
+    }
+}
+
+/// Collects positional bit values, bit `i` taking the value of the `i`th
+/// item of the iterator. The set is exactly as long as the iterator.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = vec![true, false, true].into_iter().collect();
+///
+/// assert_eq!(bs.len(), 3);
+/// assert!(bs.test(0));
+/// assert!(!bs.test(1));
+/// assert!(bs.test(2));
+/// ```
+impl FromIterator<bool> for DenseBitSet {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bs = DenseBitSet::with_capacity(0);
+        bs.extend(iter);
+        bs
+    }
+}
+
+/// Sets every index produced by the iterator, growing the set as needed.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let mut bs = DenseBitSet::with_capacity(64);
+/// bs.extend(vec![3, 200]);
+///
+/// assert!(bs.test(3));
+/// assert!(bs.test(200));
+/// ```
+impl Extend<usize> for DenseBitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for i in iter {
+            self.set_growing(i);
+        }
+    }
+}
+
+/// Appends positional bit values starting at `len()`, as repeated `push`
+/// calls would.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let mut bs = DenseBitSet::with_capacity(64);
+/// bs.extend(vec![true, false, true]);
+///
+/// assert_eq!(bs.len(), 67);
+/// assert!(bs.test(64));
+/// assert!(!bs.test(65));
+/// assert!(bs.test(66));
+/// ```
+impl Extend<bool> for DenseBitSet {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+// DenseBitSet iterator TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::needless_bool, clippy::useless_conversion)]
+    fn iter_bits() {
+        let bit_pattern: usize = 0b00110100;
+        let bs = DenseBitSet::from_bits(bit_pattern);
+        let expected_values: Vec<bool> = (0..BITS_PER_WORD).map(|i| if (bit_pattern >> i) & 0x01 != 0 { true } else { false }).collect();
+
+        for (expected, bit_is_set) in expected_values.into_iter().zip(bs.into_iter()) {
+            assert_eq!(expected, bit_is_set);
+        }
+    }
+
+
+    #[test]
+    fn iter_bits_known_size() {
+        let bit_pattern: usize = 0b00111010;
+        let bs = DenseBitSet::from_bits(bit_pattern);
+
+        assert_eq!(bs.into_iter().len(), BITS_PER_WORD);
+    }
+
+
+    #[test]
+    fn can_batch_ones() {
+        let bs = DenseBitSet::from_indices(1000, (0..1000).filter(|i| i % 7 == 0));
+        let mut chunks = bs.ones_chunks(16);
+        let mut seen = Vec::new();
+
+        while let Some(chunk) = chunks.next_chunk() {
+            assert!(chunk.len() == 16 || seen.len() + chunk.len() == bs.ones().count());
+            seen.extend_from_slice(chunk);
+        }
+        assert_eq!(seen, bs.to_indices());
+        assert!(DenseBitSet::with_capacity(64).ones_chunks(4).next_chunk().is_none());
+    }
+
+    #[test]
+    fn runs_match_bitwise_scan() {
+        let bs = DenseBitSet::from_indices(300, (0..300).filter(|i| i % 100 < 70 && i % 9 != 0));
+        let mut expected: Vec<Range<usize>> = Vec::new();
+        for i in bs.ones() {
+            match expected.last_mut() {
+                Some(run) if run.end == i => run.end += 1,
+                _ => expected.push(i..i + 1),
+            }
+        }
+
+        assert_eq!(bs.runs().collect::<Vec<_>>(), expected);
+        assert_eq!(DenseBitSet::with_capacity(128).runs().count(), 0);
+    }
+
+    #[test]
+    fn ones_matching_filters_by_stride() {
+        let bs = DenseBitSet::from_indices(1000, (0..1000).filter(|i| i % 3 != 0));
+
+        for &step in &[1, 3, 4, 7, BITS_PER_WORD, BITS_PER_WORD + 1, 300] {
+            for phase in (0..step).step_by(step / 3 + 1) {
+                let expected: Vec<usize> = bs.ones().filter(|i| i % step == phase).collect();
+                assert_eq!(bs.ones_matching(step, phase).collect::<Vec<_>>(), expected);
+            }
+        }
+        assert_eq!(DenseBitSet::with_capacity(0).ones_matching(5, 2).count(), 0);
+    }
+
+    #[test]
+    fn groups_split_on_large_gaps() {
+        let bs = DenseBitSet::from_indices(300, vec![0, 1, 2, 66, 67, 129, 299]);
+
+        assert_eq!(bs.ones_grouped_by_gap(0).collect::<Vec<_>>(), vec![vec![0, 1, 2], vec![66, 67], vec![129], vec![299]]);
+        assert_eq!(bs.ones_grouped_by_gap(62).count(), 3);
+        assert_eq!(bs.ones_grouped_by_gap(63).count(), 2);
+        assert_eq!(bs.ones_grouped_by_gap(300).collect::<Vec<_>>(), vec![bs.to_indices()]);
+        assert_eq!(DenseBitSet::with_capacity(64).ones_grouped_by_gap(1).count(), 0);
+    }
+
+    #[test]
+    fn can_collect_indices() {
+        let bs: DenseBitSet = vec![0, 64, 129].into_iter().collect();
+
+        assert_eq!(bs.words(), 3);
+        assert!(bs.test(0));
+        assert!(bs.test(64));
+        assert!(bs.test(129));
+        assert!(!bs.test(128));
+
+        let empty: DenseBitSet = Vec::<usize>::new().into_iter().collect();
+        assert_eq!(empty.len(), 0);
+    }
+
+
+    #[test]
+    fn can_collect_bools() {
+        let bs: DenseBitSet = (0..100).map(|i| i % 3 == 0).collect();
+
+        assert_eq!(bs.len(), 100);
+        assert_eq!(bs.words(), 2);
+        for i in 0..100 {
+            assert_eq!(bs.test(i), i % 3 == 0);
+        }
+    }
+
+
+    #[test]
+    fn can_extend_with_indices() {
+        let mut bs = DenseBitSet::with_capacity(64);
+        bs.extend(vec![1, 63, 64, 300]);
+
+        assert_eq!(bs.words(), 5);
+        assert!(bs.test(1));
+        assert!(bs.test(63));
+        assert!(bs.test(64));
+        assert!(bs.test(300));
+        assert!(!bs.test(299));
+    }
+
+
+    #[test]
+    fn can_extend_with_bools() {
+        let mut bs = DenseBitSet::from_bits(0b1);
+        bs.extend((0..70).map(|i| i % 2 == 1));
+
+        assert!(bs.test(0));
+        assert_eq!(bs.words(), 3);
+        for i in 0..70 {
+            assert_eq!(bs.test(64 + i), i % 2 == 1);
+        }
+
+        bs.extend(vec![false; 10]);
+        assert_eq!(bs.len(), 144);
+        assert_eq!(bs.words(), 3);
+    }
+
+
+    #[test]
+    fn ones_where_only_sees_set_bits() {
+        let bs = DenseBitSet::from_indices(256, vec![0, 63, 64, 200, 255]);
+        let mut seen = Vec::new();
+        let matched: Vec<usize> = bs.ones_where(|i| { seen.push(i); i % 2 == 0 }).collect();
+
+        assert_eq!(matched, vec![0, 64, 200]);
+        assert_eq!(seen, bs.to_indices());
+
+        let mut calls = 0;
+        assert_eq!(bs.ones_where(|i| { calls += 1; i > 60 }).next(), Some(63));
+        assert_eq!(calls, 2);
+        assert_eq!(bs.count_ones_where(|i| i >= 64), 3);
+    }
+
+
+    #[test]
+    fn symmetric_difference_matches_xor() {
+        let a = DenseBitSet::from_indices(512, (0..512).filter(|i| i % 3 == 0));
+        let b = DenseBitSet::from_indices(512, (0..512).filter(|i| i % 5 == 0));
+
+        assert_eq!(a.symmetric_difference(&b).collect::<Vec<_>>(), a.xor(&b).to_indices());
+        assert_eq!(a.symmetric_difference(&a).next(), None);
+
+        let empty = DenseBitSet::with_capacity(0);
+        assert_eq!(empty.symmetric_difference(&a).count(), a.count_ones());
+        assert_eq!(a.symmetric_difference(&empty).count(), a.count_ones());
+    }
+}
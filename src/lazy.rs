@@ -0,0 +1,240 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Lazily evaluated set algebra
+//!
+//! `a.and_ref(&b).or_ref(&c)` builds a small expression tree of borrowed
+//! operands instead of a set. Nothing is computed until the expression is
+//! written into a target, materialized, counted or iterated, and then every
+//! word of the result is computed in one pass with no intermediate sets.
+
+use { BitWords, DenseBitSet, BITS_PER_WORD };
+
+/// A set expression that can produce any word of its result on demand
+pub trait BitExpr: Sized {
+    /// returns word `i` of the result
+    fn word(&self, i: usize) -> usize;
+
+    /// returns the number of bits in the result
+    fn bit_len(&self) -> usize;
+
+    /// returns the number of words in the result
+    fn word_len(&self) -> usize {
+        self.bit_len().div_ceil(BITS_PER_WORD)
+    }
+
+    /// Lazy `self & rhs`
+    fn and_ref<R: BitExpr>(self, rhs: R) -> LazyAnd<Self, R> {
+        assert!(self.bit_len() == rhs.bit_len());
+        LazyAnd { lhs: self, rhs }
+    }
+
+    /// Lazy `self | rhs`
+    fn or_ref<R: BitExpr>(self, rhs: R) -> LazyOr<Self, R> {
+        assert!(self.bit_len() == rhs.bit_len());
+        LazyOr { lhs: self, rhs }
+    }
+
+    /// Lazy `self ^ rhs`
+    fn xor_ref<R: BitExpr>(self, rhs: R) -> LazyXor<Self, R> {
+        assert!(self.bit_len() == rhs.bit_len());
+        LazyXor { lhs: self, rhs }
+    }
+
+    /// Lazy `self & !rhs`
+    fn and_not_ref<R: BitExpr>(self, rhs: R) -> LazyAndNot<Self, R> {
+        assert!(self.bit_len() == rhs.bit_len());
+        LazyAndNot { lhs: self, rhs }
+    }
+
+    /// Overwrites `target` with the result. Panics if `target` is not
+    /// `bit_len()` bits long.
+    fn eval_into(&self, target: &mut DenseBitSet) {
+        assert!(target.len() == self.bit_len());
+        for (i, word) in target.bits.iter_mut().enumerate() {
+            *word = self.word(i);
+        }
+    }
+
+    /// Returns the result as a new set
+    fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked((0..self.word_len()).map(|i| self.word(i)).collect(), self.bit_len())
+    }
+
+    /// Returns the number of bits set in the result
+    fn count_ones(&self) -> usize {
+        (0..self.word_len()).map(|i| self.word(i).count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices set in the result
+    fn ones(&self) -> LazyOnes<'_, Self> {
+        LazyOnes { expr: self, word_index: 0, current: 0 }
+    }
+}
+
+impl<S: BitWords + ?Sized> BitExpr for &S {
+    fn word(&self, i: usize) -> usize {
+        BitWords::word(*self, i)
+    }
+
+    fn bit_len(&self) -> usize {
+        BitWords::bit_len(*self)
+    }
+}
+
+macro_rules! lazy_op {
+    ($(#[$doc:meta])* $name:ident, |$a:ident, $b:ident| $word:expr) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<L, R> {
+            lhs: L,
+            rhs: R,
+        }
+
+        impl<L: BitExpr, R: BitExpr> BitExpr for $name<L, R> {
+            fn word(&self, i: usize) -> usize {
+                let ($a, $b) = (self.lhs.word(i), self.rhs.word(i));
+                $word
+            }
+
+            fn bit_len(&self) -> usize {
+                self.lhs.bit_len()
+            }
+        }
+    };
+}
+
+lazy_op!(
+    /// A lazy intersection, built by `and_ref`
+    LazyAnd, |a, b| a & b);
+lazy_op!(
+    /// A lazy union, built by `or_ref`
+    LazyOr, |a, b| a | b);
+lazy_op!(
+    /// A lazy symmetric difference, built by `xor_ref`
+    LazyXor, |a, b| a ^ b);
+lazy_op!(
+    /// A lazy difference, built by `and_not_ref`
+    LazyAndNot, |a, b| a & !b);
+
+/// Iterator over the indices set in a lazy expression, computing one word
+/// at a time
+#[derive(Debug)]
+pub struct LazyOnes<'a, E: 'a> {
+    expr: &'a E,
+    /// index of the next word to compute
+    word_index: usize,
+    /// bits of the current word not yet yielded
+    current: usize,
+}
+
+impl<'a, E: BitExpr> Iterator for LazyOnes<'a, E> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            if self.word_index >= self.expr.word_len() {
+                return None;
+            }
+            self.current = self.expr.word(self.word_index);
+            self.word_index += 1;
+        }
+
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some((self.word_index - 1) * BITS_PER_WORD + bit)
+    }
+}
+
+impl DenseBitSet {
+    /// Starts a lazy expression: `self & other`, computed only when the
+    /// expression is evaluated
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ BitExpr, DenseBitSet };
+    ///
+    /// let a = DenseBitSet::from_indices(128, vec![1, 2, 3]);
+    /// let b = DenseBitSet::from_indices(128, vec![2, 3, 4]);
+    /// let c = DenseBitSet::from_indices(128, vec![100]);
+    ///
+    /// let expr = a.and_ref(&b).or_ref(&c);
+    /// assert_eq!(expr.ones().collect::<Vec<_>>(), vec![2, 3, 100]);
+    ///
+    /// let mut target = DenseBitSet::with_capacity(128);
+    /// expr.eval_into(&mut target);
+    /// assert_eq!(target.count_ones(), 3);
+    /// ```
+    pub fn and_ref<'a, S: BitWords + ?Sized>(&'a self, other: &'a S) -> LazyAnd<&'a DenseBitSet, &'a S> {
+        BitExpr::and_ref(self, other)
+    }
+
+    /// Starts a lazy expression: `self | other`
+    pub fn or_ref<'a, S: BitWords + ?Sized>(&'a self, other: &'a S) -> LazyOr<&'a DenseBitSet, &'a S> {
+        BitExpr::or_ref(self, other)
+    }
+
+    /// Starts a lazy expression: `self ^ other`
+    pub fn xor_ref<'a, S: BitWords + ?Sized>(&'a self, other: &'a S) -> LazyXor<&'a DenseBitSet, &'a S> {
+        BitExpr::xor_ref(self, other)
+    }
+
+    /// Starts a lazy expression: `self & !other`
+    pub fn and_not_ref<'a, S: BitWords + ?Sized>(&'a self, other: &'a S) -> LazyAndNot<&'a DenseBitSet, &'a S> {
+        BitExpr::and_not_ref(self, other)
+    }
+}
+
+// Lazy TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn lazy_matches_eager_ops() {
+        let a = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 2 == 0));
+        let b = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 3 == 0));
+        let c = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 7 == 0));
+
+        let eager = a.and(&b).or(&c).xor(&b).difference(&c);
+        let lazy = a.and_ref(&b).or_ref(&c).xor_ref(&b).and_not_ref(&c);
+
+        assert_eq!(lazy.to_dense(), eager);
+        assert_eq!(lazy.count_ones(), eager.count_ones());
+        assert_eq!(lazy.ones().collect::<Vec<_>>(), eager.ones().collect::<Vec<_>>());
+
+        let mut target = DenseBitSet::with_capacity(640);
+        lazy.eval_into(&mut target);
+        assert_eq!(target, eager);
+    }
+
+    #[test]
+    #[should_panic]
+    fn lazy_rejects_mismatched_lengths() {
+        let a = DenseBitSet::with_capacity(64);
+        let b = DenseBitSet::with_capacity(128);
+        a.or_ref(&b);
+    }
+}
@@ -20,465 +20,358 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-//! A dense bit set implemented over `std::Vec`
+//! Heap-allocated bit set implementations.
+//!
+//! The crate is split into a small core and optional pieces:
+//!
+//! * The `std` feature, on by default, is needed by everything except
+//!   `StaticBitSet`. Without it the crate is `no_std` and only offers
+//!   `StaticBitSet`, through the `fixed` feature.
+//! * `DenseBitSet` is the core backend, a bit set stored in a `Vec<usize>`.
+//! * `AtomicBitSet` (feature `atomic`, on by default) is a fixed-capacity
+//!   set shared between threads.
+//! * `GrowableAtomicBitSet` (feature `crossbeam-epoch`) is a shared set
+//!   that grows without blocking readers.
+//! * The `serde` feature adds `Serialize`/`Deserialize` for the backends.
+//! * The `rayon` feature adds parallel traversal such as
+//!   `DenseBitSet::par_for_each_one`.
+//! * The `profiling` feature reports the latency of bulk operations, rank
+//!   index builds and serialization to a pluggable `LatencyRecorder`.
+//! * The `compress` feature adds chunked zstd archives through
+//!   `DenseBitSet::save_compressed` and `CompressedArchive`.
+//! * The `compare` feature adds `BenchWorkload`, a harness measuring
+//!   `DenseBitSet` against `fixedbitset`, `bit-set` and `roaring`.
+//! * The `roaring` feature adds conversions to and from the `roaring`
+//!   crate's `RoaringBitmap` and `RoaringTreemap`.
+//!
+//! * `sparse`, `hybrid`, `fixed` (`BitArray` and `StaticBitSet`), `small`,
+//!   `ewah`, `rank-select` (`RankSelectBitSet` and `DynamicRankBitSet`),
+//!   `elias-fano`, `range-set` and `hierarchical` each compile one backend.
+//!   They are all on by default; build with `default-features = false` and
+//!   list the ones you use to leave the rest out.
+//!
+//! Every backend lives in its own module and every public type is
+//! re-exported from the crate root, so paths stay stable as modules move.
 //!
 //! # Examples
-//! 
-//! use bitsets::DenseBitSet
-//! 
+//!
+//! ```
+//! use bitsets::DenseBitSet;
+//!
 //! let mut bs = DenseBitSet::with_capacity(1024);
-//! 
+//!
 //! bs.set(5);
 //! bs.set(6);
 //! bs.set(15);
-//! 
-//! if (bs.test(5)) {
-//!   println!("Hey it works!");
+//!
+//! if bs.test(5) {
+//!     println!("Hey it works!");
 //! }
-//! 
-//! if (bs.test(13)) {
+//!
+//! if bs.test(13) {
 //!     println!("Hey it doesn't work");
 //! }
-//! 
+//! ```
+
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+#[cfg(all(not(feature = "std"), not(test)))]
+extern crate core as std;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "roaring")]
+extern crate roaring;
+#[cfg(feature = "crossbeam-epoch")]
+extern crate crossbeam_epoch;
+#[cfg(feature = "compress")]
+extern crate zstd;
+#[cfg(feature = "compare")]
+extern crate bit_set;
+#[cfg(feature = "compare")]
+extern crate fixedbitset;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
+
+#[cfg(any(feature = "std", feature = "fixed"))]
+use std::mem;
 
+/// Runs `$body`, reporting its latency as `$kernel` over `$words` words
+/// when the `profiling` feature is on. With `|out| $words`, the count is
+/// computed from `$body`'s output, for work whose size is only known after.
+#[cfg(feature = "profiling")]
+macro_rules! profiled {
+    ($kernel:ident, |$out:ident| $words:expr, $body:expr) => {
+        ::profiling::timed(::profiling::ProfiledKernel::$kernel, || $body, |$out: &_| $words)
+    };
+    ($kernel:ident, $words:expr, $body:expr) => {{
+        let words = $words;
+        ::profiling::timed(::profiling::ProfiledKernel::$kernel, || $body, move |_: &_| words)
+    }};
+}
 
-use std::mem;
-use std::fmt;
-use std::iter::{ ExactSizeIterator, Iterator };
+#[cfg(all(feature = "std", not(feature = "profiling")))]
+macro_rules! profiled {
+    ($kernel:ident, |$out:ident| $words:expr, $body:expr) => {
+        $body
+    };
+    ($kernel:ident, $words:expr, $body:expr) => {
+        $body
+    };
+}
 
+#[cfg(feature = "std")]
+mod alloc_set;
+#[cfg(feature = "std")]
+mod allocator;
+#[cfg(feature = "std")]
+mod bitset;
+#[cfg(feature = "std")]
+mod bloom;
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
+mod combine;
+#[cfg(feature = "std")]
+mod dense;
+#[cfg(feature = "std")]
+mod edit;
+#[cfg(feature = "std")]
+mod endian;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "std")]
+mod format;
+#[cfg(feature = "std")]
+mod hints;
+#[cfg(feature = "std")]
+mod id_allocator;
+#[cfg(feature = "std")]
+mod iter;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+pub mod mask;
+#[cfg(feature = "std")]
+mod matrix;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+mod normalize;
+#[cfg(feature = "std")]
+mod ops;
+#[cfg(feature = "std")]
+mod optimized;
+#[cfg(feature = "std")]
+mod packed;
+#[cfg(feature = "std")]
+mod parse;
+#[cfg(feature = "std")]
+mod pattern;
+#[cfg(feature = "std")]
+mod permuted;
+#[cfg(feature = "std")]
+mod query;
+#[cfg(feature = "std")]
+mod range;
+#[cfg(feature = "std")]
+mod rank;
+#[cfg(feature = "std")]
+mod relation;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "std")]
+mod resize;
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+mod shadow;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+mod simd;
+#[cfg(feature = "std")]
+mod slice;
+#[cfg(feature = "std")]
+mod sliced_counter;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod typed_index;
+#[cfg(feature = "std")]
+mod view;
+#[cfg(feature = "std")]
+mod word;
+#[cfg(feature = "std")]
+mod word_set;
+
+#[cfg(feature = "rank-select")]
+mod dynamic_rank;
+#[cfg(feature = "elias-fano")]
+mod elias_fano;
+#[cfg(feature = "ewah")]
+mod ewah;
+#[cfg(feature = "fixed")]
+mod fixed;
+#[cfg(feature = "hierarchical")]
+mod hierarchical;
+#[cfg(feature = "hybrid")]
+mod hybrid;
+#[cfg(feature = "range-set")]
+mod range_set;
+#[cfg(feature = "rank-select")]
+mod rank_select;
+#[cfg(feature = "small")]
+mod small;
+#[cfg(feature = "sparse")]
+mod sparse;
+
+#[cfg(feature = "compress")]
+mod archive;
+#[cfg(feature = "compare")]
+mod compare;
+#[cfg(feature = "atomic")]
+mod atomic;
+#[cfg(feature = "crossbeam-epoch")]
+mod epoch;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "profiling")]
+mod profiling;
+#[cfg(feature = "roaring")]
+mod roaring_impls;
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+#[cfg(feature = "std")]
+pub use dense::DenseBitSet;
+#[cfg(feature = "std")]
+pub use alloc_set::{ AllocBitSet, ArenaAllocator, HeapAllocator, WordAllocator };
+#[cfg(feature = "std")]
+pub use allocator::{ AllocatorStats, BitmapAllocator };
+#[cfg(feature = "compress")]
+pub use archive::{ ArchiveError, CompressedArchive, ARCHIVE_CHUNK_WORDS, ARCHIVE_MAX_CHUNK_WORDS };
+#[cfg(feature = "std")]
+pub use bitset::BitSet;
+#[cfg(feature = "std")]
+pub use bloom::{ BloomFilter, RotatingBloomFilter };
+#[cfg(feature = "std")]
+pub use clock::ClockBitmap;
+#[cfg(feature = "compare")]
+pub use compare::{ BenchBackend, BenchWorkload, BENCH_DENSITIES, BENCH_SIZES };
+#[cfg(feature = "atomic")]
+pub use atomic::{ AtomicBitSet, AtomicOnesIterator };
+#[cfg(feature = "rank-select")]
+pub use dynamic_rank::DynamicRankBitSet;
+#[cfg(feature = "elias-fano")]
+pub use elias_fano::{ EliasFanoIter, EliasFanoSet };
+#[cfg(feature = "crossbeam-epoch")]
+pub use epoch::GrowableAtomicBitSet;
+#[cfg(feature = "ewah")]
+pub use ewah::EwahBitSet;
+#[cfg(feature = "std")]
+pub use fingerprint::RollingFingerprints;
+#[cfg(all(feature = "fixed", feature = "std"))]
+pub use fixed::BitArray;
+#[cfg(feature = "fixed")]
+pub use fixed::StaticBitSet;
+#[cfg(feature = "std")]
+pub use format::{ BitFormat, DebugSet, DisplayWith };
+#[cfg(feature = "hierarchical")]
+pub use hierarchical::{ HierarchicalBitSet, HierarchicalOnes };
+#[cfg(feature = "std")]
+pub use hints::HintedBitSet;
+#[cfg(feature = "hybrid")]
+pub use hybrid::HybridBitSet;
+#[cfg(feature = "std")]
+pub use id_allocator::{ IdAllocator, IdAllocatorStats, ReusePolicy };
+#[cfg(feature = "std")]
+pub use iter::{ DenseBitIterator, DenseOnesChunks, DenseOnesGroups, DenseOnesIterator, DenseOnesMatching, DenseOnesWhere, DenseRunsIterator, DenseSymmetricDifference };
+#[cfg(feature = "std")]
+pub use lazy::{ BitExpr, LazyAnd, LazyAndNot, LazyOnes, LazyOr, LazyXor };
+#[cfg(feature = "std")]
+pub use matrix::BitMatrix;
+#[cfg(feature = "std")]
+pub use metrics::Overlap;
+#[cfg(feature = "std")]
+pub use normalize::{ NonCanonicalError, Normalize };
+#[cfg(feature = "std")]
+pub use ops::BitWords;
+#[cfg(feature = "std")]
+pub use optimized::{ Kernel, OptimizedOps };
+#[cfg(feature = "std")]
+pub use packed::{ pack_many, unpack_many, PackedSets, UnpackError };
+#[cfg(feature = "std")]
+pub use parse::ParseBitSetError;
+#[cfg(feature = "std")]
+pub use permuted::{ BitReversal, IndexPermutation, Morton, PermutedBitSet };
+#[cfg(feature = "profiling")]
+pub use profiling::{ clear_latency_recorder, set_latency_recorder, LatencyHistogram, LatencyRecorder, ProfiledKernel };
+#[cfg(feature = "std")]
+pub use query::{ Query, QueryError, MAX_QUERY_NESTING };
+#[cfg(feature = "range-set")]
+pub use range_set::RangeSet;
+#[cfg(feature = "rank-select")]
+pub use rank_select::RankSelectBitSet;
+#[cfg(feature = "std")]
+pub use report::{ StorageBackend, StorageReport };
+#[cfg(feature = "roaring")]
+pub use roaring_impls::RoaringConversionError;
+#[cfg(feature = "std")]
+pub use shadow::ShadowedBitSet;
+#[cfg(feature = "std")]
+pub use shared::SharedBitSet;
+#[cfg(feature = "std")]
+pub use slice::{ BitSlice, BitSliceMut };
+#[cfg(feature = "std")]
+pub use sliced_counter::BitSlicedCounter;
+#[cfg(feature = "small")]
+pub use small::SmallBitSet;
+#[cfg(feature = "sparse")]
+pub use sparse::SparseBitSet;
+#[cfg(feature = "std")]
+pub use stream::{ verify_and_iter_words, write_checksummed_words, VerifiedWords, WordStreamError, MAX_BLOCK_WORDS };
+#[cfg(feature = "std")]
+pub use typed_index::{ IndexError, TypedIndexExt };
+#[cfg(feature = "std")]
+pub use view::{ DenseBitSetView, DenseBitSetViewOnes };
+#[cfg(feature = "std")]
+pub use word::{ FixedWidthWord, Word };
+
+#[cfg(any(feature = "std", feature = "fixed"))]
 const BITS_PER_BYTE: usize = 8;
+#[cfg(any(feature = "std", feature = "fixed"))]
 const BYTES_PER_WORD: usize = mem::size_of::<usize>();
+#[cfg(any(feature = "std", feature = "fixed"))]
 const BITS_PER_WORD: usize = BYTES_PER_WORD * BITS_PER_BYTE;
 
+#[cfg(feature = "std")]
 #[inline]
 fn get_word_offset(pos: usize) -> usize {
     pos / BITS_PER_WORD
 }
 
+#[cfg(feature = "std")]
 #[inline]
 fn get_bit_offset(pos: usize) -> usize {
     pos % BITS_PER_WORD
 }
 
+#[cfg(feature = "std")]
 #[inline]
 fn get_bitmask(pos: usize) -> usize {
-    1 << get_bit_offset(pos)
-}
-
-/// A dense bit set implemented over `std::Vec<usize>`
-#[derive(Clone, Eq, PartialEq)]
-pub struct DenseBitSet {
-    num_bits: usize,
-    bits: Vec<usize>,
-}
-
-
-impl DenseBitSet {
-    /// Creates a `DenseBitSet` that can contain at least `num_bits` bits.
-    /// This will be rounded to the nearest word size that can accomodate
-    /// `num_bits` bits.
-    /// 
-    /// # Examples
-    /// 
-    /// Basic usage:
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// 
-    /// let mut bs = DenseBitSet::with_capacity(128);
-    /// 
-    /// bs.set(1);
-    /// bs.set(2);
-    /// bs.set(3);
-    /// 
-    /// assert!(bs.test(1));
-    /// assert!(bs.test(2));
-    /// assert!(bs.test(3));
-    /// ```
-    pub fn with_capacity(num_bits: usize) -> DenseBitSet {
-        DenseBitSet::with_capacity_and_state(num_bits, 0)
-    }
-
-
-    /// Creates a `DenseBitSet` that can contain at least `num_bits` bits.
-    /// Each word of the underlying storage is initialized to `initial_state`.
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// 
-    /// // 11111111111111111111111111111111111111111111111111111111
-    /// let bs1 = DenseBitSet::with_capacity_and_state(64, std::usize::MAX);
-    ///
-    /// // 00000000000000000000000000000000000000000000000000000000
-    /// let bs2 = DenseBitSet::with_capacity_and_state(64, 0);
-    /// ```
-    pub fn with_capacity_and_state(num_bits: usize, initial_state: usize) -> DenseBitSet {
-        let full_words = num_bits / BITS_PER_WORD;
-        let remaining_bits = num_bits % BITS_PER_WORD;
-        let words_to_allocate;
-        if remaining_bits > 0 {
-            words_to_allocate = full_words + 1;
-        } else {
-            words_to_allocate = full_words;
-        }
-
-        DenseBitSet {
-            bits: vec![initial_state; words_to_allocate],
-            num_bits: words_to_allocate * BITS_PER_WORD,
-        }
-    }
-
-    /// creates a single-word sized DenseBitSet initialized to `bit_pattern`.
-    ///
-    /// # Examples
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// let bs = DenseBitSet::from_bits(0b0101010101010101);
-    /// 
-    /// assert!(bs.test(0));
-    /// assert!(!bs.test(1));
-    /// ```
-    pub fn from_bits(bit_pattern: usize) -> DenseBitSet {
-        DenseBitSet::with_capacity_and_state(BITS_PER_WORD, bit_pattern)
-    }
-
-    /// Creates a DenseBitSet using the given Vec as the
-    /// underlying bits.
-    pub fn from_vec(v: Vec<usize>) -> DenseBitSet {
-        DenseBitSet {
-            num_bits: BITS_PER_WORD * v.len(),
-            bits: v,
-        }
-    }
-
-    /// Tests whether the ith bit is set
-    /// Returns true if is set, else false
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// 
-    /// let bs = DenseBitSet::with_capacity(64);
-    /// assert!(!bs.test(16));
-    /// ```
-    pub fn test(&self, i: usize) -> bool {
-        (self.bits[get_word_offset(i)] & get_bitmask(i)) != 0
-    }
-
-    /// Sets the ith bit.
-    /// Returns true if bit was not set previously
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// 
-    /// let mut bs = DenseBitSet::with_capacity(64);
-    /// 
-    /// let is_present = bs.test(32);
-    /// assert!(!is_present);
-    /// 
-    /// let first_time_set = bs.set(32);
-    /// assert!(first_time_set);
-    /// 
-    /// let is_present = bs.test(32);
-    /// assert!(is_present);
-    /// ```
-    pub fn set(&mut self, i: usize) -> bool {
-        let idx = get_word_offset(i);
-        let prior = self.bits[idx];
-        let bitmask = get_bitmask(i);
-
-        self.bits[idx] |= bitmask;
-        (prior & bitmask) == 0
-    }
-
-    /// flips the value of the ith bit
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// use bitsets::DenseBitSet;
-    /// 
-    /// let mut bs = DenseBitSet::with_capacity(64);
-    /// 
-    /// assert!(!bs.test(16));
-    /// assert!(!bs.test(24));
-    /// bs.set(46);
-    /// assert!(bs.test(46));
-    /// 
-    /// bs.flip(14);
-    /// bs.flip(24);
-    /// bs.flip(46);
-    /// 
-    /// assert!(bs.test(14));
-    /// assert!(bs.test(24));
-    /// assert!(!bs.test(46));
-    /// 
-    /// ```
-    pub fn flip(&mut self, i: usize) {
-        self.bits[get_word_offset(i)] ^= get_bitmask(i)
-    }
-
-    /// In-place bitwise-not
-    pub fn inplace_not(&mut self) {
-        for i in 0..self.bits.len() {
-            self.bits[i] = !self.bits[i];
-        }
-    }
-
-    /// In-place bitwise-and with `other`
-    pub fn inplace_and(&mut self, other: &DenseBitSet) {
-        assert!(self.words() == other.words());
-
-        for i in 0..self.bits.len() {
-            self.bits[i] &= other.bits[i];
-        }
-    }
-
-    /// In-place bitwise-or with `other`
-    pub fn inplace_or(&mut self, other: &DenseBitSet) {
-        assert!(self.words() == other.words());
-
-        for i in 0..self.bits.len() {
-            self.bits[i] |= other.bits[i];
-        }
-    }
-
-    /// In-place bitwise-xor with `other`
-    pub fn inplace_xor(&mut self, other: &DenseBitSet) {
-        assert!(self.words() == other.words());
-
-        for i in 0..self.bits.len() {
-            self.bits[i] ^= other.bits[i];
-        }
-    }
-
-    pub fn and(&self, other: &DenseBitSet) -> DenseBitSet {
-        assert!(self.words() == other.words());
-
-        let mut output = self.clone();
-        output.inplace_and(other);
-        output
-    }
-
-    pub fn or(&self, other: &DenseBitSet) -> DenseBitSet {
-        assert!(self.words() == other.words());
-        
-        let mut output = self.clone();
-        output.inplace_or(other);
-        output
-    }
-
-    pub fn xor(&self, other: &DenseBitSet) -> DenseBitSet {
-        assert!(self.words() == other.words());
-        let mut output = self.clone();
-        output.inplace_xor(other);
-        output
-    }
-
-    /// returns the number of elements in the underlying Vec<usize>
-    pub fn words(&self) -> usize {
-        self.bits.len()
-    }
-
-    /// returns the number of bits this set can accommodate
-    pub fn len(&self) -> usize {
-        self.num_bits
-    }
-}
-
-impl fmt::Debug for DenseBitSet {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // write!(f, "DenseBitSet {{ x: {}, y: {} }}", self.x, self.y)
-        let mut res = write!(f, "DenseBitset: ");
-        for i in 0..self.len() {
-            res = write!(f, "{}", if self.test(i) { 1 } else { 0 });
-        }
-        res
-    }
-}
-
-/// An iterator for DenseBitSet
-/// Allows the caller to iterate over each bit as a bool
-#[derive(Clone, Eq, PartialEq)]
-pub struct DenseBitIterator<'a> {
-    collection: &'a DenseBitSet,
-    index: usize
-}
-
-impl<'a> Iterator for DenseBitIterator<'a> {
-    type Item = bool;
-
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.collection.len() {
-            let result = self.collection.test(self.index);
-            self.index += 1;
-
-            Some(result)
-        } else {
-            None
-        }
-    }
+    mask::word_and_mask_for::<usize>(pos).1
 }
 
-impl<'a> ExactSizeIterator for DenseBitIterator<'a> {
-    /// The size of the bitset is known at the time of creation
-    fn len(&self) -> usize {
-        self.collection.len()
-    }
-}
-
-impl<'a> IntoIterator for &'a DenseBitSet {
-    type Item = bool;
-    type IntoIter = DenseBitIterator<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        DenseBitIterator {
-            collection: &self,
-            index: 0
-        }
-    }
-}
-
-// DenseBitSet TESTS
-mod tests {
-    
-    use super::*;
-
-    #[test]
-    fn can_create() {
-        let bs = DenseBitSet::with_capacity(128);
-
-        assert!(bs.len() == 128);
-    }
-
-    #[test]
-    fn can_set_and_test_bits() {
-        let mut bs = DenseBitSet::with_capacity(128);
-        assert_eq!(bs.test(0), false);
-        assert_eq!(bs.test(10), false);
-        assert_eq!(bs.test(30), false);
-        bs.set(0);
-        bs.set(10);
-        bs.set(30);
-        assert_eq!(bs.test(0), true);
-        assert_eq!(bs.test(10), true);
-        assert_eq!(bs.test(30), true);
-    }
-
-    #[test]
-    fn can_report_num_words() {
-        // initialize with non-aligned value
-        let bs = DenseBitSet::with_capacity(100);
-        assert_eq!(128, bs.len());
-        assert_eq!(2, bs.words());
-    }
-
-    #[test]
-    fn can_clear_bits() {
-        let mut bs = DenseBitSet::with_capacity(64);
-
-        bs.set(45);
-        assert_eq!(bs.test(45), true);
-
-        bs.flip(45);
-        assert_eq!(bs.test(45), false);
-    }
-
-    #[test]
-    fn can_initialize_from_literal() {
-
-        // 1010101110101110101010101010000000000000000000000000000000000000
-        let bs = DenseBitSet::from_bits(0b0101010101010111010111010101);
-        assert!(bs.test(0));
-        assert!(!bs.test(1));
-        assert!(bs.test(2));
-        assert!(!bs.test(3));
-        assert!(bs.test(4));
-    }
-
-    #[test]
-    fn can_compare() {
-        let A = DenseBitSet::from_bits(0b111000111);
-        let B = DenseBitSet::from_bits(0b111000111);
-        let C = DenseBitSet::from_bits(0b110111111);
-
-        assert!(A == B);
-        assert!(B == A);
-
-        assert!(A != C);
-        assert!(C != A);
-    }
-
-    #[test]
-    fn can_union_bits() {
-
-        let A = DenseBitSet::from_bits(0b1000110001);
-        let B = DenseBitSet::from_bits(0b0010000100);
-        let C = A.or(&B);
-
-        let bits = DenseBitSet::from_bits(0b1010110101);
-
-        assert_eq!(bits, C);
-    }
-
-    #[test]
-    fn can_intersect_bits() {
-
-        let A = DenseBitSet::from_bits(0b1000110001);
-        let B = DenseBitSet::from_bits(0b1010100100);
-        let C = A.and(&B);
-
-        let bits = DenseBitSet::from_bits(0b1000100000);
-
-        assert_eq!(bits, C);
-    }
-
-    #[test]
-    fn can_xor_bits() {
-
-        let A = DenseBitSet::from_bits(0b11100010101);
-        let B = DenseBitSet::from_bits(0b11110100100);
-        let C = A.xor(&B);
-
-        let bits = DenseBitSet::from_bits(0b00010110001);
-
-        assert_eq!(bits, C);
-    }
-
-    #[test]
-    fn can_not_bits() {
-
-        let mut bs = DenseBitSet::from_bits(0b11100011101);
-        let sb = DenseBitSet::from_bits(0b1111111111111111111111111111111111111111111111111111100011100010);
-
-        bs.inplace_not();
-
-        assert_eq!(sb, bs);
-    }
-
-    #[test]
-    fn iter_bits() {
-        let bit_pattern: usize = 0b00110100;
-        let bs = DenseBitSet::from_bits(bit_pattern);
-        let expected_values: Vec<bool> = (0..BITS_PER_WORD).map(|i| if (bit_pattern >> i) & 0x01 != 0 { true } else { false }).collect();
-
-        for (expected, bit_is_set) in expected_values.into_iter().zip(bs.into_iter()) {
-            assert_eq!(expected, bit_is_set);
-        }
-    }
-
-    #[test]
-    fn iter_bits_known_size() {
-        let bit_pattern: usize = 0b00111010;
-        let bs = DenseBitSet::from_bits(bit_pattern);
-
-        assert_eq!(bs.into_iter().len(), BITS_PER_WORD);
-    }
+/// mask of the lowest `n` bits of a word, `n <= BITS_PER_WORD`
+#[cfg(feature = "std")]
+#[inline]
+fn get_low_mask(n: usize) -> usize {
+    mask::low_bits_mask(n)
 }
@@ -0,0 +1,118 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bit mask arithmetic over raw words
+//!
+//! These are the helpers the set types use internally, generic over the
+//! word type so code working with raw words of any width can share them.
+//!
+//! # Examples
+//!
+//! ```
+//! use bitsets::mask;
+//!
+//! assert_eq!(mask::low_bits_mask::<u8>(3), 0b0000_0111);
+//! assert_eq!(mask::range_mask::<u8>(2..5), 0b0001_1100);
+//! assert_eq!(mask::word_and_mask_for::<u32>(35), (1, 0b1000));
+//! ```
+
+use std::ops::Range;
+
+use Word;
+
+/// Returns a word with the lowest `n` bits set, or every bit if
+/// `n >= W::BITS`
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::mask::low_bits_mask;
+///
+/// assert_eq!(low_bits_mask::<u16>(0), 0);
+/// assert_eq!(low_bits_mask::<u16>(16), 0xffff);
+/// ```
+#[inline]
+pub fn low_bits_mask<W: Word>(n: usize) -> W {
+    W::low_mask(n)
+}
+
+/// Returns a word with bits `range.start` up to, not including,
+/// `range.end` set. Panics unless `range.start <= range.end <= W::BITS`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::mask::range_mask;
+///
+/// assert_eq!(range_mask::<u32>(4..8), 0xf0);
+/// assert_eq!(range_mask::<u32>(0..32), !0);
+/// assert_eq!(range_mask::<u32>(7..7), 0);
+/// ```
+#[inline]
+pub fn range_mask<W: Word>(range: Range<usize>) -> W {
+    assert!(range.start <= range.end && range.end <= W::BITS);
+    W::low_mask(range.end) & !W::low_mask(range.start)
+}
+
+/// Returns the index of the word holding bit `pos` and the mask selecting
+/// that bit within the word
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::mask::word_and_mask_for;
+///
+/// let mut words = [0u64; 2];
+/// let (w, mask) = word_and_mask_for::<u64>(70);
+/// words[w] |= mask;
+///
+/// assert_eq!(words, [0, 1 << 6]);
+/// ```
+#[inline]
+pub fn word_and_mask_for<W: Word>(pos: usize) -> (usize, W) {
+    let offset = pos % W::BITS;
+    (pos / W::BITS, range_mask(offset..offset + 1))
+}
+
+// mask TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn masks_agree_across_word_sizes() {
+        for n in 0..=8 {
+            assert_eq!(low_bits_mask::<u8>(n) as u128, low_bits_mask::<u128>(n));
+        }
+        assert_eq!(low_bits_mask::<u64>(100), !0);
+        assert_eq!(range_mask::<u128>(64..128), !0u128 << 64);
+        assert_eq!(word_and_mask_for::<u8>(17), (2, 0b10));
+        assert_eq!(word_and_mask_for::<usize>(0), (0, 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_range_past_word() {
+        range_mask::<u8>(4..9);
+    }
+}
@@ -0,0 +1,214 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A dense boolean matrix stored as one bit row per vertex, for graph
+//! adjacency and relation algorithms
+
+use std::fmt;
+
+use { get_bitmask, get_word_offset, BitSlice, DenseBitSet, BITS_PER_WORD };
+
+/// A `rows × cols` matrix of bits. Each row is padded to whole words, so
+/// rows can be borrowed as `BitSlice`s and combined with `DenseBitSet`s of
+/// `cols` bits word by word.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::BitMatrix;
+///
+/// // a path 0 - 1 - 2
+/// let graph = BitMatrix::from_edges(3, vec![(0, 1), (1, 2)], false);
+///
+/// assert_eq!(graph.degree(1), 2);
+/// assert_eq!(graph.neighbors(1).ones().collect::<Vec<_>>(), vec![0, 2]);
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct BitMatrix {
+    rows: usize,
+    cols: usize,
+    row_words: usize,
+    bits: Vec<usize>,
+}
+
+impl BitMatrix {
+    /// Creates a `rows × cols` matrix with every bit clear
+    pub fn new(rows: usize, cols: usize) -> BitMatrix {
+        let row_words = cols.div_ceil(BITS_PER_WORD);
+
+        BitMatrix {
+            rows,
+            cols,
+            row_words,
+            bits: vec![0; rows * row_words],
+        }
+    }
+
+    /// Creates the `n × n` adjacency matrix of a graph on vertices `0..n`.
+    /// Each edge `(u, v)` sets `(u, v)`, and also `(v, u)` unless `directed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::BitMatrix;
+    ///
+    /// let graph = BitMatrix::from_edges(3, vec![(0, 1), (0, 2)], true);
+    ///
+    /// assert!(graph.test(0, 2));
+    /// assert!(!graph.test(2, 0));
+    /// ```
+    pub fn from_edges<I: IntoIterator<Item = (usize, usize)>>(n: usize, edges: I, directed: bool) -> BitMatrix {
+        let mut matrix = BitMatrix::new(n, n);
+        for (u, v) in edges {
+            matrix.set(u, v);
+            if !directed {
+                matrix.set(v, u);
+            }
+        }
+        matrix
+    }
+
+    /// returns the number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// returns the number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Tests whether bit `(row, col)` is set
+    pub fn test(&self, row: usize, col: usize) -> bool {
+        (self.bits[self.word_index(row, col)] & get_bitmask(col)) != 0
+    }
+
+    /// Sets bit `(row, col)`.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, row: usize, col: usize) -> bool {
+        let idx = self.word_index(row, col);
+        let prior = self.bits[idx];
+        let bitmask = get_bitmask(col);
+
+        self.bits[idx] |= bitmask;
+        (prior & bitmask) == 0
+    }
+
+    /// Borrows a row as a `BitSlice` of `cols()` bits
+    pub fn row(&self, row: usize) -> BitSlice<'_> {
+        assert!(row < self.rows);
+        let start = row * self.row_words;
+        BitSlice::new(&self.bits[start..start + self.row_words], self.cols)
+    }
+
+    /// Borrows the out-neighbors of vertex `u`, i.e. row `u`
+    pub fn neighbors(&self, u: usize) -> BitSlice<'_> {
+        self.row(u)
+    }
+
+    /// returns the out-degree of vertex `u`, i.e. the set bits in row `u`
+    pub fn degree(&self, u: usize) -> usize {
+        self.row(u).count_ones()
+    }
+
+    /// Returns the union of the neighbors of every vertex in `frontier`,
+    /// e.g. one step of a breadth-first search. `frontier` must cover at
+    /// least `rows()` bits; the result has `cols()` bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ BitMatrix, DenseBitSet };
+    ///
+    /// let graph = BitMatrix::from_edges(4, vec![(0, 1), (0, 2), (2, 3)], true);
+    /// let frontier = DenseBitSet::from_indices(4, vec![0, 2]);
+    ///
+    /// assert_eq!(graph.union_neighborhood(&frontier).to_indices(), vec![1, 2, 3]);
+    /// ```
+    pub fn union_neighborhood(&self, frontier: &DenseBitSet) -> DenseBitSet {
+        assert!(frontier.len() >= self.rows);
+
+        let mut output = DenseBitSet::with_capacity(self.cols);
+        for u in frontier.ones().take_while(|&u| u < self.rows) {
+            output.inplace_or(&self.row(u));
+        }
+        output
+    }
+
+    fn word_index(&self, row: usize, col: usize) -> usize {
+        assert!(row < self.rows && col < self.cols);
+        row * self.row_words + get_word_offset(col)
+    }
+}
+
+impl fmt::Debug for BitMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "BitMatrix {}x{}:", self.rows, self.cols)?;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                write!(f, "{}", if self.test(row, col) { 1 } else { 0 })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// BitMatrix TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_build_undirected_graph() {
+        let graph = BitMatrix::from_edges(100, vec![(0, 99), (0, 70), (70, 99)], false);
+
+        assert_eq!(graph.degree(0), 2);
+        assert_eq!(graph.degree(99), 2);
+        assert_eq!(graph.degree(1), 0);
+        assert!(graph.test(99, 70));
+        assert_eq!(graph.neighbors(70).ones().collect::<Vec<_>>(), vec![0, 99]);
+    }
+
+    #[test]
+    fn can_walk_breadth_first() {
+        let graph = BitMatrix::from_edges(200, (0..199).map(|i| (i, i + 1)), true);
+        let mut frontier = DenseBitSet::from_indices(200, vec![0]);
+
+        for _ in 0..150 {
+            frontier = graph.union_neighborhood(&frontier);
+        }
+        assert_eq!(frontier.to_indices(), vec![150]);
+    }
+
+    #[test]
+    fn rows_do_not_bleed_into_each_other() {
+        let mut matrix = BitMatrix::new(3, 65);
+        matrix.set(1, 64);
+
+        assert!(!matrix.test(0, 64));
+        assert!(!matrix.test(2, 0));
+        assert_eq!(matrix.row(1).len(), 65);
+        assert_eq!(matrix.row(1).count_ones(), 1);
+    }
+}
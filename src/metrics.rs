@@ -0,0 +1,174 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Binary similarity and distance metrics between `DenseBitSet`s
+//!
+//! Every metric is derived from the three counts in `Overlap`, which
+//! `DenseBitSet::overlap` gathers in a single pass over both word arrays.
+//! Sets of different lengths compare as if the shorter one were padded with
+//! zeros.
+
+use DenseBitSet;
+
+/// The counts two sets are compared by
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Overlap {
+    /// number of indices in both sets
+    pub both: usize,
+    /// number of indices only in the left set
+    pub left_only: usize,
+    /// number of indices only in the right set
+    pub right_only: usize,
+}
+
+impl Overlap {
+    /// returns the size of the union
+    pub fn union(&self) -> usize {
+        self.both + self.left_only + self.right_only
+    }
+
+    /// returns the number of indices in exactly one set, the Hamming
+    /// distance between the two
+    pub fn hamming(&self) -> usize {
+        self.left_only + self.right_only
+    }
+
+    /// returns `|a & b| / |a | b|`, 1 when both sets are empty
+    pub fn jaccard(&self) -> f64 {
+        match self.union() {
+            0 => 1.0,
+            union => self.both as f64 / union as f64,
+        }
+    }
+
+    /// returns `2|a & b| / (|a| + |b|)`, 1 when both sets are empty
+    pub fn dice(&self) -> f64 {
+        match 2 * self.both + self.hamming() {
+            0 => 1.0,
+            total => (2 * self.both) as f64 / total as f64,
+        }
+    }
+
+    /// returns `|a & b| / sqrt(|a| |b|)`, 1 when both sets are empty and 0
+    /// when only one is
+    pub fn cosine(&self) -> f64 {
+        let left = (self.both + self.left_only) as f64;
+        let right = (self.both + self.right_only) as f64;
+        match (left == 0.0, right == 0.0) {
+            (true, true) => 1.0,
+            (true, false) | (false, true) => 0.0,
+            (false, false) => self.both as f64 / (left * right).sqrt(),
+        }
+    }
+}
+
+impl DenseBitSet {
+    /// Counts the indices in both sets and in each set alone, in one pass
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let a = DenseBitSet::from_indices(64, vec![1, 2, 3, 4]);
+    /// let b = DenseBitSet::from_indices(128, vec![3, 4, 5, 100]);
+    /// let overlap = a.overlap(&b);
+    ///
+    /// assert_eq!((overlap.both, overlap.left_only, overlap.right_only), (2, 2, 2));
+    /// assert_eq!(overlap.jaccard(), 2.0 / 6.0);
+    /// assert_eq!(overlap.dice(), 0.5);
+    /// assert_eq!(overlap.cosine(), 0.5);
+    /// assert_eq!(overlap.hamming(), 4);
+    /// ```
+    pub fn overlap(&self, other: &DenseBitSet) -> Overlap {
+        let mut overlap = Overlap::default();
+        let words = self.bits.len().max(other.bits.len());
+
+        for w in 0..words {
+            let a = self.bits.get(w).cloned().unwrap_or(0);
+            let b = other.bits.get(w).cloned().unwrap_or(0);
+            overlap.both += (a & b).count_ones() as usize;
+            overlap.left_only += (a & !b).count_ones() as usize;
+            overlap.right_only += (b & !a).count_ones() as usize;
+        }
+        overlap
+    }
+
+    /// Returns the Jaccard index of the two sets, see `Overlap::jaccard`
+    pub fn jaccard(&self, other: &DenseBitSet) -> f64 {
+        self.overlap(other).jaccard()
+    }
+
+    /// Returns the Sørensen–Dice coefficient of the two sets, see
+    /// `Overlap::dice`
+    pub fn dice(&self, other: &DenseBitSet) -> f64 {
+        self.overlap(other).dice()
+    }
+
+    /// Returns the cosine similarity of the two sets, see `Overlap::cosine`
+    pub fn cosine(&self, other: &DenseBitSet) -> f64 {
+        self.overlap(other).cosine()
+    }
+
+    /// Returns the number of indices in exactly one of the two sets, see
+    /// `Overlap::hamming`
+    pub fn hamming_distance(&self, other: &DenseBitSet) -> usize {
+        self.overlap(other).hamming()
+    }
+}
+
+// Metrics TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn metrics_match_naive_counts() {
+        let a = DenseBitSet::from_indices(64 * 10, (0..64 * 10).filter(|i| i % 3 == 0));
+        let b = DenseBitSet::from_indices(64 * 12, (0..64 * 12).filter(|i| i % 5 == 0));
+        let overlap = a.overlap(&b);
+
+        let both = (0..64 * 10).filter(|i| i % 15 == 0).count();
+        assert_eq!(overlap.both, both);
+        assert_eq!(overlap.left_only, a.count_ones() - both);
+        assert_eq!(overlap.right_only, b.count_ones() - both);
+        assert_eq!(a.hamming_distance(&b), overlap.hamming());
+        assert_eq!(b.hamming_distance(&a), overlap.hamming());
+        assert_eq!(b.overlap(&a).jaccard(), a.jaccard(&b));
+    }
+
+    #[test]
+    fn metrics_handle_empty_sets() {
+        let empty = DenseBitSet::with_capacity(0);
+        let some = DenseBitSet::from_indices(64, vec![7]);
+
+        assert_eq!(empty.jaccard(&empty), 1.0);
+        assert_eq!(empty.dice(&empty), 1.0);
+        assert_eq!(empty.cosine(&empty), 1.0);
+        assert_eq!(empty.jaccard(&some), 0.0);
+        assert_eq!(some.cosine(&empty), 0.0);
+        assert_eq!(some.dice(&some), 1.0);
+        assert_eq!(some.hamming_distance(&empty), 1);
+    }
+}
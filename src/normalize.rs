@@ -0,0 +1,166 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bringing a `DenseBitSet` built from outside data into normal form
+
+use std::error::Error;
+use std::fmt;
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// Options for `DenseBitSet::normalize`
+///
+/// By default the pass repairs what it finds. In strict mode it changes
+/// nothing and reports the first problem instead.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Normalize {
+    strict: bool,
+    compact: bool,
+}
+
+impl Normalize {
+    /// Repairs non-canonical sets: clears padding bits and trims or pads
+    /// the words to match the length
+    pub fn repair() -> Normalize {
+        Normalize { strict: false, compact: false }
+    }
+
+    /// Rejects non-canonical sets instead of repairing them
+    pub fn strict() -> Normalize {
+        Normalize { strict: true, compact: false }
+    }
+
+    /// Whether to also release unused capacity of the word buffer
+    pub fn compact(mut self, compact: bool) -> Normalize {
+        self.compact = compact;
+        self
+    }
+}
+
+/// The ways a set can be out of normal form
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NonCanonicalError {
+    /// the word buffer does not match `len()`
+    WordCount {
+        /// words needed to hold `len()` bits
+        expected: usize,
+        /// words present
+        found: usize,
+    },
+    /// a bit past `len()` is set
+    PaddingBitsSet,
+}
+
+impl fmt::Display for NonCanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NonCanonicalError::WordCount { expected, found } => {
+                write!(f, "expected {} words for the set length, found {}", expected, found)
+            }
+            NonCanonicalError::PaddingBitsSet => write!(f, "bits past the set length are set"),
+        }
+    }
+}
+
+impl Error for NonCanonicalError {}
+
+impl DenseBitSet {
+    /// Brings the set into normal form: exactly enough words for `len()`
+    /// bits, with every bit past `len()` clear. Sets built through the
+    /// public API are always normal, so this is for data assembled from
+    /// untrusted or legacy sources; the deserializers run it on every set
+    /// they load. The pass is deterministic: the same input always gives
+    /// the same output or the same error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ DenseBitSet, Normalize };
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(64);
+    /// bs.set(3);
+    ///
+    /// assert_eq!(bs.normalize(Normalize::strict()), Ok(()));
+    /// assert_eq!(bs.normalize(Normalize::repair().compact(true)), Ok(()));
+    /// ```
+    pub fn normalize(&mut self, options: Normalize) -> Result<(), NonCanonicalError> {
+        let expected = self.num_bits.div_ceil(BITS_PER_WORD);
+        if self.bits.len() != expected {
+            if options.strict {
+                return Err(NonCanonicalError::WordCount { expected, found: self.bits.len() });
+            }
+            self.bits.resize(expected, 0);
+        }
+
+        if let Some(&last) = self.bits.last() {
+            if last & !self.last_word_mask() != 0 {
+                if options.strict {
+                    return Err(NonCanonicalError::PaddingBitsSet);
+                }
+                self.clear_padding();
+            }
+        }
+
+        if options.compact {
+            self.bits.shrink_to_fit();
+        }
+        Ok(())
+    }
+}
+
+// Normalize TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn raw(bits: Vec<usize>, num_bits: usize) -> DenseBitSet {
+        DenseBitSet { bits, num_bits }
+    }
+
+    #[test]
+    fn repairs_padding_and_word_count() {
+        let mut bs = raw(vec![!0], 10);
+        assert_eq!(bs.normalize(Normalize::repair()), Ok(()));
+        assert_eq!(bs.to_indices(), (0..10).collect::<Vec<_>>());
+
+        let mut bs = raw(vec![1, 2, 3], BITS_PER_WORD + 1);
+        assert_eq!(bs.normalize(Normalize::repair()), Ok(()));
+        assert_eq!(bs.words(), 2);
+        assert_eq!(bs.to_indices(), vec![0]);
+
+        let mut bs = raw(vec![], 3 * BITS_PER_WORD);
+        assert_eq!(bs.normalize(Normalize::repair().compact(true)), Ok(()));
+        assert_eq!(bs, DenseBitSet::with_capacity(3 * BITS_PER_WORD));
+    }
+
+    #[test]
+    fn strict_mode_reports_without_changing() {
+        let mut bs = raw(vec![!0], 10);
+        assert_eq!(bs.normalize(Normalize::strict()), Err(NonCanonicalError::PaddingBitsSet));
+        assert_eq!(bs.bits, vec![!0]);
+
+        let mut bs = raw(vec![0; 3], BITS_PER_WORD);
+        assert_eq!(bs.normalize(Normalize::strict()), Err(NonCanonicalError::WordCount { expected: 1, found: 3 }));
+        assert_eq!(bs.bits.len(), 3);
+    }
+}
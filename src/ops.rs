@@ -0,0 +1,403 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Whole-set boolean algebra over `DenseBitSet`
+//!
+//! The right-hand operand of every binary operation can be any `BitWords`
+//! type, so borrowed views such as `BitMatrix` rows combine with owned sets
+//! without being copied first.
+
+use std::borrow::Cow;
+
+use simd;
+use { BitSlice, BitSliceMut, DenseBitSet };
+
+/// Read access to the words of a bit set. Implemented by owned sets and by
+/// borrowed views so either can be an operand of the binary operations.
+pub trait BitWords {
+    /// returns the words holding the bits: bit `i` is bit `i % usize::BITS`
+    /// of word `i / usize::BITS`, and bits past `bit_len()` are zero. Sets
+    /// lend out their storage; views that do not start on a word boundary
+    /// return a shifted copy.
+    fn as_words(&self) -> Cow<'_, [usize]>;
+
+    /// returns the number of bits
+    fn bit_len(&self) -> usize;
+
+    /// returns word `w` of `as_words()`, without building the whole slice
+    fn word(&self, w: usize) -> usize {
+        self.as_words()[w]
+    }
+}
+
+impl BitWords for DenseBitSet {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        Cow::Borrowed(&self.bits)
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'a> BitWords for BitSlice<'a> {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        self.shifted_words()
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len()
+    }
+
+    fn word(&self, w: usize) -> usize {
+        BitSlice::word(self, w)
+    }
+}
+
+impl<'a> BitWords for BitSliceMut<'a> {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        self.as_bit_slice().shifted_words()
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len()
+    }
+
+    fn word(&self, w: usize) -> usize {
+        self.as_bit_slice().word(w)
+    }
+}
+
+impl DenseBitSet {
+    /// In-place bitwise-not
+    pub fn inplace_not(&mut self) {
+        profiled!(BulkNot, self.words(), simd::not(&mut self.bits));
+        self.clear_padding();
+    }
+
+    /// In-place bitwise-and with `other`
+    pub fn inplace_and<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let other = other.as_words();
+        assert!(self.words() == other.len());
+
+        profiled!(BulkAnd, other.len(), simd::and(&mut self.bits, &other));
+    }
+
+    /// In-place bitwise-or with `other`
+    pub fn inplace_or<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let other = other.as_words();
+        assert!(self.words() == other.len());
+
+        profiled!(BulkOr, other.len(), simd::or(&mut self.bits, &other));
+        self.clear_padding();
+    }
+
+    /// In-place bitwise-xor with `other`
+    pub fn inplace_xor<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let other = other.as_words();
+        assert!(self.words() == other.len());
+
+        profiled!(BulkXor, other.len(), simd::xor(&mut self.bits, &other));
+        self.clear_padding();
+    }
+
+    /// In-place set difference with `other`: clears every bit set in `other`
+    pub fn inplace_difference<S: BitWords + ?Sized>(&mut self, other: &S) {
+        let other = other.as_words();
+        assert!(self.words() == other.len());
+
+        profiled!(BulkDifference, other.len(), simd::and_not(&mut self.bits, &other));
+    }
+
+    /// Same as `inplace_difference`: `self &= !other`
+    pub fn and_not_assign<S: BitWords + ?Sized>(&mut self, other: &S) {
+        self.inplace_difference(other);
+    }
+
+    /// Fused `self |= b & c` in one pass, with no temporary for `b & c`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut reached = DenseBitSet::from_bits(0b0001);
+    /// let frontier = DenseBitSet::from_bits(0b0110);
+    /// let allowed = DenseBitSet::from_bits(0b1100);
+    ///
+    /// reached.or_and(&frontier, &allowed);
+    /// assert_eq!(reached, DenseBitSet::from_bits(0b0101));
+    /// ```
+    pub fn or_and<B: BitWords + ?Sized, C: BitWords + ?Sized>(&mut self, b: &B, c: &C) {
+        let (b, c) = (b.as_words(), c.as_words());
+        assert!(self.words() == b.len() && self.words() == c.len());
+
+        profiled!(BulkFused, b.len(), simd::or_and(&mut self.bits, &b, &c));
+        self.clear_padding();
+    }
+
+    /// Fused `self |= b & !c` in one pass
+    pub fn or_and_not<B: BitWords + ?Sized, C: BitWords + ?Sized>(&mut self, b: &B, c: &C) {
+        let (b, c) = (b.as_words(), c.as_words());
+        assert!(self.words() == b.len() && self.words() == c.len());
+
+        profiled!(BulkFused, b.len(), simd::or_and_not(&mut self.bits, &b, &c));
+        self.clear_padding();
+    }
+
+    /// Fused `self &= b | c` in one pass
+    pub fn and_or<B: BitWords + ?Sized, C: BitWords + ?Sized>(&mut self, b: &B, c: &C) {
+        let (b, c) = (b.as_words(), c.as_words());
+        assert!(self.words() == b.len() && self.words() == c.len());
+
+        profiled!(BulkFused, b.len(), simd::and_or(&mut self.bits, &b, &c));
+    }
+
+    /// Fused `self ^= b & c` in one pass
+    pub fn xor_and<B: BitWords + ?Sized, C: BitWords + ?Sized>(&mut self, b: &B, c: &C) {
+        let (b, c) = (b.as_words(), c.as_words());
+        assert!(self.words() == b.len() && self.words() == c.len());
+
+        profiled!(BulkFused, b.len(), simd::xor_and(&mut self.bits, &b, &c));
+        self.clear_padding();
+    }
+
+    pub fn and<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.clone();
+        output.inplace_and(other);
+        output
+    }
+
+    pub fn or<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.clone();
+        output.inplace_or(other);
+        output
+    }
+
+    pub fn xor<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.clone();
+        output.inplace_xor(other);
+        output
+    }
+
+    /// Returns the number of bits set in both `self` and `other`, the
+    /// popcount of `self & other`, in one pass with no temporary set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let a = DenseBitSet::from_indices(128, vec![1, 2, 3, 100]);
+    /// let b = DenseBitSet::from_indices(128, vec![2, 3, 4]);
+    ///
+    /// assert_eq!(a.intersection_count(&b), 2);
+    /// assert_eq!(a.union_count(&b), 5);
+    /// assert_eq!(a.difference_count(&b), 2);
+    /// ```
+    pub fn intersection_count<S: BitWords + ?Sized>(&self, other: &S) -> usize {
+        let other_words = other.as_words();
+        assert!(self.words() == other_words.len());
+        simd::popcount_and(&self.bits, &other_words)
+    }
+
+    /// Returns the number of bits set in `self` or `other`, the popcount of
+    /// `self | other`, in one pass with no temporary set
+    pub fn union_count<S: BitWords + ?Sized>(&self, other: &S) -> usize {
+        let other_words = other.as_words();
+        assert!(self.words() == other_words.len());
+        simd::popcount_or(&self.bits, &other_words)
+    }
+
+    /// Returns the number of bits set in `self` but not in `other`, the
+    /// popcount of `self & !other`, in one pass with no temporary set
+    pub fn difference_count<S: BitWords + ?Sized>(&self, other: &S) -> usize {
+        let other_words = other.as_words();
+        assert!(self.words() == other_words.len());
+        simd::popcount_and_not(&self.bits, &other_words)
+    }
+
+    /// Returns the bits set in `self` but not in `other`, `self & !other`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let users = DenseBitSet::from_indices(64, vec![1, 2, 3, 4]);
+    /// let banned = DenseBitSet::from_indices(64, vec![2, 4, 9]);
+    ///
+    /// assert_eq!(users.difference(&banned).to_indices(), vec![1, 3]);
+    /// ```
+    pub fn difference<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.clone();
+        output.inplace_difference(other);
+        output
+    }
+}
+
+// DenseBitSet ops TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn can_union_bits() {
+
+        let A = DenseBitSet::from_bits(0b1000110001);
+        let B = DenseBitSet::from_bits(0b0010000100);
+        let C = A.or(&B);
+
+        let bits = DenseBitSet::from_bits(0b1010110101);
+
+        assert_eq!(bits, C);
+    }
+
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn can_intersect_bits() {
+
+        let A = DenseBitSet::from_bits(0b1000110001);
+        let B = DenseBitSet::from_bits(0b1010100100);
+        let C = A.and(&B);
+
+        let bits = DenseBitSet::from_bits(0b1000100000);
+
+        assert_eq!(bits, C);
+    }
+
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn can_xor_bits() {
+
+        let A = DenseBitSet::from_bits(0b11100010101);
+        let B = DenseBitSet::from_bits(0b11110100100);
+        let C = A.xor(&B);
+
+        let bits = DenseBitSet::from_bits(0b00010110001);
+
+        assert_eq!(bits, C);
+    }
+
+
+    #[test]
+    fn can_difference_bits() {
+
+        let a = DenseBitSet::from_bits(0b11100010101);
+        let b = DenseBitSet::from_bits(0b11110100100);
+        let mut c = a.difference(&b);
+
+        let bits = DenseBitSet::from_bits(0b00000010001);
+
+        assert_eq!(bits, c);
+        c.inplace_difference(&a);
+        assert_eq!(c.count_ones(), 0);
+    }
+
+
+    #[test]
+    fn fused_ops_match_composed_ops() {
+        let a = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 2 == 0));
+        let b = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 3 == 0));
+        let c = DenseBitSet::from_indices(640, (0..640).filter(|i| i % 5 < 2));
+
+        let mut fused = a.clone();
+        fused.or_and(&b, &c);
+        assert_eq!(fused, a.or(&b.and(&c)));
+
+        let mut fused = a.clone();
+        fused.or_and_not(&b, &c);
+        assert_eq!(fused, a.or(&b.difference(&c)));
+
+        let mut fused = a.clone();
+        fused.and_or(&b, &c);
+        assert_eq!(fused, a.and(&b.or(&c)));
+
+        let mut fused = a.clone();
+        fused.xor_and(&b, &c);
+        assert_eq!(fused, a.xor(&b.and(&c)));
+
+        let mut fused = a.clone();
+        fused.and_not_assign(&b);
+        assert_eq!(fused, a.difference(&b));
+    }
+
+
+    #[test]
+    fn fused_ops_keep_padding_clear() {
+        let operand = DenseBitSet::from_bits(0b110000);
+        let empty = DenseBitSet::from_vec_masked(vec![0], 3);
+
+        let mut fused = empty.clone();
+        fused.or_and(&operand, &operand);
+        assert_eq!((fused.count_ones(), &fused), (0, &empty));
+
+        let mut fused = empty.clone();
+        fused.or_and_not(&operand, &empty);
+        assert_eq!((fused.count_ones(), &fused), (0, &empty));
+
+        let mut fused = empty.clone();
+        fused.xor_and(&operand, &operand);
+        assert_eq!((fused.count_ones(), &fused), (0, &empty));
+    }
+
+
+    #[test]
+    fn counts_match_materialized_ops() {
+        let a = DenseBitSet::from_indices(64 * 300, (0..64 * 300).filter(|i| i % 3 == 0));
+        let b = DenseBitSet::from_indices(64 * 300, (0..64 * 300).filter(|i| i % 7 < 2));
+
+        assert_eq!(a.intersection_count(&b), a.and(&b).count_ones());
+        assert_eq!(a.union_count(&b), a.or(&b).count_ones());
+        assert_eq!(a.difference_count(&b), a.difference(&b).count_ones());
+        assert_eq!(b.difference_count(&a), b.difference(&a).count_ones());
+    }
+
+
+    #[test]
+    fn can_mix_owned_and_borrowed_operands() {
+        let matrix = ::BitMatrix::from_edges(128, vec![(0, 1), (0, 100), (5, 100)], true);
+        let mut bs = DenseBitSet::from_indices(128, vec![1, 2]);
+
+        assert_eq!(bs.and(&matrix.row(0)).to_indices(), vec![1]);
+        assert_eq!(matrix.row(5).or(&bs).to_indices(), vec![1, 2, 100]);
+
+        bs.inplace_xor(&matrix.row(0));
+        assert_eq!(bs.to_indices(), vec![2, 100]);
+    }
+
+
+    #[test]
+    fn can_not_bits() {
+
+        let mut bs = DenseBitSet::from_bits(0b11100011101);
+        let sb = DenseBitSet::from_bits(0b1111111111111111111111111111111111111111111111111111100011100010);
+
+        bs.inplace_not();
+
+        assert_eq!(sb, bs);
+    }
+}
@@ -0,0 +1,295 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `OptimizedOps`, opt-in density-aware selection of boolean op kernels
+//!
+//! The plain `DenseBitSet` operations always run one word loop, which keeps
+//! their cost predictable. `OptimizedOps` caches the popcount of the set it
+//! wraps and uses it to pick a cheaper strategy per operation.
+
+use simd;
+use DenseBitSet;
+
+/// a set is sparse enough to merge by index when it holds fewer than one
+/// set bit per this many words of the operands
+const SPARSE_WORDS_PER_ONE: usize = 4;
+/// sets at least this many words long are processed by the `simd` kernels
+const CHUNKED_MIN_WORDS: usize = 64;
+
+/// A strategy for a binary operation
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Kernel {
+    /// one word at a time
+    WordLoop,
+    /// the vectorized kernels shared with the plain `DenseBitSet` ops
+    Chunked,
+    /// walk the set bits of the sparser operand and apply them to a copy
+    /// of, or lookups into, the denser one
+    SparseMerge,
+}
+
+/// A `DenseBitSet` with a cached popcount, whose operations pick a kernel
+/// from the density of their operands. Like the plain operations, every
+/// kernel returns a set as long as `self`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, Kernel, OptimizedOps };
+///
+/// let dense = OptimizedOps::new(DenseBitSet::with_capacity_and_state(1 << 16, !0));
+/// let sparse = OptimizedOps::new(DenseBitSet::from_indices(1 << 16, vec![5, 40_000]));
+///
+/// assert_eq!(dense.kernel_for(&sparse), Kernel::SparseMerge);
+/// assert_eq!(dense.and(&sparse).count_ones(), 2);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptimizedOps {
+    bits: DenseBitSet,
+    ones: usize,
+}
+
+impl OptimizedOps {
+    /// Wraps `bits`, counting its set bits once
+    pub fn new(bits: DenseBitSet) -> OptimizedOps {
+        let ones = bits.count_ones();
+        OptimizedOps { bits, ones }
+    }
+
+    /// Borrows the underlying set
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the underlying set
+    pub fn into_inner(self) -> DenseBitSet {
+        self.bits
+    }
+
+    /// returns the number of set bits, from the cache
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// returns the fraction of bits that are set, 0 for an empty set
+    pub fn density(&self) -> f64 {
+        if self.bits.len() == 0 {
+            0.0
+        } else {
+            self.ones as f64 / self.bits.len() as f64
+        }
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        self.bits.test(i)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        let changed = self.bits.set(i);
+        self.ones += changed as usize;
+        changed
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        let changed = self.bits.remove(i);
+        self.ones -= changed as usize;
+        changed
+    }
+
+    /// Returns the kernel a binary operation between `self` and `other`
+    /// would use
+    pub fn kernel_for(&self, other: &OptimizedOps) -> Kernel {
+        let words = self.bits.words();
+        if self.ones.min(other.ones) * SPARSE_WORDS_PER_ONE < words {
+            Kernel::SparseMerge
+        } else if words >= CHUNKED_MIN_WORDS {
+            Kernel::Chunked
+        } else {
+            Kernel::WordLoop
+        }
+    }
+
+    /// Returns the bitwise-and of `self` and `other`
+    pub fn and(&self, other: &OptimizedOps) -> OptimizedOps {
+        assert!(self.bits.words() == other.bits.words());
+
+        match self.kernel_for(other) {
+            Kernel::SparseMerge => {
+                let (sparse, dense) = self.by_density(other);
+                let len = self.bits.len();
+                let mut output = OptimizedOps::new(DenseBitSet::from_vec_masked(vec![0; self.bits.words()], len));
+                for i in sparse.bits.ones().take_while(|&i| i < len).filter(|&i| dense.bits.test(i)) {
+                    output.set(i);
+                }
+                output
+            }
+            kernel => self.zip(other, kernel, |a, b| a & b, simd::and),
+        }
+    }
+
+    /// Returns the bitwise-or of `self` and `other`
+    pub fn or(&self, other: &OptimizedOps) -> OptimizedOps {
+        assert!(self.bits.words() == other.bits.words());
+
+        match self.kernel_for(other) {
+            Kernel::SparseMerge => {
+                let (sparse, dense) = self.by_density(other);
+                let mut output = self.copy_of(dense);
+                for i in sparse.bits.ones().take_while(|&i| i < self.bits.len()) {
+                    output.set(i);
+                }
+                output
+            }
+            kernel => self.zip(other, kernel, |a, b| a | b, simd::or),
+        }
+    }
+
+    /// Returns the bitwise-xor of `self` and `other`
+    pub fn xor(&self, other: &OptimizedOps) -> OptimizedOps {
+        assert!(self.bits.words() == other.bits.words());
+
+        match self.kernel_for(other) {
+            Kernel::SparseMerge => {
+                let (sparse, dense) = self.by_density(other);
+                let mut output = self.copy_of(dense);
+                for i in sparse.bits.ones().take_while(|&i| i < self.bits.len()) {
+                    if !output.remove(i) {
+                        output.set(i);
+                    }
+                }
+                output
+            }
+            kernel => self.zip(other, kernel, |a, b| a ^ b, simd::xor),
+        }
+    }
+
+    /// Returns the number of bits set in both `self` and `other`
+    pub fn and_count(&self, other: &OptimizedOps) -> usize {
+        assert!(self.bits.words() == other.bits.words());
+
+        match self.kernel_for(other) {
+            Kernel::SparseMerge => {
+                let (sparse, dense) = self.by_density(other);
+                sparse.bits.ones().filter(|&i| dense.bits.test(i)).count()
+            }
+            _ => simd::popcount_and(&self.bits.bits, &other.bits.bits),
+        }
+    }
+
+    /// returns `(sparser, denser)`
+    fn by_density<'a>(&'a self, other: &'a OptimizedOps) -> (&'a OptimizedOps, &'a OptimizedOps) {
+        if self.ones <= other.ones { (self, other) } else { (other, self) }
+    }
+
+    /// returns a copy of `source` cut or padded to the length of `self`
+    fn copy_of(&self, source: &OptimizedOps) -> OptimizedOps {
+        OptimizedOps::new(DenseBitSet::from_vec_masked(source.bits.bits.clone(), self.bits.len()))
+    }
+
+    fn zip(&self, other: &OptimizedOps, kernel: Kernel, f: fn(usize, usize) -> usize, chunked: fn(&mut [usize], &[usize])) -> OptimizedOps {
+        let (a, b) = (&self.bits.bits, &other.bits.bits);
+
+        let words = if kernel == Kernel::Chunked {
+            let mut words = a.clone();
+            chunked(&mut words, b);
+            words
+        } else {
+            a.iter().zip(b).map(|(x, y)| f(*x, *y)).collect()
+        };
+
+        OptimizedOps::new(DenseBitSet::from_vec_masked(words, self.bits.len()))
+    }
+}
+
+impl From<DenseBitSet> for OptimizedOps {
+    fn from(bits: DenseBitSet) -> OptimizedOps {
+        OptimizedOps::new(bits)
+    }
+}
+
+// OptimizedOps TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn check_against_plain(a: &DenseBitSet, b: &DenseBitSet, kernel: Kernel) {
+        let (oa, ob) = (OptimizedOps::new(a.clone()), OptimizedOps::new(b.clone()));
+        assert_eq!(oa.kernel_for(&ob), kernel);
+
+        for (fast, plain) in [(oa.and(&ob), a.and(b)), (oa.or(&ob), a.or(b)), (oa.xor(&ob), a.xor(b))] {
+            assert_eq!(fast.count_ones(), plain.count_ones());
+            assert_eq!(fast.into_inner(), plain);
+        }
+        assert_eq!(oa.and_count(&ob), a.and(b).count_ones());
+    }
+
+    #[test]
+    fn every_kernel_matches_plain_ops() {
+        let len = 200 * 64;
+        let dense = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 3 != 0));
+        let other = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 5 < 2));
+        let sparse = DenseBitSet::from_indices(len, vec![0, 77, len - 1]);
+        let small = DenseBitSet::from_indices(128, (0..128).filter(|i| i % 2 == 0));
+        let small_other = DenseBitSet::from_indices(128, (0..128).filter(|i| i % 7 == 0));
+
+        check_against_plain(&dense, &other, Kernel::Chunked);
+        check_against_plain(&dense, &sparse, Kernel::SparseMerge);
+        check_against_plain(&sparse, &dense, Kernel::SparseMerge);
+        check_against_plain(&small, &small_other, Kernel::WordLoop);
+    }
+
+    #[test]
+    fn every_kernel_keeps_the_left_length() {
+        let len = 200 * 64;
+        let dense = DenseBitSet::from_vec_masked(vec![!0; 200], len - 3);
+        let sparse = DenseBitSet::from_indices(len, vec![0, 77, len - 1]);
+        let other = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 5 < 2));
+
+        check_against_plain(&dense, &sparse, Kernel::SparseMerge);
+        check_against_plain(&sparse, &dense, Kernel::SparseMerge);
+        check_against_plain(&dense, &other, Kernel::Chunked);
+        for (a, b) in [(&dense, &sparse), (&dense, &other)] {
+            let (oa, ob) = (OptimizedOps::new(a.clone()), OptimizedOps::new(b.clone()));
+            assert_eq!(oa.and(&ob).as_bitset().len(), len - 3);
+            assert_eq!(oa.or(&ob).as_bitset().len(), len - 3);
+            assert_eq!(oa.xor(&ob).as_bitset().len(), len - 3);
+        }
+    }
+
+    #[test]
+    fn keeps_count_cached() {
+        let mut bs = OptimizedOps::new(DenseBitSet::with_capacity(256));
+        bs.set(3);
+        bs.set(3);
+        bs.set(200);
+        bs.remove(7);
+
+        assert_eq!(bs.count_ones(), 2);
+        assert_eq!(bs.count_ones(), bs.as_bitset().count_ones());
+    }
+}
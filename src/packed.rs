@@ -0,0 +1,198 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Packing many small sets into one blob with an index table
+//!
+//! A blob is laid out as, all integers little-endian `u64`:
+//!
+//! * the number of sets `n`,
+//! * an index table of `n` entries `(len in bits, payload offset)`,
+//! * the payload: each set as `to_le_bytes()`, back to back.
+//!
+//! The table gives constant-time access to any set without decoding the
+//! others.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use word::{ words_from_le_bytes, words_to_le_bytes };
+use DenseBitSet;
+
+/// bytes per integer in the header and index table
+const FIELD_BYTES: usize = 8;
+/// bytes per index table entry
+const ENTRY_BYTES: usize = 2 * FIELD_BYTES;
+
+/// The error returned when a packed blob is malformed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnpackError {
+    /// the blob ends before its header or index table does
+    Truncated,
+    /// an index table entry points past the end of the payload
+    EntryOutOfBounds {
+        /// position of the entry in the table
+        index: usize,
+    },
+}
+
+impl fmt::Display for UnpackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UnpackError::Truncated => write!(f, "packed blob is truncated"),
+            UnpackError::EntryOutOfBounds { index } => write!(f, "entry {} points past the end of the payload", index),
+        }
+    }
+}
+
+impl Error for UnpackError {}
+
+/// Packs `sets` into a single blob, see the module docs for the layout
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ pack_many, unpack_many, DenseBitSet, PackedSets };
+///
+/// let masks: Vec<DenseBitSet> = (0..1000).map(|i| DenseBitSet::from_bits(i)).collect();
+/// let blob = pack_many(&masks);
+///
+/// let packed = PackedSets::parse(&blob).unwrap();
+/// assert_eq!(packed.get(999), Some(masks[999].clone()));
+/// assert_eq!(unpack_many(&blob).unwrap(), masks);
+/// ```
+pub fn pack_many(sets: &[DenseBitSet]) -> Vec<u8> {
+    let payload_len: usize = sets.iter().map(|set| set.len().div_ceil(8)).sum();
+    let mut blob = Vec::with_capacity(FIELD_BYTES + sets.len() * ENTRY_BYTES + payload_len);
+
+    blob.extend_from_slice(&(sets.len() as u64).to_le_bytes());
+    let mut offset = 0;
+    for set in sets {
+        blob.extend_from_slice(&(set.len() as u64).to_le_bytes());
+        blob.extend_from_slice(&(offset as u64).to_le_bytes());
+        offset += set.len().div_ceil(8);
+    }
+    for set in sets {
+        blob.extend_from_slice(&words_to_le_bytes(&set.bits, set.len()));
+    }
+    blob
+}
+
+/// Unpacks every set of a blob written by `pack_many`
+pub fn unpack_many(blob: &[u8]) -> Result<Vec<DenseBitSet>, UnpackError> {
+    let packed = PackedSets::parse(blob)?;
+    Ok((0..packed.len()).filter_map(|i| packed.get(i)).collect())
+}
+
+/// A validated view of a blob written by `pack_many`, decoding sets on
+/// demand
+#[derive(Clone, Copy, Debug)]
+pub struct PackedSets<'a> {
+    table: &'a [u8],
+    payload: &'a [u8],
+}
+
+fn read_field(bytes: &[u8], at: usize) -> u64 {
+    let mut field = [0; FIELD_BYTES];
+    field.copy_from_slice(&bytes[at..at + FIELD_BYTES]);
+    u64::from_le_bytes(field)
+}
+
+impl<'a> PackedSets<'a> {
+    /// Checks the header and that every index entry lies within the payload
+    pub fn parse(blob: &'a [u8]) -> Result<PackedSets<'a>, UnpackError> {
+        if blob.len() < FIELD_BYTES {
+            return Err(UnpackError::Truncated);
+        }
+        let table_len = usize::try_from(read_field(blob, 0)).ok()
+            .and_then(|n| n.checked_mul(ENTRY_BYTES))
+            .filter(|&len| len <= blob.len() - FIELD_BYTES)
+            .ok_or(UnpackError::Truncated)?;
+
+        let (table, payload) = blob[FIELD_BYTES..].split_at(table_len);
+        let packed = PackedSets { table, payload };
+        for index in 0..packed.len() {
+            packed.entry(index).ok_or(UnpackError::EntryOutOfBounds { index })?;
+        }
+        Ok(packed)
+    }
+
+    /// Returns the number of sets in the blob
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.table.len() / ENTRY_BYTES
+    }
+
+    /// Decodes the ith set, or returns `None` if `i` is out of bounds.
+    /// Bits past the length of the set are ignored.
+    pub fn get(&self, i: usize) -> Option<DenseBitSet> {
+        if i >= self.len() {
+            return None;
+        }
+        let (num_bits, bytes) = self.entry(i)?;
+        Some(DenseBitSet::from_vec_masked(words_from_le_bytes(bytes), num_bits))
+    }
+
+    /// returns the length and payload bytes of entry `i`, or `None` if they
+    /// do not fit the payload
+    fn entry(&self, i: usize) -> Option<(usize, &'a [u8])> {
+        let num_bits = usize::try_from(read_field(self.table, i * ENTRY_BYTES)).ok()?;
+        let offset = usize::try_from(read_field(self.table, i * ENTRY_BYTES + FIELD_BYTES)).ok()?;
+        let end = offset.checked_add(num_bits.div_ceil(8))?;
+        self.payload.get(offset..end).map(|bytes| (num_bits, bytes))
+    }
+}
+
+// PackedSets TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use BITS_PER_WORD;
+
+    #[test]
+    fn round_trips_mixed_lengths() {
+        let sets: Vec<DenseBitSet> = (0..50)
+            .map(|n| DenseBitSet::from_vec_masked(vec![!0; (n * 13usize).div_ceil(BITS_PER_WORD)], n * 13))
+            .collect();
+        let blob = pack_many(&sets);
+
+        assert_eq!(unpack_many(&blob).unwrap(), sets);
+        assert_eq!(PackedSets::parse(&blob).unwrap().get(50), None);
+        assert_eq!(unpack_many(&pack_many(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn rejects_malformed_blobs() {
+        let mut blob = pack_many(&[DenseBitSet::from_bits(0b101), DenseBitSet::from_bits(0b11)]);
+
+        assert_eq!(PackedSets::parse(&blob[..4]).unwrap_err(), UnpackError::Truncated);
+        assert_eq!(PackedSets::parse(&blob[..20]).unwrap_err(), UnpackError::Truncated);
+
+        let payload = blob.len() - FIELD_BYTES - 2 * ENTRY_BYTES;
+        assert_eq!(PackedSets::parse(&blob[..blob.len() - 1]).unwrap_err(), UnpackError::EntryOutOfBounds { index: 1 });
+
+        // point the first entry past the payload
+        blob[FIELD_BYTES + FIELD_BYTES] = payload as u8;
+        assert_eq!(PackedSets::parse(&blob).unwrap_err(), UnpackError::EntryOutOfBounds { index: 0 });
+    }
+}
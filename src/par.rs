@@ -0,0 +1,82 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Parallel traversal of `DenseBitSet`, enabled by the `rayon` feature
+
+use rayon::prelude::*;
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// words handed to a rayon task before it stops splitting
+const MIN_WORDS_PER_TASK: usize = 256;
+
+impl DenseBitSet {
+    /// Calls `f` with the index of every set bit, decoding the words in
+    /// parallel on the rayon thread pool. The word array is split
+    /// recursively and idle threads steal halves from busy ones, so dense
+    /// and sparse regions balance out.
+    ///
+    /// `f` runs concurrently and sees the indices in no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{ AtomicUsize, Ordering };
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(1 << 20, (0..1 << 20).step_by(1000));
+    /// let sum = AtomicUsize::new(0);
+    ///
+    /// bs.par_for_each_one(|i| { sum.fetch_add(i, Ordering::Relaxed); });
+    ///
+    /// assert_eq!(sum.into_inner(), bs.ones().sum());
+    /// ```
+    pub fn par_for_each_one<F: Fn(usize) + Sync + Send>(&self, f: F) {
+        self.bits.par_iter().enumerate().with_min_len(MIN_WORDS_PER_TASK).for_each(|(w, &word)| {
+            let mut word = word;
+            while word != 0 {
+                f(w * BITS_PER_WORD + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
+        });
+    }
+}
+
+// Parallel TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn visits_every_one_exactly_once() {
+        let bs = DenseBitSet::from_indices(100_000, (0..100_000).filter(|i| i % 7 == 0 || i % 4096 < 3));
+        let seen = Mutex::new(Vec::new());
+
+        bs.par_for_each_one(|i| seen.lock().unwrap().push(i));
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen, bs.to_indices());
+    }
+}
@@ -0,0 +1,195 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Parsing `DenseBitSet`s from binary, octal and hexadecimal strings
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use DenseBitSet;
+
+/// An error returned when parsing a `DenseBitSet` from a string fails
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseBitSetError {
+    /// the string held no digits
+    Empty,
+    /// a character that is not a digit of the radix or `_`
+    InvalidDigit {
+        /// byte offset of the character in the input
+        index: usize,
+        /// the offending character
+        found: char,
+    },
+}
+
+impl fmt::Display for ParseBitSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseBitSetError::Empty => write!(f, "cannot parse bit set from empty string"),
+            ParseBitSetError::InvalidDigit { index, found } => {
+                write!(f, "invalid digit {:?} at index {}", found, index)
+            }
+        }
+    }
+}
+
+impl Error for ParseBitSetError {}
+
+impl DenseBitSet {
+    /// Parses digits in `radix`, which must be 2, 4, 8, 16 or 32, into a
+    /// `DenseBitSet`. The string reads like an integer literal: the last
+    /// digit holds bit 0. Underscores may separate digits. Every digit,
+    /// including leading zeros, contributes to the capacity of the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not one of the supported values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::try_from_str_radix("8_1", 16).unwrap();
+    /// assert_eq!(bs.to_indices(), vec![0, 7]);
+    ///
+    /// assert!(DenseBitSet::try_from_str_radix("102", 2).is_err());
+    /// ```
+    pub fn try_from_str_radix(s: &str, radix: u32) -> Result<DenseBitSet, ParseBitSetError> {
+        DenseBitSet::parse_digits(s, 0, radix)
+    }
+
+    /// parses `s[offset..]`, reporting error positions relative to `s`
+    fn parse_digits(s: &str, offset: usize, radix: u32) -> Result<DenseBitSet, ParseBitSetError> {
+        assert!(radix.is_power_of_two() && (2..=32).contains(&radix), "unsupported radix {}", radix);
+        let bits_per_digit = radix.trailing_zeros() as usize;
+
+        let mut digits = Vec::with_capacity(s.len() - offset);
+        for (index, c) in s[offset..].char_indices() {
+            if c == '_' {
+                continue;
+            }
+            match c.to_digit(radix) {
+                Some(digit) => digits.push(digit),
+                None => return Err(ParseBitSetError::InvalidDigit { index: offset + index, found: c }),
+            }
+        }
+        if digits.is_empty() {
+            return Err(ParseBitSetError::Empty);
+        }
+
+        let mut bs = DenseBitSet::with_capacity(digits.len() * bits_per_digit);
+        for (position, digit) in digits.iter().rev().enumerate() {
+            for bit in 0..bits_per_digit {
+                if (digit >> bit) & 1 != 0 {
+                    bs.set(position * bits_per_digit + bit);
+                }
+            }
+        }
+        Ok(bs)
+    }
+}
+
+/// Parses `0b`, `0o` and `0x` prefixed strings in binary, octal and
+/// hexadecimal. Strings without a prefix are read as binary.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs: DenseBitSet = "0b1010_0111".parse().unwrap();
+/// assert_eq!(bs, DenseBitSet::from_bits(0b1010_0111));
+///
+/// let bs: DenseBitSet = "0xdeadbeef".parse().unwrap();
+/// assert_eq!(bs, DenseBitSet::from_bits(0xdeadbeef));
+///
+/// let err = "0x12g4".parse::<DenseBitSet>().unwrap_err();
+/// assert_eq!(err.to_string(), "invalid digit 'g' at index 4");
+/// ```
+impl FromStr for DenseBitSet {
+    type Err = ParseBitSetError;
+
+    fn from_str(s: &str) -> Result<DenseBitSet, ParseBitSetError> {
+        let prefix = s.get(..2).map(|prefix| prefix.to_ascii_lowercase());
+
+        match prefix.as_deref() {
+            Some("0b") => DenseBitSet::parse_digits(s, 2, 2),
+            Some("0o") => DenseBitSet::parse_digits(s, 2, 8),
+            Some("0x") => DenseBitSet::parse_digits(s, 2, 16),
+            _ => DenseBitSet::parse_digits(s, 0, 2),
+        }
+    }
+}
+
+// Parsing TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use BITS_PER_WORD;
+
+    #[test]
+    fn can_parse_prefixed_strings() {
+        let bs: DenseBitSet = "0B1000_0001".parse().unwrap();
+        assert_eq!(bs.to_indices(), vec![0, 7]);
+
+        let bs: DenseBitSet = "0o17".parse().unwrap();
+        assert_eq!(bs.to_indices(), vec![0, 1, 2, 3]);
+
+        let bs: DenseBitSet = "0xF0".parse().unwrap();
+        assert_eq!(bs.to_indices(), vec![4, 5, 6, 7]);
+
+        let bs: DenseBitSet = "110".parse().unwrap();
+        assert_eq!(bs.to_indices(), vec![1, 2]);
+    }
+
+    #[test]
+    fn digits_set_the_capacity() {
+        let long = format!("0x1{}", "0".repeat(31));
+        let bs: DenseBitSet = long.parse().unwrap();
+
+        assert_eq!(bs.words(), 128 / BITS_PER_WORD);
+        assert_eq!(bs.to_indices(), vec![124]);
+    }
+
+    #[test]
+    fn reports_invalid_input() {
+        assert_eq!("".parse::<DenseBitSet>(), Err(ParseBitSetError::Empty));
+        assert_eq!("0x__".parse::<DenseBitSet>(), Err(ParseBitSetError::Empty));
+        assert_eq!(
+            "0b10_21".parse::<DenseBitSet>(),
+            Err(ParseBitSetError::InvalidDigit { index: 5, found: '2' })
+        );
+        assert_eq!(
+            DenseBitSet::try_from_str_radix("1é", 16),
+            Err(ParseBitSetError::InvalidDigit { index: 1, found: 'é' })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_unsupported_radix() {
+        let _ = DenseBitSet::try_from_str_radix("123", 10);
+    }
+}
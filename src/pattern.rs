@@ -0,0 +1,136 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bit pattern search and replacement at arbitrary bit offsets
+
+use { DenseBitSet, BITS_PER_WORD };
+
+impl DenseBitSet {
+    /// Returns the lowest offset `>= from` at which the first `pattern_len`
+    /// bits of `pattern` occur, comparing a word at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let haystack = DenseBitSet::from_bits(0b0110_1100_0110);
+    /// let needle = DenseBitSet::from_bits(0b011);
+    ///
+    /// assert_eq!(haystack.find_pattern(&needle, 3, 0), Some(1));
+    /// assert_eq!(haystack.find_pattern(&needle, 3, 2), Some(6));
+    /// ```
+    pub fn find_pattern(&self, pattern: &DenseBitSet, pattern_len: usize, from: usize) -> Option<usize> {
+        assert!(pattern_len <= pattern.len());
+        if pattern_len > self.len() {
+            return None;
+        }
+
+        (from..=self.len() - pattern_len).find(|&start| self.matches_at(pattern, pattern_len, start))
+    }
+
+    /// Replaces every non-overlapping occurrence of the first `pattern_len`
+    /// bits of `find` with the first `pattern_len` bits of `replace`,
+    /// scanning from bit 0. Replaced regions are not searched again.
+    /// Returns the number of replacements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_bits(0b1_0101);
+    /// let find = DenseBitSet::from_bits(0b01);
+    /// let replace = DenseBitSet::from_bits(0b10);
+    ///
+    /// assert_eq!(bs.replace_pattern(&find, &replace, 2), 3);
+    /// assert_eq!(bs, DenseBitSet::from_bits(0b10_1010));
+    /// ```
+    pub fn replace_pattern(&mut self, find: &DenseBitSet, replace: &DenseBitSet, pattern_len: usize) -> usize {
+        assert!(pattern_len > 0, "pattern_len must be non-zero");
+        assert!(pattern_len <= find.len() && pattern_len <= replace.len());
+
+        let mut replaced = 0;
+        let mut from = 0;
+        while let Some(start) = self.find_pattern(find, pattern_len, from) {
+            for chunk in (0..pattern_len).step_by(BITS_PER_WORD) {
+                let len = (pattern_len - chunk).min(BITS_PER_WORD);
+                self.write_bits(start + chunk, len, replace.read_bits(chunk, len));
+            }
+
+            replaced += 1;
+            from = start + pattern_len;
+        }
+        replaced
+    }
+
+    fn matches_at(&self, pattern: &DenseBitSet, pattern_len: usize, start: usize) -> bool {
+        (0..pattern_len).step_by(BITS_PER_WORD).all(|chunk| {
+            let len = (pattern_len - chunk).min(BITS_PER_WORD);
+            self.read_bits(start + chunk, len) == pattern.read_bits(chunk, len)
+        })
+    }
+}
+
+// Pattern TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_find_patterns_across_words() {
+        let mut haystack = DenseBitSet::with_capacity(512);
+        let needle = DenseBitSet::from_indices(100, vec![0, 70, 99]);
+        for i in &[130, 200, 229] {
+            haystack.set(*i);
+        }
+
+        assert_eq!(haystack.find_pattern(&needle, 100, 0), Some(130));
+        assert_eq!(haystack.find_pattern(&needle, 100, 131), None);
+        assert_eq!(haystack.find_pattern(&DenseBitSet::with_capacity(1024), 600, 0), None);
+    }
+
+    #[test]
+    fn can_replace_long_patterns() {
+        let mut bs = DenseBitSet::with_capacity(1024);
+        let find = DenseBitSet::from_indices(80, vec![0, 79]);
+        let replace = DenseBitSet::from_indices(80, vec![40]);
+        for start in &[10, 300, 390] {
+            bs.set(*start);
+            bs.set(start + 79);
+        }
+
+        assert_eq!(bs.replace_pattern(&find, &replace, 80), 3);
+        assert_eq!(bs.to_indices(), vec![50, 340, 430]);
+    }
+
+    #[test]
+    fn replacements_do_not_overlap() {
+        let mut bs = DenseBitSet::from_bits(0b1111);
+        let find = DenseBitSet::from_bits(0b11);
+        let replace = DenseBitSet::from_bits(0b00);
+
+        assert_eq!(bs.replace_pattern(&find, &replace, 2), 2);
+        assert_eq!(bs.count_ones(), 0);
+    }
+}
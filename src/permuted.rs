@@ -0,0 +1,259 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `PermutedBitSet`, which stores bits in an order given by an index permutation
+
+use { DenseBitSet, BITS_PER_WORD };
+
+/// A bijection between the logical indices `0..len()` and the storage
+/// positions of a `PermutedBitSet`
+pub trait IndexPermutation {
+    /// returns the size of the domain
+    fn len(&self) -> usize;
+
+    /// maps a logical index to its storage position
+    fn to_storage(&self, i: usize) -> usize;
+
+    /// maps a storage position back to its logical index
+    fn to_logical(&self, pos: usize) -> usize;
+
+    /// returns whether the domain is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bit-reversal addressing over `2^bits` indices, the order used by
+/// radix-2 FFTs: index `i` is stored at `i` with its low `bits` bits reversed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BitReversal {
+    bits: u32,
+}
+
+impl BitReversal {
+    /// Creates the bit-reversal permutation of `2^bits` indices
+    pub fn new(bits: u32) -> BitReversal {
+        assert!((bits as usize) < BITS_PER_WORD);
+        BitReversal { bits }
+    }
+
+    fn reverse(&self, i: usize) -> usize {
+        i.reverse_bits().checked_shr(BITS_PER_WORD as u32 - self.bits).unwrap_or(0)
+    }
+}
+
+impl IndexPermutation for BitReversal {
+    fn len(&self) -> usize {
+        1 << self.bits
+    }
+
+    fn to_storage(&self, i: usize) -> usize {
+        self.reverse(i)
+    }
+
+    fn to_logical(&self, pos: usize) -> usize {
+        self.reverse(pos)
+    }
+}
+
+/// Morton (Z-order) addressing for a `2^order × 2^order` grid whose cells
+/// are numbered row by row, `y * side + x`. Storage interleaves the bits of
+/// `x` and `y`, so cells close together in 2D sit close together in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Morton {
+    order: u32,
+}
+
+impl Morton {
+    /// Creates the Morton permutation of a `2^order × 2^order` grid
+    pub fn new(order: u32) -> Morton {
+        assert!((2 * order as usize) < BITS_PER_WORD);
+        Morton { order }
+    }
+
+    /// returns the side length of the grid
+    pub fn side(&self) -> usize {
+        1 << self.order
+    }
+}
+
+impl IndexPermutation for Morton {
+    fn len(&self) -> usize {
+        1 << (2 * self.order)
+    }
+
+    fn to_storage(&self, i: usize) -> usize {
+        let (x, y) = (i & (self.side() - 1), i >> self.order);
+        (0..self.order).fold(0, |pos, b| {
+            pos | (((x >> b) & 1) << (2 * b)) | (((y >> b) & 1) << (2 * b + 1))
+        })
+    }
+
+    fn to_logical(&self, pos: usize) -> usize {
+        let (x, y) = (0..self.order).fold((0, 0), |(x, y), b| {
+            (x | (((pos >> (2 * b)) & 1) << b), y | (((pos >> (2 * b + 1)) & 1) << b))
+        });
+        (y << self.order) | x
+    }
+}
+
+/// A `DenseBitSet` addressed through an `IndexPermutation`. Every index
+/// passed in or handed out is logical; only the storage order differs.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ BitReversal, PermutedBitSet };
+///
+/// let mut bs = PermutedBitSet::new(BitReversal::new(3));
+/// bs.set(1);
+///
+/// assert!(bs.test(1));
+/// assert!(bs.as_storage().test(0b100));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PermutedBitSet<P: IndexPermutation> {
+    bits: DenseBitSet,
+    perm: P,
+}
+
+impl<P: IndexPermutation> PermutedBitSet<P> {
+    /// Creates an empty set covering the domain of `perm`
+    pub fn new(perm: P) -> PermutedBitSet<P> {
+        PermutedBitSet { bits: DenseBitSet::with_capacity(perm.len()), perm }
+    }
+
+    /// returns the permutation
+    pub fn permutation(&self) -> &P {
+        &self.perm
+    }
+
+    /// returns the number of logical indices
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.perm.len()
+    }
+
+    /// Tests whether logical bit `i` is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.len());
+        self.bits.test(self.perm.to_storage(i))
+    }
+
+    /// Sets logical bit `i`.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.len());
+        self.bits.set(self.perm.to_storage(i))
+    }
+
+    /// Clears logical bit `i`.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        assert!(i < self.len());
+        self.bits.remove(self.perm.to_storage(i))
+    }
+
+    /// Flips logical bit `i`
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.len());
+        self.bits.flip(self.perm.to_storage(i))
+    }
+
+    /// returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Returns the logical indices of the set bits, in storage order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.ones().map(move |pos| self.perm.to_logical(pos))
+    }
+
+    /// Returns the logical indices of the set bits, in ascending order
+    pub fn to_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.ones().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Borrows the bits in storage order
+    pub fn as_storage(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the bits in storage order
+    pub fn into_storage(self) -> DenseBitSet {
+        self.bits
+    }
+}
+
+// PermutedBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn is_bijection<P: IndexPermutation>(perm: &P) -> bool {
+        let mut seen = DenseBitSet::with_capacity(perm.len());
+        (0..perm.len()).all(|i| {
+            let pos = perm.to_storage(i);
+            pos < perm.len() && seen.set(pos) && perm.to_logical(pos) == i
+        })
+    }
+
+    #[test]
+    fn permutations_are_bijections() {
+        for bits in 0..10 {
+            assert!(is_bijection(&BitReversal::new(bits)));
+        }
+        for order in 0..5 {
+            assert!(is_bijection(&Morton::new(order)));
+        }
+    }
+
+    #[test]
+    fn can_address_bit_reversed() {
+        let perm = BitReversal::new(4);
+
+        assert_eq!(perm.to_storage(0b0001), 0b1000);
+        assert_eq!(perm.to_storage(0b0110), 0b0110);
+        assert_eq!(perm.to_storage(0b1011), 0b1101);
+    }
+
+    #[test]
+    fn can_address_morton_grid() {
+        let mut grid = PermutedBitSet::new(Morton::new(3));
+        let side = grid.permutation().side();
+
+        // the top-left 2x2 block is the first 4 storage bits
+        for &(x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+            grid.set(y * side + x);
+        }
+        assert_eq!(grid.as_storage().to_indices(), vec![0, 1, 2, 3]);
+        assert_eq!(grid.to_indices(), vec![0, 1, side, side + 1]);
+
+        grid.flip(1);
+        assert!(grid.remove(side));
+        assert_eq!(grid.count_ones(), 2);
+    }
+}
@@ -0,0 +1,228 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Latency instrumentation for the large kernels, enabled by the
+//! `profiling` feature
+//!
+//! Bulk operations, rank index builds and serialization report how long
+//! they took to the recorder installed with `set_latency_recorder`. With no
+//! recorder installed a kernel pays a single relaxed atomic load.
+
+use std::convert::TryFrom;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::sync::{ Arc, RwLock };
+use std::time::{ Duration, Instant };
+
+/// A kernel whose latency is reported
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ProfiledKernel {
+    /// `inplace_and` and `and`
+    BulkAnd,
+    /// `inplace_or` and `or`
+    BulkOr,
+    /// `inplace_xor` and `xor`
+    BulkXor,
+    /// `inplace_not`
+    BulkNot,
+    /// `inplace_difference` and `difference`
+    BulkDifference,
+    /// the fused three-operand ops such as `or_and`
+    BulkFused,
+    /// building or refreshing a `RankSelectBitSet` index
+    RankBuild,
+    /// writing a set through serde or `write_checksummed`
+    Serialize,
+    /// reading a set through serde or `read_checksummed`
+    Deserialize,
+}
+
+impl ProfiledKernel {
+    /// Every kernel, in declaration order
+    pub const ALL: [ProfiledKernel; 9] = [
+        ProfiledKernel::BulkAnd,
+        ProfiledKernel::BulkOr,
+        ProfiledKernel::BulkXor,
+        ProfiledKernel::BulkNot,
+        ProfiledKernel::BulkDifference,
+        ProfiledKernel::BulkFused,
+        ProfiledKernel::RankBuild,
+        ProfiledKernel::Serialize,
+        ProfiledKernel::Deserialize,
+    ];
+}
+
+/// Receives one call per kernel run. Implementations are called from
+/// whichever thread ran the kernel and should be cheap.
+pub trait LatencyRecorder: Send + Sync {
+    /// Records that `kernel` took `elapsed` over `words` words of input,
+    /// or 0 when the size is not known up front, as for deserialization
+    fn record(&self, kernel: ProfiledKernel, words: usize, elapsed: Duration);
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDER: RwLock<Option<Arc<dyn LatencyRecorder>>> = RwLock::new(None);
+
+/// Installs `recorder` for every thread, replacing any previous one
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use bitsets::{ set_latency_recorder, DenseBitSet, LatencyHistogram, ProfiledKernel };
+///
+/// let histogram = Arc::new(LatencyHistogram::new());
+/// set_latency_recorder(histogram.clone());
+///
+/// let mut bs = DenseBitSet::with_capacity(1 << 20);
+/// bs.inplace_or(&DenseBitSet::with_capacity(1 << 20));
+///
+/// assert!(histogram.count(ProfiledKernel::BulkOr) >= 1);
+/// assert!(histogram.quantile(ProfiledKernel::BulkOr, 0.99).is_some());
+/// ```
+pub fn set_latency_recorder(recorder: Arc<dyn LatencyRecorder>) {
+    *RECORDER.write().unwrap_or_else(|e| e.into_inner()) = Some(recorder);
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Removes the installed recorder, if any
+pub fn clear_latency_recorder() {
+    ENABLED.store(false, Ordering::Release);
+    *RECORDER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// runs `f`, reporting its latency to the installed recorder along with
+/// the number of words `words` derives from its output
+#[inline]
+pub(crate) fn timed<T, F: FnOnce() -> T, C: FnOnce(&T) -> usize>(kernel: ProfiledKernel, f: F, words: C) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let output = f();
+    let elapsed = start.elapsed();
+
+    // release the lock before calling out, in case the recorder swaps itself
+    let recorder = RECORDER.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(recorder) = recorder {
+        recorder.record(kernel, words(&output), elapsed);
+    }
+    output
+}
+
+/// number of power-of-two latency buckets, enough for any `u64` of nanoseconds
+const BUCKETS: usize = 65;
+
+/// A lock-free `LatencyRecorder` keeping one histogram per kernel with
+/// power-of-two nanosecond buckets
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    /// `BUCKETS` counters per kernel; bucket `b > 0` counts latencies in
+    /// `[2^(b-1), 2^b)` nanoseconds
+    buckets: Vec<AtomicU64>,
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram { buckets: (0..ProfiledKernel::ALL.len() * BUCKETS).map(|_| AtomicU64::new(0)).collect() }
+    }
+
+    fn kernel_buckets(&self, kernel: ProfiledKernel) -> &[AtomicU64] {
+        let k = kernel as usize;
+        &self.buckets[k * BUCKETS..(k + 1) * BUCKETS]
+    }
+
+    /// Returns the number of runs of `kernel` recorded
+    pub fn count(&self, kernel: ProfiledKernel) -> u64 {
+        self.kernel_buckets(kernel).iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Returns an upper bound on the `q` quantile of the latency of
+    /// `kernel`, with `q` in `[0, 1]`, or `None` if it never ran
+    pub fn quantile(&self, kernel: ProfiledKernel, q: f64) -> Option<Duration> {
+        let count = self.count(kernel);
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (b, bucket) in self.kernel_buckets(kernel).iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                let nanos = if b == 0 { 0 } else { 1u64.checked_shl(b as u32).map_or(u64::MAX, |n| n - 1) };
+                return Some(Duration::from_nanos(nanos));
+            }
+        }
+        None
+    }
+
+    /// Clears every bucket
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> LatencyHistogram {
+        LatencyHistogram::new()
+    }
+}
+
+impl LatencyRecorder for LatencyHistogram {
+    fn record(&self, kernel: ProfiledKernel, _words: usize, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        let b = (u64::BITS - nanos.leading_zeros()) as usize;
+        self.kernel_buckets(kernel)[b].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// profiling TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_by_power_of_two() {
+        let hist = LatencyHistogram::new();
+        for nanos in [0, 1, 3, 100, 100, 100, 5000] {
+            hist.record(ProfiledKernel::RankBuild, 0, Duration::from_nanos(nanos));
+        }
+
+        assert_eq!(hist.count(ProfiledKernel::RankBuild), 7);
+        assert_eq!(hist.count(ProfiledKernel::BulkAnd), 0);
+        assert_eq!(hist.quantile(ProfiledKernel::RankBuild, 0.0), Some(Duration::from_nanos(0)));
+        assert_eq!(hist.quantile(ProfiledKernel::RankBuild, 0.5), Some(Duration::from_nanos(127)));
+        assert_eq!(hist.quantile(ProfiledKernel::RankBuild, 1.0), Some(Duration::from_nanos(8191)));
+        assert_eq!(hist.quantile(ProfiledKernel::BulkAnd, 0.5), None);
+
+        hist.record(ProfiledKernel::Serialize, 0, Duration::from_secs(u64::MAX));
+        assert_eq!(hist.quantile(ProfiledKernel::Serialize, 1.0), Some(Duration::from_nanos(u64::MAX)));
+
+        hist.reset();
+        assert_eq!(hist.count(ProfiledKernel::RankBuild), 0);
+    }
+}
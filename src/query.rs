@@ -0,0 +1,417 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A small expression language for combining named `DenseBitSet`s
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{ BuildHasher, Hash };
+use std::str::FromStr;
+
+use DenseBitSet;
+
+/// An error returned when parsing or evaluating a `Query` fails
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QueryError {
+    /// a character that cannot start a token
+    UnexpectedChar {
+        /// byte offset of the character in the query
+        index: usize,
+        /// the offending character
+        found: char,
+    },
+    /// a token where an operand or operator was expected
+    UnexpectedToken {
+        /// byte offset of the token in the query
+        index: usize,
+    },
+    /// the query ended where an operand or `)` was expected
+    UnexpectedEnd,
+    /// parentheses and `!` nest deeper than `MAX_QUERY_NESTING`
+    TooDeep {
+        /// byte offset of the token past the limit
+        index: usize,
+    },
+    /// the query names a set that was not supplied
+    UnknownSet(String),
+    /// a named set differs in length from the first one
+    LengthMismatch {
+        /// the name of the set
+        name: String,
+        /// the length of the first set
+        expected: usize,
+        /// the length of this set
+        found: usize,
+    },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::UnexpectedChar { index, found } => {
+                write!(f, "unexpected character {:?} at index {}", found, index)
+            }
+            QueryError::UnexpectedToken { index } => write!(f, "unexpected token at index {}", index),
+            QueryError::UnexpectedEnd => write!(f, "unexpected end of query"),
+            QueryError::TooDeep { index } => {
+                write!(f, "query nests deeper than {} at index {}", MAX_QUERY_NESTING, index)
+            }
+            QueryError::UnknownSet(ref name) => write!(f, "unknown set {:?}", name),
+            QueryError::LengthMismatch { ref name, expected, found } => {
+                write!(f, "set {:?} has {} bits, expected {}", name, found, expected)
+            }
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+/// one instruction of the postfix program a query compiles to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Op {
+    /// pushes the word of the nth named set
+    Load(usize),
+    Not,
+    And,
+    Or,
+    Xor,
+    AndNot,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token {
+    Name(usize, usize),
+    Symbol(char),
+}
+
+/// A parsed set expression over named bit sets.
+///
+/// Names are made of letters, digits, `_` and `.`. The operators, from
+/// tightest to loosest binding, are `!` (complement), `-` (difference),
+/// `&` (intersection), `^` (symmetric difference) and `|` (union), with
+/// parentheses for grouping.
+///
+/// A query compiles to a postfix program that is evaluated one word at a
+/// time across every operand, so however large the expression, evaluation
+/// makes a single pass and allocates only the result.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use bitsets::{ DenseBitSet, Query };
+///
+/// let mut sets = HashMap::new();
+/// sets.insert("users", DenseBitSet::from_indices(64, 0..10));
+/// sets.insert("premium", DenseBitSet::from_indices(64, [2, 3, 5, 7, 11]));
+/// sets.insert("banned", DenseBitSet::from_indices(64, [3]));
+///
+/// let query: Query = "(users & premium) - banned".parse().unwrap();
+/// assert_eq!(query.eval(&sets).unwrap().to_indices(), vec![2, 5, 7]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Query {
+    names: Vec<String>,
+    program: Vec<Op>,
+    depth: usize,
+}
+
+impl Query {
+    /// Parses `query`
+    pub fn parse(query: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { query, tokens: &tokens, pos: 0, nesting: 0, names: Vec::new(), program: Vec::new() };
+        parser.expr(0)?;
+        if let Some(&(index, _)) = tokens.get(parser.pos) {
+            return Err(QueryError::UnexpectedToken { index });
+        }
+
+        let (names, program) = (parser.names, parser.program);
+        let mut depth = 0;
+        let mut max_depth = 0;
+        for op in &program {
+            match *op {
+                Op::Load(_) => depth += 1,
+                Op::Not => {}
+                _ => depth -= 1,
+            }
+            max_depth = max_depth.max(depth);
+        }
+        Ok(Query { names, program, depth: max_depth })
+    }
+
+    /// Returns the distinct set names the query refers to, in order of first use
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|name| name.as_str())
+    }
+
+    /// Evaluates the query against the sets in `sets`
+    pub fn eval<K, S>(&self, sets: &HashMap<K, DenseBitSet, S>) -> Result<DenseBitSet, QueryError>
+        where K: Borrow<str> + Hash + Eq, S: BuildHasher
+    {
+        self.eval_with(|name| sets.get(name))
+    }
+
+    /// Evaluates the query, resolving each name with `lookup`. Every set
+    /// must have the same length.
+    pub fn eval_with<'a, F>(&self, lookup: F) -> Result<DenseBitSet, QueryError>
+        where F: Fn(&str) -> Option<&'a DenseBitSet>
+    {
+        let mut operands = Vec::with_capacity(self.names.len());
+        for name in &self.names {
+            let set = lookup(name).ok_or_else(|| QueryError::UnknownSet(name.clone()))?;
+            if let Some(first) = operands.first().map(|first: &&DenseBitSet| first.len()) {
+                if set.len() != first {
+                    return Err(QueryError::LengthMismatch { name: name.clone(), expected: first, found: set.len() });
+                }
+            }
+            operands.push(set);
+        }
+
+        let num_bits = operands[0].len();
+        let words: Vec<&[usize]> = operands.iter().map(|set| &set.bits[..]).collect();
+        let mut stack = Vec::with_capacity(self.depth);
+        let output = (0..operands[0].words()).map(|w| {
+            stack.clear();
+            for op in &self.program {
+                if let Op::Load(n) = *op {
+                    stack.push(words[n][w]);
+                    continue;
+                }
+                let top = stack.pop().unwrap();
+                let result = match *op {
+                    Op::Not => !top,
+                    Op::And => stack.pop().unwrap() & top,
+                    Op::Or => stack.pop().unwrap() | top,
+                    Op::Xor => stack.pop().unwrap() ^ top,
+                    Op::AndNot => stack.pop().unwrap() & !top,
+                    Op::Load(_) => unreachable!(),
+                };
+                stack.push(result);
+            }
+            stack[0]
+        }).collect();
+
+        Ok(DenseBitSet::from_vec_masked(output, num_bits))
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Query, QueryError> {
+        Query::parse(s)
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn tokenize(query: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '(' | ')' | '!' | '-' | '&' | '^' | '|' => tokens.push((index, Token::Symbol(c))),
+            c if c.is_whitespace() => {}
+            c if is_name_char(c) => {
+                let mut end = index + c.len_utf8();
+                while let Some(&(next, c)) = chars.peek() {
+                    if !is_name_char(c) {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push((index, Token::Name(index, end)));
+            }
+            found => return Err(QueryError::UnexpectedChar { index, found }),
+        }
+    }
+    Ok(tokens)
+}
+
+/// deepest nesting of parentheses and `!` a query may use. The parser
+/// recurses once per level, and queries can come from untrusted input.
+pub const MAX_QUERY_NESTING: usize = 256;
+
+/// binary operators with their binding power, loosest first
+const BINARY: [(char, usize, Op); 4] = [('|', 1, Op::Or), ('^', 2, Op::Xor), ('&', 3, Op::And), ('-', 4, Op::AndNot)];
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: &'a [(usize, Token)],
+    pos: usize,
+    /// parentheses and `!` currently open
+    nesting: usize,
+    names: Vec<String>,
+    program: Vec<Op>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<(usize, Token), QueryError> {
+        let token = *self.tokens.get(self.pos).ok_or(QueryError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// parses operators binding tighter than `min_power`, by precedence climbing
+    fn expr(&mut self, min_power: usize) -> Result<(), QueryError> {
+        self.operand()?;
+        while let Some(&(_, Token::Symbol(c))) = self.tokens.get(self.pos) {
+            let (power, op) = match BINARY.iter().find(|&&(symbol, _, _)| symbol == c) {
+                Some(&(_, power, op)) if power > min_power => (power, op),
+                _ => break,
+            };
+            self.pos += 1;
+            self.expr(power)?;
+            self.program.push(op);
+        }
+        Ok(())
+    }
+
+    fn operand(&mut self) -> Result<(), QueryError> {
+        let token = self.next()?;
+        if let (index, Token::Symbol('!')) | (index, Token::Symbol('(')) = token {
+            if self.nesting == MAX_QUERY_NESTING {
+                return Err(QueryError::TooDeep { index });
+            }
+            self.nesting += 1;
+            let result = self.nested(token.1);
+            self.nesting -= 1;
+            return result;
+        }
+
+        match token {
+            (_, Token::Name(start, end)) => {
+                let name = &self.query[start..end];
+                let slot = match self.names.iter().position(|known| known == name) {
+                    Some(slot) => slot,
+                    None => {
+                        self.names.push(name.to_string());
+                        self.names.len() - 1
+                    }
+                };
+                self.program.push(Op::Load(slot));
+            }
+            (index, _) => return Err(QueryError::UnexpectedToken { index }),
+        }
+        Ok(())
+    }
+
+    /// parses what follows an opening `!` or `(`
+    fn nested(&mut self, opening: Token) -> Result<(), QueryError> {
+        if opening == Token::Symbol('!') {
+            self.operand()?;
+            self.program.push(Op::Not);
+            return Ok(());
+        }
+
+        self.expr(0)?;
+        match self.next()? {
+            (_, Token::Symbol(')')) => Ok(()),
+            (index, _) => Err(QueryError::UnexpectedToken { index }),
+        }
+    }
+}
+
+// Query TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn sets() -> HashMap<&'static str, DenseBitSet> {
+        let mut sets = HashMap::new();
+        sets.insert("a", DenseBitSet::from_indices(100, (0..100).filter(|i| i % 2 == 0)));
+        sets.insert("b", DenseBitSet::from_indices(100, (0..100).filter(|i| i % 3 == 0)));
+        sets.insert("c", DenseBitSet::from_indices(100, (0..100).filter(|i| i % 5 == 0)));
+        sets
+    }
+
+    fn not(set: &DenseBitSet) -> DenseBitSet {
+        let mut output = set.clone();
+        output.inplace_not();
+        output
+    }
+
+    #[test]
+    fn follows_precedence() {
+        let sets = sets();
+        let (a, b, c) = (&sets["a"], &sets["b"], &sets["c"]);
+        let eval = |query: &str| query.parse::<Query>().unwrap().eval(&sets).unwrap();
+
+        assert_eq!(eval("a | b & c"), a.or(&b.and(c)));
+        assert_eq!(eval("(a | b) & c"), a.or(b).and(c));
+        assert_eq!(eval("a ^ b - c"), a.xor(&b.and(&not(c))));
+        assert_eq!(eval("a - b - c"), a.and(&not(b)).and(&not(c)));
+        assert_eq!(eval("!a & !!b"), not(a).and(b));
+        assert_eq!(eval("!(a|b|c)").count_ones(), not(&a.or(b).or(c)).count_ones());
+    }
+
+    #[test]
+    fn complement_stays_within_len() {
+        let mut sets = HashMap::new();
+        sets.insert("empty".to_string(), DenseBitSet::from_vec_masked(vec![0; 2], 70));
+        let all = Query::parse("!empty").unwrap().eval(&sets).unwrap();
+        assert_eq!(all.len(), 70);
+        assert_eq!(all.count_ones(), 70);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(Query::parse("a & $"), Err(QueryError::UnexpectedChar { index: 4, found: '$' }));
+        assert_eq!(Query::parse("a &"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(Query::parse("a b"), Err(QueryError::UnexpectedToken { index: 2 }));
+        assert_eq!(Query::parse("(a | b"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(Query::parse(")"), Err(QueryError::UnexpectedToken { index: 0 }));
+
+        let mut sets = sets();
+        let query = Query::parse("a & users.premium").unwrap();
+        assert_eq!(query.names().collect::<Vec<_>>(), vec!["a", "users.premium"]);
+        assert_eq!(query.eval(&sets), Err(QueryError::UnknownSet("users.premium".to_string())));
+
+        sets.insert("users.premium", DenseBitSet::with_capacity(200));
+        assert_eq!(
+            query.eval(&sets),
+            Err(QueryError::LengthMismatch { name: "users.premium".to_string(), expected: 128, found: 256 })
+        );
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let deep = format!("{}a{}", "(".repeat(200_000), ")".repeat(200_000));
+        assert_eq!(Query::parse(&deep), Err(QueryError::TooDeep { index: MAX_QUERY_NESTING }));
+
+        let negated = format!("{}a", "!".repeat(200_000));
+        assert_eq!(Query::parse(&negated), Err(QueryError::TooDeep { index: MAX_QUERY_NESTING }));
+
+        // the limit itself is fine
+        let limit = format!("{}a{}", "(".repeat(MAX_QUERY_NESTING), ")".repeat(MAX_QUERY_NESTING));
+        assert_eq!(Query::parse(&limit).unwrap().eval(&sets()).unwrap(), sets()["a"]);
+        let mixed = format!("{}a{}", "!(".repeat(MAX_QUERY_NESTING / 2), ")".repeat(MAX_QUERY_NESTING / 2));
+        assert!(Query::parse(&mixed).is_ok());
+    }
+}
@@ -0,0 +1,318 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bulk operations and queries over ranges of bits
+//!
+//! A range is split into one mask per word it touches, so only the words at
+//! either end need partial masks and the words in between are handled whole.
+
+use std::ops::{ Bound, Range, RangeBounds };
+
+use { get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// Resolves `range` against a set of `len` bits, panicking if it is out of bounds
+pub(crate) fn to_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&s) => s,
+        Bound::Excluded(&s) => s + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&e) => e + 1,
+        Bound::Excluded(&e) => e,
+        Bound::Unbounded => len,
+    };
+
+    assert!(start <= end, "range start {} is greater than end {}", start, end);
+    assert!(end <= len, "range end {} is out of bounds for a set of {} bits", end, len);
+    start..end
+}
+
+/// Yields `(word index, mask)` for every word overlapping `range`
+fn word_masks(range: Range<usize>) -> impl Iterator<Item = (usize, usize)> {
+    let (first, last) = if range.start < range.end {
+        (get_word_offset(range.start), get_word_offset(range.end - 1) + 1)
+    } else {
+        (0, 0)
+    };
+
+    (first..last).map(move |w| {
+        let lo = range.start.saturating_sub(w * BITS_PER_WORD);
+        let hi = (range.end - w * BITS_PER_WORD).min(BITS_PER_WORD);
+        (w, get_low_mask(hi) & !get_low_mask(lo))
+    })
+}
+
+impl DenseBitSet {
+    /// Copies the bits in `range` into a new set starting at bit 0, one
+    /// shifted word at a time
+    pub(crate) fn extract(&self, range: Range<usize>) -> DenseBitSet {
+        let len = range.end - range.start;
+        let words = (range.start..range.end)
+            .step_by(BITS_PER_WORD)
+            .map(|start| self.read_bits(start, (range.end - start).min(BITS_PER_WORD)))
+            .collect();
+        DenseBitSet::from_vec_masked(words, len)
+    }
+
+    /// Sets every bit in `range`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(256);
+    /// bs.set_range(10..200);
+    ///
+    /// assert_eq!(bs.count_ones(), 190);
+    /// assert!(!bs.test(9) && bs.test(10) && bs.test(199) && !bs.test(200));
+    /// ```
+    pub fn set_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        for (w, mask) in word_masks(to_range(range, self.len())) {
+            self.bits[w] |= mask;
+        }
+    }
+
+    /// Clears every bit in `range`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity_and_state(128, !0);
+    /// bs.clear_range(..64);
+    ///
+    /// assert_eq!(bs.count_ones(), 64);
+    /// ```
+    pub fn clear_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        for (w, mask) in word_masks(to_range(range, self.len())) {
+            self.bits[w] &= !mask;
+        }
+    }
+
+    /// Flips every bit in `range`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_bits(0b0110);
+    /// bs.flip_range(1..=3);
+    ///
+    /// assert_eq!(bs.to_indices(), vec![3]);
+    /// ```
+    pub fn flip_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        for (w, mask) in word_masks(to_range(range, self.len())) {
+            self.bits[w] ^= mask;
+        }
+    }
+
+    /// Returns whether every bit in `range` is set. An empty range is
+    /// vacuously all set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(256);
+    /// bs.set_range(60..130);
+    ///
+    /// assert!(bs.all_in_range(60..130));
+    /// assert!(!bs.all_in_range(59..130));
+    /// ```
+    pub fn all_in_range<R: RangeBounds<usize>>(&self, range: R) -> bool {
+        word_masks(to_range(range, self.len())).all(|(w, mask)| self.bits[w] & mask == mask)
+    }
+
+    /// Returns whether any bit in `range` is set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![200]);
+    ///
+    /// assert!(bs.any_in_range(100..=200));
+    /// assert!(!bs.any_in_range(..200));
+    /// ```
+    pub fn any_in_range<R: RangeBounds<usize>>(&self, range: R) -> bool {
+        word_masks(to_range(range, self.len())).any(|(w, mask)| self.bits[w] & mask != 0)
+    }
+
+    /// Returns the number of set bits in `range`, i.e. the rank of
+    /// `range.end` minus the rank of `range.start`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 64, 65, 200]);
+    ///
+    /// assert_eq!(bs.count_ones_in_range(3..=65), 3);
+    /// assert_eq!(bs.count_ones_in_range(66..), 1);
+    /// ```
+    pub fn count_ones_in_range<R: RangeBounds<usize>>(&self, range: R) -> usize {
+        word_masks(to_range(range, self.len()))
+            .map(|(w, mask)| (self.bits[w] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    /// Copies the bits in `range` into a new set of `range.len()` bits,
+    /// renumbered from 0. Unaligned ranges are shifted a word at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 64, 65, 200]);
+    /// let window = bs.get_range(60..70);
+    ///
+    /// assert_eq!(window.len(), 10);
+    /// assert_eq!(window.to_indices(), vec![4, 5]);
+    /// ```
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> DenseBitSet {
+        self.extract(to_range(range, self.len()))
+    }
+
+    /// Copies `len` bits of `src` starting at `src_start` over the bits of
+    /// `self` starting at `dst_start`, a bit-level `memcpy`. Either offset
+    /// may be unaligned; bits are moved a shifted word at a time. Panics if
+    /// either range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let fragment = DenseBitSet::from_indices(64, vec![0, 2, 3]);
+    /// let mut frame = DenseBitSet::with_capacity(128);
+    /// frame.copy_bits_from(&fragment, 0, 61, 4);
+    ///
+    /// assert_eq!(frame.to_indices(), vec![61, 63, 64]);
+    /// ```
+    pub fn copy_bits_from(&mut self, src: &DenseBitSet, src_start: usize, dst_start: usize, len: usize) {
+        assert!(src_start + len <= src.len(), "source range is out of bounds");
+        assert!(dst_start + len <= self.len(), "destination range is out of bounds");
+
+        for done in (0..len).step_by(BITS_PER_WORD) {
+            let chunk = (len - done).min(BITS_PER_WORD);
+            let value = src.read_bits(src_start + done, chunk);
+            self.write_bits(dst_start + done, chunk, value);
+        }
+    }
+}
+
+// Range TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn range_ops_match_single_bit_ops() {
+        let ranges = [0..0, 0..1, 3..64, 63..65, 64..128, 5..250, 0..256, 255..256];
+
+        for range in ranges {
+            let mut bulk = DenseBitSet::from_indices(256, (0..256).filter(|i| i % 3 == 0));
+            let mut single = bulk.clone();
+
+            bulk.flip_range(range.clone());
+            for i in range.clone() {
+                single.flip(i);
+            }
+            assert_eq!(bulk, single);
+
+            bulk.set_range(range.clone());
+            assert!(range.clone().all(|i| bulk.test(i)));
+            bulk.clear_range(range.clone());
+            assert!(range.clone().all(|i| !bulk.test(i)));
+        }
+    }
+
+    #[test]
+    fn range_queries_match_single_bit_tests() {
+        let bs = DenseBitSet::from_indices(256, (0..256).filter(|&i| i < 70 || i == 130 || i > 190));
+
+        for start in (0..256).step_by(7) {
+            for end in (start..=256).step_by(11) {
+                assert_eq!(bs.all_in_range(start..end), (start..end).all(|i| bs.test(i)));
+                assert_eq!(bs.any_in_range(start..end), (start..end).any(|i| bs.test(i)));
+                assert_eq!(bs.count_ones_in_range(start..end), (start..end).filter(|&i| bs.test(i)).count());
+            }
+        }
+    }
+
+    #[test]
+    fn get_range_matches_single_bit_tests() {
+        let bs = DenseBitSet::from_indices(256, (0..256).filter(|&i| i % 3 == 0 || i > 190));
+
+        for start in (0..256).step_by(7) {
+            for end in (start..=256).step_by(11) {
+                let window = bs.get_range(start..end);
+                assert_eq!(window.len(), end - start);
+                assert!((start..end).all(|i| window.test(i - start) == bs.test(i)));
+                assert_eq!(window.count_ones(), bs.count_ones_in_range(start..end));
+            }
+        }
+        assert_eq!(bs.get_range(..), bs);
+    }
+
+    #[test]
+    fn copy_bits_matches_single_bit_copies() {
+        let src = DenseBitSet::from_indices(256, (0..256).filter(|&i| i % 3 == 0 || i > 190));
+
+        for &(src_start, dst_start, len) in &[(0, 0, 256), (5, 70, 150), (64, 3, 64), (130, 0, 126), (7, 200, 1), (9, 9, 0)] {
+            let mut bulk = DenseBitSet::from_indices(320, (0..320).filter(|&i| i % 2 == 0));
+            let mut single = bulk.clone();
+
+            bulk.copy_bits_from(&src, src_start, dst_start, len);
+            for i in 0..len {
+                if src.test(src_start + i) {
+                    single.set(dst_start + i);
+                } else {
+                    single.remove(dst_start + i);
+                }
+            }
+            assert_eq!(bulk, single);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_copy_past_end() {
+        let src = DenseBitSet::with_capacity(64);
+        DenseBitSet::with_capacity(64).copy_bits_from(&src, 0, 1, 64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_range_past_end() {
+        DenseBitSet::with_capacity(64).set_range(0..65);
+    }
+}
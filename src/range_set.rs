@@ -0,0 +1,184 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `RangeSet`, interval arithmetic over a bounded universe
+
+use std::ops::RangeBounds;
+
+use { DenseBitSet, DenseRunsIterator, BITS_PER_WORD };
+
+/// A set of indices below a fixed universe, manipulated as ranges.
+///
+/// Ranges are stored as bits of a `DenseBitSet`, so overlapping and
+/// adjacent inserts merge for free, and `iter_ranges()` rebuilds maximal
+/// ranges from the runs of set bits. Suited to small bounded universes
+/// such as port numbers or disk block addresses.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::RangeSet;
+///
+/// let mut ports = RangeSet::new(1 << 16);
+/// ports.insert_range(8000..8100);
+/// ports.insert_range(8050..=8200);
+/// ports.remove_range(8080..8090);
+///
+/// assert!(ports.contains_range(8000..8080));
+/// assert_eq!(ports.iter_ranges().collect::<Vec<_>>(), vec![8000..8080, 8090..8201]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RangeSet {
+    bits: DenseBitSet,
+}
+
+impl RangeSet {
+    /// Creates an empty set over the indices `0..universe`
+    pub fn new(universe: usize) -> RangeSet {
+        RangeSet { bits: DenseBitSet::from_vec_masked(vec![0; universe.div_ceil(BITS_PER_WORD)], universe) }
+    }
+
+    /// Returns the number of indices the set can hold
+    pub fn universe(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns the number of indices in the set
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Returns whether the set holds no indices
+    pub fn is_empty(&self) -> bool {
+        self.bits.first_set().is_none()
+    }
+
+    /// Borrows the underlying set
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the underlying set
+    pub fn into_inner(self) -> DenseBitSet {
+        self.bits
+    }
+
+    /// Adds every index in `range`, panicking if it reaches past the universe
+    pub fn insert_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        self.bits.set_range(range);
+    }
+
+    /// Removes every index in `range`, panicking if it reaches past the universe
+    pub fn remove_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        self.bits.clear_range(range);
+    }
+
+    /// Returns whether `i` is in the set
+    pub fn contains(&self, i: usize) -> bool {
+        i < self.bits.len() && self.bits.test(i)
+    }
+
+    /// Returns whether every index in `range` is in the set. An empty range
+    /// is always contained.
+    pub fn contains_range<R: RangeBounds<usize>>(&self, range: R) -> bool {
+        self.bits.all_in_range(range)
+    }
+
+    /// Returns the maximal ranges of indices in the set, in increasing order
+    pub fn iter_ranges(&self) -> DenseRunsIterator<'_> {
+        self.bits.runs()
+    }
+
+    /// Returns the maximal ranges of the universe not in the set, in
+    /// increasing order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::RangeSet;
+    ///
+    /// let mut used = RangeSet::new(100);
+    /// used.insert_range(10..20);
+    /// used.insert_range(50..);
+    ///
+    /// assert_eq!(used.complement_ranges().collect::<Vec<_>>(), vec![0..10, 20..50]);
+    /// ```
+    pub fn complement_ranges(&self) -> DenseRunsIterator<'_> {
+        DenseRunsIterator::new(&self.bits, false)
+    }
+}
+
+impl From<DenseBitSet> for RangeSet {
+    fn from(bits: DenseBitSet) -> RangeSet {
+        RangeSet { bits }
+    }
+}
+
+// RangeSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn inserts_merge_and_removes_split() {
+        let mut lbas = RangeSet::new(1000);
+        lbas.insert_range(100..200);
+        lbas.insert_range(200..300);
+        lbas.insert_range(250..400);
+        lbas.remove_range(120..130);
+
+        assert_eq!(lbas.iter_ranges().collect::<Vec<_>>(), vec![100..120, 130..400]);
+        assert_eq!(lbas.len(), 290);
+        assert!(lbas.contains_range(130..400));
+        assert!(!lbas.contains_range(119..121));
+        assert!(lbas.contains_range(5..5));
+    }
+
+    #[test]
+    fn ranges_and_complement_tile_the_universe() {
+        let mut set = RangeSet::new(300);
+        for r in [0..3, 63..65, 127..128, 200..300] {
+            set.insert_range(r);
+        }
+
+        let mut tiles: Vec<_> = set.iter_ranges().chain(set.complement_ranges()).collect();
+        tiles.sort_by_key(|r| r.start);
+        assert!(tiles.windows(2).all(|w| w[0].end == w[1].start));
+        assert_eq!((tiles[0].start, tiles[tiles.len() - 1].end), (0, 300));
+        assert_eq!(set.complement_ranges().collect::<Vec<_>>(), vec![3..63, 65..127, 128..200]);
+    }
+
+    #[test]
+    fn empty_and_full_sets() {
+        let mut set = RangeSet::new(70);
+        assert!(set.is_empty());
+        assert_eq!(set.iter_ranges().count(), 0);
+        assert_eq!(set.complement_ranges().collect::<Vec<_>>(), vec![0..70]);
+
+        set.insert_range(..);
+        assert_eq!(set.iter_ranges().collect::<Vec<_>>(), vec![0..70]);
+        assert_eq!(set.complement_ranges().count(), 0);
+        assert!(!set.contains(70));
+    }
+}
@@ -0,0 +1,112 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Rank and select over `DenseBitSet`
+
+use { get_bit_offset, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// Returns the bit offset of the `k`th (0-based) set bit of `word`.
+/// `k` must be less than `word.count_ones()`.
+#[inline]
+pub(crate) fn select_in_word(mut word: usize, k: usize) -> usize {
+    debug_assert!(k < word.count_ones() as usize);
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl DenseBitSet {
+    /// Returns the number of set bits strictly below `i`.
+    /// Panics if `i > len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 64, 65, 200]);
+    ///
+    /// assert_eq!(bs.rank(3), 0);
+    /// assert_eq!(bs.rank(4), 1);
+    /// assert_eq!(bs.rank(200), 3);
+    /// assert_eq!(bs.rank(256), 4);
+    /// ```
+    pub fn rank(&self, i: usize) -> usize {
+        assert!(i <= self.len());
+
+        let w = get_word_offset(i);
+        let full: usize = self.bits[..w].iter().map(|word| word.count_ones() as usize).sum();
+        let partial = match get_bit_offset(i) {
+            0 => 0,
+            offset => (self.bits[w] & get_low_mask(offset)).count_ones() as usize,
+        };
+        full + partial
+    }
+
+    /// Returns the index of the `k`th (0-based) set bit, or `None` if fewer
+    /// than `k + 1` bits are set. `rank(select(k)) == k` for every such `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 64, 65, 200]);
+    ///
+    /// assert_eq!(bs.select(0), Some(3));
+    /// assert_eq!(bs.select(2), Some(65));
+    /// assert_eq!(bs.select(4), None);
+    /// ```
+    pub fn select(&self, k: usize) -> Option<usize> {
+        let mut remaining = k;
+        for (w, &word) in self.bits.iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                return Some(w * BITS_PER_WORD + select_in_word(word, remaining));
+            }
+            remaining -= ones;
+        }
+        None
+    }
+}
+
+// Rank TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rank_and_select_are_inverse() {
+        let bs = DenseBitSet::from_indices(500, (0..500).filter(|i| i % 13 == 0 || i % 64 == 63));
+        let indices = bs.to_indices();
+
+        for (k, &i) in indices.iter().enumerate() {
+            assert_eq!(bs.select(k), Some(i));
+            assert_eq!(bs.rank(i), k);
+            assert_eq!(bs.rank(i + 1), k + 1);
+        }
+        assert_eq!(bs.select(indices.len()), None);
+        assert_eq!(bs.rank(bs.len()), indices.len());
+    }
+}
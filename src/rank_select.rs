@@ -0,0 +1,335 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `RankSelectBitSet`, a set with a precomputed rank/select index
+
+use std::ops::Range;
+use std::time::Instant;
+
+use rank::select_in_word;
+use { get_bit_offset, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// words covered by one block count
+const BLOCK_WORDS: usize = 8;
+/// bits covered by one superblock count; block counts are relative to
+/// their superblock so they fit in a `u16`
+const SUPERBLOCK_BITS: usize = 1 << 16;
+const SUPERBLOCK_BLOCKS: usize = SUPERBLOCK_BITS / (BLOCK_WORDS * BITS_PER_WORD);
+
+/// A `DenseBitSet` with a two-level popcount index, for read-heavy use.
+///
+/// The index keeps the number of set bits before every superblock of 2^16
+/// bits, and before every block of 8 words relative to its superblock.
+/// That costs 16 bits per block, about 3% on top of the set. `rank` reads
+/// two counts and at most 8 words. `select` binary searches the counts and
+/// then scans at most 8 words.
+///
+/// Writes leave the counts after the written block stale. Queries stay
+/// correct, but counting through stale blocks is linear in their size.
+/// The index can be refreshed all at once with `rebuild()`, or a little at
+/// a time with `rebuild_some()` or `rebuild_until()`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, RankSelectBitSet };
+///
+/// let bs = RankSelectBitSet::new(DenseBitSet::from_indices(1 << 20, (0..1 << 20).step_by(3)));
+///
+/// assert_eq!(bs.rank(300), 100);
+/// assert_eq!(bs.select(100), Some(300));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RankSelectBitSet {
+    bits: DenseBitSet,
+    superblocks: Vec<usize>,
+    blocks: Vec<u16>,
+    // the counts before blocks 0..=clean_blocks are up to date
+    clean_blocks: usize,
+}
+
+impl RankSelectBitSet {
+    /// Builds the index over `bits`
+    pub fn new(bits: DenseBitSet) -> RankSelectBitSet {
+        let num_blocks = bits.words().div_ceil(BLOCK_WORDS);
+        let mut rs = RankSelectBitSet {
+            superblocks: vec![0; num_blocks.div_ceil(SUPERBLOCK_BLOCKS) + 1],
+            blocks: vec![0; num_blocks],
+            bits,
+            clean_blocks: 0,
+        };
+        rs.rebuild();
+        rs
+    }
+
+    /// Borrows the underlying set
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the underlying set, dropping the index
+    pub fn into_inner(self) -> DenseBitSet {
+        self.bits
+    }
+
+    /// returns the number of bits in the set
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.rank(self.len())
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        self.bits.test(i)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        self.invalidate(i);
+        self.bits.set(i)
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        self.invalidate(i);
+        self.bits.remove(i)
+    }
+
+    /// flips the value of the ith bit
+    pub fn flip(&mut self, i: usize) {
+        self.invalidate(i);
+        self.bits.flip(i)
+    }
+
+    /// returns the number of words whose counts are out of date
+    pub fn stale_words(&self) -> usize {
+        self.bits.words() - (self.clean_blocks * BLOCK_WORDS).min(self.bits.words())
+    }
+
+    /// Recomputes every stale count
+    pub fn rebuild(&mut self) {
+        let end = self.blocks.len();
+        self.build_blocks(self.clean_blocks..end);
+    }
+
+    /// Recomputes the stale counts of about `budget_words` words, in order.
+    /// Returns true once the whole index is up to date.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ DenseBitSet, RankSelectBitSet };
+    ///
+    /// let mut bs = RankSelectBitSet::new(DenseBitSet::with_capacity(1 << 16));
+    /// bs.set(0);
+    /// assert_eq!(bs.stale_words(), bs.as_bitset().words());
+    ///
+    /// while !bs.rebuild_some(64) {}
+    /// assert_eq!(bs.stale_words(), 0);
+    /// assert_eq!(bs.rank(100), 1);
+    /// ```
+    pub fn rebuild_some(&mut self, budget_words: usize) -> bool {
+        let start = self.clean_blocks.min(self.blocks.len());
+        let end = (start + budget_words.div_ceil(BLOCK_WORDS).max(1)).min(self.blocks.len());
+        self.build_blocks(start..end);
+        self.stale_words() == 0
+    }
+
+    /// Recomputes stale counts a superblock at a time until the index is up
+    /// to date or `deadline` passes. Returns true once the whole index is
+    /// up to date.
+    pub fn rebuild_until(&mut self, deadline: Instant) -> bool {
+        while !self.rebuild_some(SUPERBLOCK_BLOCKS * BLOCK_WORDS) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the number of set bits strictly below `i`, as
+    /// `DenseBitSet::rank` does. Panics if `i > len()`.
+    pub fn rank(&self, i: usize) -> usize {
+        assert!(i <= self.len());
+
+        let w = get_word_offset(i);
+        let block = (w / BLOCK_WORDS).min(self.clean_blocks);
+        let words = &self.bits.bits[block * BLOCK_WORDS..w];
+        let partial = match get_bit_offset(i) {
+            0 => 0,
+            offset => (self.bits.bits[w] & get_low_mask(offset)).count_ones() as usize,
+        };
+
+        self.block_rank(block) + words.iter().map(|word| word.count_ones() as usize).sum::<usize>() + partial
+    }
+
+    /// Returns the index of the `k`th (0-based) set bit, as
+    /// `DenseBitSet::select` does
+    pub fn select(&self, k: usize) -> Option<usize> {
+        let block = if k < self.block_rank(self.clean_blocks) {
+            // the last superblock, then the last block in it, starting at or below k
+            let superblocks = &self.superblocks[..self.clean_blocks / SUPERBLOCK_BLOCKS + 1];
+            let superblock = superblocks.partition_point(|&count| count <= k) - 1;
+            let first = superblock * SUPERBLOCK_BLOCKS;
+            let last = (first + SUPERBLOCK_BLOCKS).min(self.clean_blocks);
+            let relative = k - self.superblocks[superblock];
+            first + self.blocks[first..last].partition_point(|&count| count as usize <= relative) - 1
+        } else {
+            self.clean_blocks
+        };
+
+        let mut remaining = k - self.block_rank(block);
+        for w in block * BLOCK_WORDS..self.bits.words() {
+            let word = self.bits.bits[w];
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                return Some(w * BITS_PER_WORD + select_in_word(word, remaining));
+            }
+            remaining -= ones;
+        }
+        None
+    }
+
+    /// number of set bits before `block`; `block` may be one past the last
+    #[inline]
+    fn block_rank(&self, block: usize) -> usize {
+        if block == self.blocks.len() {
+            self.superblocks[self.superblocks.len() - 1]
+        } else {
+            self.superblocks[block / SUPERBLOCK_BLOCKS] + self.blocks[block] as usize
+        }
+    }
+
+    /// records that `running` bits are set before `block`
+    #[inline]
+    fn set_block_rank(&mut self, block: usize, running: usize) {
+        if block == self.blocks.len() {
+            let last = self.superblocks.len() - 1;
+            self.superblocks[last] = running;
+        } else {
+            if block.is_multiple_of(SUPERBLOCK_BLOCKS) {
+                self.superblocks[block / SUPERBLOCK_BLOCKS] = running;
+            }
+            self.blocks[block] = (running - self.superblocks[block / SUPERBLOCK_BLOCKS]) as u16;
+        }
+    }
+
+    /// recomputes the counts after each of `blocks`; `blocks.start` must
+    /// not be past `clean_blocks`
+    fn build_blocks(&mut self, blocks: Range<usize>) {
+        let mut running = self.block_rank(blocks.start);
+        let end = blocks.end;
+
+        profiled!(RankBuild, blocks.len() * BLOCK_WORDS, {
+            for block in blocks {
+                let last = ((block + 1) * BLOCK_WORDS).min(self.bits.words());
+                running += self.bits.bits[block * BLOCK_WORDS..last].iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum::<usize>();
+                self.set_block_rank(block + 1, running);
+            }
+        });
+        self.clean_blocks = self.clean_blocks.max(end);
+    }
+
+    /// marks the counts after the block holding bit `i` stale
+    fn invalidate(&mut self, i: usize) {
+        let block = get_word_offset(i) / BLOCK_WORDS;
+        self.clean_blocks = self.clean_blocks.min(block);
+    }
+}
+
+impl From<DenseBitSet> for RankSelectBitSet {
+    fn from(bits: DenseBitSet) -> RankSelectBitSet {
+        RankSelectBitSet::new(bits)
+    }
+}
+
+// RankSelectBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn matches_linear_rank_and_select() {
+        // spans several superblocks, with an empty one in the middle
+        let len = 5 * SUPERBLOCK_BITS + 1000;
+        let plain = DenseBitSet::from_indices(len, (0..len).filter(|&i| {
+            (i / SUPERBLOCK_BITS != 2) && (i % 17 == 0 || i % 4096 < 40)
+        }));
+        let rs = RankSelectBitSet::new(plain.clone());
+
+        assert_eq!(rs.count_ones(), plain.count_ones());
+        for i in (0..=plain.len()).step_by(101) {
+            assert_eq!(rs.rank(i), plain.rank(i));
+        }
+        for k in (0..rs.count_ones()).step_by(37) {
+            assert_eq!(rs.select(k), plain.select(k));
+        }
+        assert_eq!(rs.select(rs.count_ones() - 1), plain.last_set());
+        assert_eq!(rs.select(rs.count_ones()), None);
+    }
+
+    #[test]
+    fn can_index_empty_set() {
+        let rs = RankSelectBitSet::new(DenseBitSet::with_capacity(0));
+
+        assert_eq!(rs.rank(0), 0);
+        assert_eq!(rs.select(0), None);
+    }
+
+    #[test]
+    fn stays_correct_while_rebuilding() {
+        let len = 3 * SUPERBLOCK_BITS;
+        let mut plain = DenseBitSet::from_indices(len, (0..len).step_by(5));
+        let mut rs = RankSelectBitSet::new(plain.clone());
+
+        for &i in &[7, SUPERBLOCK_BITS + 3, 2 * SUPERBLOCK_BITS + 1] {
+            plain.set(i);
+            rs.set(i);
+        }
+        plain.remove(len - 5);
+        rs.remove(len - 5);
+
+        loop {
+            for i in (0..=len).step_by(997) {
+                assert_eq!(rs.rank(i), plain.rank(i));
+            }
+            for k in (0..plain.count_ones() + 1).step_by(211) {
+                assert_eq!(rs.select(k), plain.select(k));
+            }
+            if rs.rebuild_some(1000) {
+                break;
+            }
+        }
+        assert_eq!(rs.count_ones(), plain.count_ones());
+        assert_eq!(rs, RankSelectBitSet::new(plain));
+    }
+}
@@ -0,0 +1,173 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Inclusion relations between `DenseBitSet`s
+//!
+//! Sets are compared by the indices they contain, so sets of different
+//! lengths compare as if the shorter one were padded with zeros.
+
+use std::cmp::Ordering;
+
+use DenseBitSet;
+
+impl DenseBitSet {
+    /// Returns whether `self` has a bit `other` lacks, and whether `other`
+    /// has a bit `self` lacks, stopping as soon as both are known
+    fn differences(&self, other: &DenseBitSet) -> (bool, bool) {
+        let (mut extra, mut missing) = (false, false);
+        let words = self.bits.len().max(other.bits.len());
+
+        for w in 0..words {
+            let a = self.bits.get(w).cloned().unwrap_or(0);
+            let b = other.bits.get(w).cloned().unwrap_or(0);
+            extra |= a & !b != 0;
+            missing |= b & !a != 0;
+            if extra && missing {
+                break;
+            }
+        }
+        (extra, missing)
+    }
+
+    /// Compares two sets by inclusion, the partial order of the subset
+    /// lattice: `Less` if `self` is a proper subset of `other`, `Greater`
+    /// if it is a proper superset, `Equal` if they hold the same indices and
+    /// `None` if neither contains the other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let read = DenseBitSet::from_bits(0b001);
+    /// let read_write = DenseBitSet::from_bits(0b011);
+    /// let exec = DenseBitSet::from_bits(0b100);
+    ///
+    /// assert_eq!(read.partial_cmp_by_inclusion(&read_write), Some(Ordering::Less));
+    /// assert_eq!(read_write.partial_cmp_by_inclusion(&read), Some(Ordering::Greater));
+    /// assert_eq!(read.partial_cmp_by_inclusion(&exec), None);
+    /// ```
+    pub fn partial_cmp_by_inclusion(&self, other: &DenseBitSet) -> Option<Ordering> {
+        match self.differences(other) {
+            (false, false) => Some(Ordering::Equal),
+            (false, true) => Some(Ordering::Less),
+            (true, false) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+
+    /// Returns whether every index in `self` is in `other`, stopping at the
+    /// first word that says otherwise
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let granted = DenseBitSet::from_indices(64, vec![1, 2, 5]);
+    /// let required = DenseBitSet::from_indices(64, vec![1, 5]);
+    ///
+    /// assert!(required.is_subset(&granted));
+    /// assert!(granted.is_superset(&required));
+    /// assert!(!granted.is_subset(&required));
+    /// ```
+    pub fn is_subset(&self, other: &DenseBitSet) -> bool {
+        self.bits.iter().enumerate().all(|(w, a)| a & !other.bits.get(w).cloned().unwrap_or(0) == 0)
+    }
+
+    /// Returns whether every index in `other` is in `self`
+    pub fn is_superset(&self, other: &DenseBitSet) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns whether `self` and `other` share no index, stopping at the
+    /// first word they overlap in
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let a = DenseBitSet::from_indices(128, vec![1, 100]);
+    /// let b = DenseBitSet::from_indices(64, vec![2, 3]);
+    ///
+    /// assert!(a.is_disjoint(&b));
+    /// assert!(!a.is_disjoint(&a));
+    /// ```
+    pub fn is_disjoint(&self, other: &DenseBitSet) -> bool {
+        self.bits.iter().zip(&other.bits).all(|(a, b)| a & b == 0)
+    }
+
+    /// Returns whether every index in `self` is in `other` and `other`
+    /// holds at least one more
+    pub fn is_proper_subset(&self, other: &DenseBitSet) -> bool {
+        self.partial_cmp_by_inclusion(other) == Some(Ordering::Less)
+    }
+
+    /// Returns whether every index in `other` is in `self` and `self`
+    /// holds at least one more
+    pub fn is_proper_superset(&self, other: &DenseBitSet) -> bool {
+        self.partial_cmp_by_inclusion(other) == Some(Ordering::Greater)
+    }
+}
+
+// Relation TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_order_by_inclusion() {
+        let small = DenseBitSet::from_indices(64, vec![1, 5]);
+        let large = DenseBitSet::from_indices(256, vec![1, 5, 200]);
+        let other = DenseBitSet::from_indices(256, vec![1, 200]);
+
+        assert!(small.is_proper_subset(&large));
+        assert!(large.is_proper_superset(&small));
+        assert!(!large.is_proper_subset(&large));
+        assert_eq!(small.partial_cmp_by_inclusion(&other), None);
+        assert_eq!(other.partial_cmp_by_inclusion(&large), Some(Ordering::Less));
+
+        // lengths do not matter, only the indices held
+        let padded = DenseBitSet::from_indices(1024, vec![1, 5]);
+        assert_eq!(small.partial_cmp_by_inclusion(&padded), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn can_test_subsets_and_disjointness() {
+        let small = DenseBitSet::from_indices(64, vec![1, 5]);
+        let large = DenseBitSet::from_indices(256, vec![1, 5, 200]);
+        let other = DenseBitSet::from_indices(256, vec![2, 199]);
+
+        assert!(small.is_subset(&large) && small.is_subset(&small));
+        assert!(!large.is_subset(&small) && large.is_superset(&small));
+        assert!(small.is_disjoint(&other) && other.is_disjoint(&small));
+        assert!(!large.is_disjoint(&small));
+
+        let empty = DenseBitSet::with_capacity(0);
+        assert!(empty.is_subset(&small) && small.is_superset(&empty));
+        assert!(empty.is_disjoint(&large));
+        assert!(!large.is_subset(&empty));
+    }
+}
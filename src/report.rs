@@ -0,0 +1,250 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Storage statistics and backend recommendations for `DenseBitSet`
+
+use { DenseBitSet, BITS_PER_WORD, BYTES_PER_WORD };
+
+/// bits covered by one roaring container
+const ROARING_CHUNK_BITS: usize = 1 << 16;
+/// cardinality above which roaring switches from an array to a bitmap
+const ROARING_ARRAY_MAX: usize = 4096;
+/// key and cardinality kept per roaring container
+const ROARING_HEADER_BYTES: usize = 4;
+
+/// A storage representation a set could be kept in
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StorageBackend {
+    /// one bit per position, as in `DenseBitSet`
+    Dense,
+    /// a sorted list of set indices
+    Sparse,
+    /// a list of `(start, length)` runs of set bits
+    RunLength,
+    /// roaring containers: array, bitmap or runs per 2^16 bits
+    Roaring,
+}
+
+/// Size and density statistics for a set, with estimated sizes under
+/// alternative representations. Estimates count payload bytes only and
+/// ignore allocator and container overhead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StorageReport {
+    /// number of bits the set can accommodate
+    pub len: usize,
+    /// number of set bits
+    pub ones: usize,
+    /// number of maximal runs of set bits
+    pub runs: usize,
+    /// bytes used by the dense words
+    pub dense_bytes: usize,
+    /// estimated bytes as a sorted index list
+    pub sparse_bytes: usize,
+    /// estimated bytes as `(start, length)` run pairs
+    pub run_length_bytes: usize,
+    /// estimated bytes as a roaring bitmap
+    pub roaring_bytes: usize,
+}
+
+impl StorageReport {
+    /// returns the fraction of bits that are set, 0 for an empty set
+    pub fn density(&self) -> f64 {
+        if self.len == 0 {
+            0.0
+        } else {
+            self.ones as f64 / self.len as f64
+        }
+    }
+
+    /// returns the estimated size in bytes under `backend`
+    pub fn estimated_bytes(&self, backend: StorageBackend) -> usize {
+        match backend {
+            StorageBackend::Dense => self.dense_bytes,
+            StorageBackend::Sparse => self.sparse_bytes,
+            StorageBackend::RunLength => self.run_length_bytes,
+            StorageBackend::Roaring => self.roaring_bytes,
+        }
+    }
+
+    /// Returns the backend with the smallest estimated size. Ties go to
+    /// `Dense`, which has the cheapest random access.
+    pub fn recommend_backend(&self) -> StorageBackend {
+        let candidates = [
+            StorageBackend::Dense,
+            StorageBackend::Sparse,
+            StorageBackend::RunLength,
+            StorageBackend::Roaring,
+        ];
+
+        let mut best = StorageBackend::Dense;
+        for backend in candidates.iter().cloned() {
+            if self.estimated_bytes(backend) < self.estimated_bytes(best) {
+                best = backend;
+            }
+        }
+        best
+    }
+}
+
+/// per-container statistics accumulated for the roaring estimate
+#[derive(Default)]
+struct Chunk {
+    ones: usize,
+    runs: usize,
+}
+
+impl Chunk {
+    fn roaring_bytes(&self) -> usize {
+        if self.ones == 0 {
+            return 0;
+        }
+
+        let array = 2 * self.ones;
+        let bitmap = ROARING_CHUNK_BITS / 8;
+        let runs = 2 + 4 * self.runs;
+        let container = if self.ones <= ROARING_ARRAY_MAX { array } else { bitmap };
+
+        ROARING_HEADER_BYTES + container.min(runs)
+    }
+}
+
+impl DenseBitSet {
+    /// Measures the set and estimates its size in other representations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::{ DenseBitSet, StorageBackend };
+    ///
+    /// let mut bs = DenseBitSet::with_capacity(1 << 20);
+    /// bs.set(12);
+    /// bs.set(500_000);
+    ///
+    /// let report = bs.storage_report();
+    /// assert_eq!(report.ones, 2);
+    /// assert_eq!(report.recommend_backend(), StorageBackend::Sparse);
+    /// ```
+    pub fn storage_report(&self) -> StorageReport {
+        let index_bytes = if self.num_bits as u64 <= u64::from(u32::MAX) + 1 { 4 } else { 8 };
+
+        let mut ones = 0;
+        let mut runs = 0;
+        let mut roaring_bytes = 0;
+        let mut chunk = Chunk::default();
+        let mut chunk_idx = 0;
+        let mut carry = 0;
+
+        for (i, word) in self.bits.iter().enumerate() {
+            let word = *word;
+
+            // a run crossing into a new container starts a new run in that
+            // container, but is still one run of the set
+            let mut chunk_carry = carry;
+            if (i * BITS_PER_WORD) / ROARING_CHUNK_BITS != chunk_idx {
+                roaring_bytes += chunk.roaring_bytes();
+                chunk = Chunk::default();
+                chunk_idx = (i * BITS_PER_WORD) / ROARING_CHUNK_BITS;
+                chunk_carry = 0;
+            }
+
+            // a run starts wherever a set bit follows a clear one
+            let starts = (word & !((word << 1) | carry)).count_ones() as usize;
+            chunk.ones += word.count_ones() as usize;
+            chunk.runs += (word & !((word << 1) | chunk_carry)).count_ones() as usize;
+            ones += word.count_ones() as usize;
+            runs += starts;
+            carry = word >> (BITS_PER_WORD - 1);
+        }
+        roaring_bytes += chunk.roaring_bytes();
+
+        StorageReport {
+            len: self.num_bits,
+            ones,
+            runs,
+            dense_bytes: self.words() * BYTES_PER_WORD,
+            sparse_bytes: ones * index_bytes,
+            run_length_bytes: runs * 2 * index_bytes,
+            roaring_bytes,
+        }
+    }
+
+    /// Shorthand for `storage_report().recommend_backend()`
+    pub fn recommend_backend(&self) -> StorageBackend {
+        self.storage_report().recommend_backend()
+    }
+}
+
+// StorageReport TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_count_runs() {
+        let mut bs = DenseBitSet::with_capacity(256);
+        bs.extend(60..70);
+        bs.extend(100..101);
+        bs.extend(120..200);
+
+        let report = bs.storage_report();
+        assert_eq!(report.ones, 91);
+        assert_eq!(report.runs, 3);
+        assert_eq!(report.dense_bytes, 32);
+        assert_eq!(report.sparse_bytes, 91 * 4);
+        assert_eq!(report.run_length_bytes, 3 * 8);
+    }
+
+    #[test]
+    fn counts_runs_across_container_boundaries() {
+        let mut bs = DenseBitSet::with_capacity(1 << 17);
+        bs.set_range(65530..65540);
+
+        let report = bs.storage_report();
+        assert_eq!(report.runs, 1);
+        assert_eq!(report.runs, bs.runs().count());
+        // each container still holds a run of its own
+        assert_eq!(report.roaring_bytes, 2 * (ROARING_HEADER_BYTES + 2 + 4));
+    }
+
+    #[test]
+    fn can_report_density() {
+        let bs = DenseBitSet::with_capacity_and_state(128, 0b11);
+        assert_eq!(bs.storage_report().density(), 4.0 / 128.0);
+        assert_eq!(DenseBitSet::with_capacity(0).storage_report().density(), 0.0);
+    }
+
+    #[test]
+    fn recommends_by_shape() {
+        let mut runs = DenseBitSet::with_capacity(1 << 16);
+        runs.extend(1000..30000);
+        assert_eq!(runs.recommend_backend(), StorageBackend::RunLength);
+
+        let noisy = DenseBitSet::from_indices(1 << 16, (0..1 << 16).filter(|i| i % 3 == 0));
+        assert_eq!(noisy.recommend_backend(), StorageBackend::Dense);
+
+        // ties go to dense
+        let full = DenseBitSet::with_capacity_and_state(64, !0);
+        assert_eq!(full.storage_report().run_length_bytes, full.storage_report().dense_bytes);
+        assert_eq!(full.recommend_backend(), StorageBackend::Dense);
+    }
+}
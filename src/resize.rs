@@ -0,0 +1,199 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Length and capacity management for `DenseBitSet`
+//!
+//! Unlike `with_capacity`, which rounds up to whole words, these keep the
+//! exact length asked for, so the set can also serve as a bit vector.
+
+use { get_bit_offset, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+impl DenseBitSet {
+    /// Changes the length to exactly `num_bits` bits. New bits are set to
+    /// `value`; bits past the new length are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut bs = DenseBitSet::from_bits(0b101);
+    /// bs.resize(100, true);
+    ///
+    /// assert_eq!(bs.len(), 100);
+    /// assert_eq!(bs.count_ones(), 2 + 36);
+    ///
+    /// bs.resize(2, false);
+    /// assert_eq!(bs.to_indices(), vec![0]);
+    /// ```
+    pub fn resize(&mut self, num_bits: usize, value: bool) {
+        if num_bits <= self.num_bits {
+            self.truncate(num_bits);
+            return;
+        }
+
+        let old_bits = self.num_bits;
+        self.bits.resize(num_bits.div_ceil(BITS_PER_WORD), if value { !0 } else { 0 });
+        self.num_bits = num_bits;
+        if value && get_bit_offset(old_bits) != 0 {
+            self.bits[get_word_offset(old_bits)] |= !get_low_mask(get_bit_offset(old_bits));
+        }
+        self.clear_padding();
+    }
+
+    /// Shortens the set to `num_bits` bits, dropping the bits past it.
+    /// Does nothing if the set is already that short. Keeps the allocation.
+    pub fn truncate(&mut self, num_bits: usize) {
+        if num_bits < self.num_bits {
+            self.bits.truncate(num_bits.div_ceil(BITS_PER_WORD));
+            self.num_bits = num_bits;
+            self.clear_padding();
+        }
+    }
+
+    /// Appends one bit at position `len()`, growing the length by exactly
+    /// one, like `Vec::push`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut mask = DenseBitSet::with_capacity(0);
+    /// mask.push(true);
+    /// mask.push(false);
+    /// mask.push(true);
+    ///
+    /// assert_eq!(mask.len(), 3);
+    /// assert_eq!(mask.pop(), Some(true));
+    /// assert_eq!(mask.pop(), Some(false));
+    /// assert_eq!(mask.to_indices(), vec![0]);
+    /// ```
+    pub fn push(&mut self, value: bool) {
+        let i = self.num_bits;
+        if get_bit_offset(i) == 0 {
+            self.bits.push(0);
+        }
+        self.num_bits += 1;
+        if value {
+            self.set(i);
+        }
+    }
+
+    /// Removes the last bit and returns it, or `None` if the set is empty,
+    /// like `Vec::pop`
+    pub fn pop(&mut self) -> Option<bool> {
+        let i = self.num_bits.checked_sub(1)?;
+        let value = self.test(i);
+        self.truncate(i);
+        Some(value)
+    }
+
+    /// returns the number of bits the set can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.bits.capacity() * BITS_PER_WORD
+    }
+
+    /// Reserves room for at least `additional` more bits past `len()`
+    pub fn reserve(&mut self, additional: usize) {
+        let words = (self.num_bits + additional).div_ceil(BITS_PER_WORD);
+        self.bits.reserve(words - self.bits.len());
+    }
+
+    /// Releases allocated words past those `len()` needs
+    pub fn shrink_to_fit(&mut self) {
+        self.bits.shrink_to_fit();
+    }
+}
+
+// Resize TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_resize_with_fill() {
+        let mut bs = DenseBitSet::from_vec_masked(vec![0b1], 3);
+
+        bs.resize(130, true);
+        assert_eq!(bs.len(), 130);
+        assert_eq!(bs.words(), 3);
+        assert_eq!(bs.count_ones(), 1 + 127);
+        assert!(!bs.test(1) && !bs.test(2) && bs.test(3) && bs.test(129));
+
+        bs.resize(200, false);
+        assert_eq!(bs.count_ones(), 128);
+        assert!(!bs.test(130));
+
+        bs.resize(64, true);
+        assert_eq!(bs.len(), 64);
+        assert_eq!(bs.count_ones(), 62);
+    }
+
+    #[test]
+    fn truncate_clears_dropped_bits() {
+        let mut bs = DenseBitSet::with_capacity_and_state(128, !0);
+
+        bs.truncate(70);
+        assert_eq!((bs.len(), bs.words(), bs.count_ones()), (70, 2, 70));
+
+        // regrowing must not resurrect the dropped bits
+        bs.resize(128, false);
+        assert_eq!(bs.count_ones(), 70);
+
+        bs.truncate(0);
+        assert_eq!((bs.len(), bs.words()), (0, 0));
+    }
+
+    #[test]
+    fn push_and_pop_keep_exact_length() {
+        let pattern: Vec<bool> = (0..200).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut bs = DenseBitSet::with_capacity(0);
+
+        for &value in &pattern {
+            bs.push(value);
+        }
+        assert_eq!(bs.len(), 200);
+        assert_eq!(bs.words(), 4);
+        assert!(pattern.iter().enumerate().all(|(i, &v)| bs.test(i) == v));
+
+        for &value in pattern.iter().rev() {
+            assert_eq!(bs.pop(), Some(value));
+        }
+        assert_eq!(bs.pop(), None);
+        assert_eq!((bs.len(), bs.words()), (0, 0));
+    }
+
+    #[test]
+    fn can_reserve_and_shrink() {
+        let mut bs = DenseBitSet::with_capacity(64);
+
+        bs.reserve(1000);
+        assert!(bs.capacity() >= 1064);
+        assert_eq!(bs.len(), 64);
+
+        bs.shrink_to_fit();
+        assert!(bs.capacity() >= 64 && bs.capacity() < 1064);
+    }
+}
@@ -0,0 +1,124 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Conversions between `DenseBitSet` and the `roaring` crate, enabled by
+//! the `roaring` feature
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use roaring::{ RoaringBitmap, RoaringTreemap };
+
+use DenseBitSet;
+
+/// The error returned when a set bit cannot be represented on the other
+/// side of a conversion
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RoaringConversionError {
+    /// the first index that does not fit
+    pub index: u64,
+}
+
+impl fmt::Display for RoaringConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {} does not fit the target bit set", self.index)
+    }
+}
+
+impl Error for RoaringConversionError {}
+
+/// Fails if `bits` has a set bit at or above 2^32
+impl<'a> TryFrom<&'a DenseBitSet> for RoaringBitmap {
+    type Error = RoaringConversionError;
+
+    fn try_from(bits: &'a DenseBitSet) -> Result<RoaringBitmap, RoaringConversionError> {
+        if let Some(last) = bits.last_set().filter(|&i| u32::try_from(i).is_err()) {
+            return Err(RoaringConversionError { index: last as u64 });
+        }
+        Ok(RoaringBitmap::from_sorted_iter(bits.ones().map(|i| i as u32)).expect("ones() is sorted"))
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for RoaringTreemap {
+    fn from(bits: &'a DenseBitSet) -> RoaringTreemap {
+        RoaringTreemap::from_sorted_iter(bits.ones().map(|i| i as u64)).expect("ones() is sorted")
+    }
+}
+
+/// The result is just long enough to hold the highest index of `bitmap`
+impl<'a> From<&'a RoaringBitmap> for DenseBitSet {
+    fn from(bitmap: &'a RoaringBitmap) -> DenseBitSet {
+        let len = bitmap.max().map_or(0, |max| max as usize + 1);
+        DenseBitSet::from_indices(len, bitmap.iter().map(|i| i as usize))
+    }
+}
+
+/// The result is just long enough to hold the highest index of `treemap`.
+/// Fails if that length does not fit a `usize`.
+impl<'a> TryFrom<&'a RoaringTreemap> for DenseBitSet {
+    type Error = RoaringConversionError;
+
+    fn try_from(treemap: &'a RoaringTreemap) -> Result<DenseBitSet, RoaringConversionError> {
+        let len = match treemap.max() {
+            None => 0,
+            Some(max) => usize::try_from(max).ok()
+                .and_then(|max| max.checked_add(1))
+                .ok_or(RoaringConversionError { index: max })?,
+        };
+        Ok(DenseBitSet::from_indices(len, treemap.iter().map(|i| i as usize)))
+    }
+}
+
+// roaring TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn bitmap_round_trip() {
+        let bs = DenseBitSet::from_indices(1 << 20, vec![0, 63, 64, 70_000, (1 << 20) - 1]);
+        let bitmap = RoaringBitmap::try_from(&bs).unwrap();
+
+        assert_eq!(bitmap.iter().map(|i| i as usize).collect::<Vec<_>>(), bs.to_indices());
+        assert_eq!(DenseBitSet::from(&bitmap), bs);
+        assert_eq!(DenseBitSet::from(&RoaringBitmap::new()).len(), 0);
+    }
+
+    #[test]
+    fn treemap_round_trip() {
+        let bs = DenseBitSet::from_indices(5000, vec![1, 2, 4095, 4999]);
+        let treemap = RoaringTreemap::from(&bs);
+
+        assert_eq!(treemap.len(), 4);
+        assert_eq!(DenseBitSet::try_from(&treemap).unwrap(), bs);
+    }
+
+    #[test]
+    fn rejects_indices_that_do_not_fit() {
+        let mut treemap = RoaringTreemap::new();
+        treemap.insert(u64::MAX);
+
+        assert_eq!(DenseBitSet::try_from(&treemap), Err(RoaringConversionError { index: u64::MAX }));
+    }
+}
@@ -0,0 +1,289 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Searching `DenseBitSet` for set bits a word at a time
+
+use { get_bit_offset, get_low_mask, get_word_offset, DenseBitSet, BITS_PER_WORD };
+
+/// bit offset of the highest set bit of a non-zero word
+#[inline]
+fn highest_bit(word: usize) -> usize {
+    BITS_PER_WORD - 1 - word.leading_zeros() as usize
+}
+
+impl DenseBitSet {
+    /// Returns the index of the lowest set bit, or `None` if no bit is set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![70, 200]);
+    ///
+    /// assert_eq!(bs.first_set(), Some(70));
+    /// assert_eq!(DenseBitSet::with_capacity(256).first_set(), None);
+    /// ```
+    pub fn first_set(&self) -> Option<usize> {
+        self.bits.iter()
+            .position(|&word| word != 0)
+            .map(|w| w * BITS_PER_WORD + self.bits[w].trailing_zeros() as usize)
+    }
+
+    /// Returns the index of the highest set bit, or `None` if no bit is set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![70, 200]);
+    ///
+    /// assert_eq!(bs.last_set(), Some(200));
+    /// ```
+    pub fn last_set(&self) -> Option<usize> {
+        self.bits.iter()
+            .rposition(|&word| word != 0)
+            .map(|w| w * BITS_PER_WORD + highest_bit(self.bits[w]))
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 70, 200]);
+    ///
+    /// assert_eq!(bs.next_set_bit(3), Some(3));
+    /// assert_eq!(bs.next_set_bit(4), Some(70));
+    /// assert_eq!(bs.next_set_bit(201), None);
+    /// ```
+    pub fn next_set_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.len() {
+            return None;
+        }
+
+        let first = get_word_offset(from);
+        let masked = self.bits[first] & !get_low_mask(get_bit_offset(from));
+        if masked != 0 {
+            return Some(first * BITS_PER_WORD + masked.trailing_zeros() as usize);
+        }
+
+        self.bits[first + 1..].iter()
+            .position(|&word| word != 0)
+            .map(|w| first + 1 + w)
+            .map(|w| w * BITS_PER_WORD + self.bits[w].trailing_zeros() as usize)
+    }
+
+    /// Returns the index of the highest set bit at or before `from`, or
+    /// `None` if there is none. `from` may lie past `len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![3, 70, 200]);
+    ///
+    /// assert_eq!(bs.prev_set_bit(200), Some(200));
+    /// assert_eq!(bs.prev_set_bit(199), Some(70));
+    /// assert_eq!(bs.prev_set_bit(2), None);
+    /// ```
+    pub fn prev_set_bit(&self, from: usize) -> Option<usize> {
+        if self.len() == 0 {
+            return None;
+        }
+
+        let from = from.min(self.len() - 1);
+        let last = get_word_offset(from);
+        let masked = self.bits[last] & get_low_mask(get_bit_offset(from) + 1);
+        if masked != 0 {
+            return Some(last * BITS_PER_WORD + highest_bit(masked));
+        }
+
+        self.bits[..last].iter()
+            .rposition(|&word| word != 0)
+            .map(|w| w * BITS_PER_WORD + highest_bit(self.bits[w]))
+    }
+
+    /// Returns the index of the lowest clear bit, or `None` if every bit is set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_bits(0b0111);
+    ///
+    /// assert_eq!(bs.first_clear(), Some(3));
+    /// ```
+    pub fn first_clear(&self) -> Option<usize> {
+        self.next_clear_bit(0)
+    }
+
+    /// Returns the index of the lowest clear bit at or after `from`, or
+    /// `None` if there is none
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let mut ids = DenseBitSet::with_capacity(128);
+    /// ids.set_range(0..70);
+    /// ids.remove(10);
+    ///
+    /// assert_eq!(ids.next_clear_bit(0), Some(10));
+    /// assert_eq!(ids.next_clear_bit(11), Some(70));
+    /// ```
+    pub fn next_clear_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.len() {
+            return None;
+        }
+
+        let first = get_word_offset(from);
+        let masked = !self.bits[first] & !get_low_mask(get_bit_offset(from));
+        let found = if masked != 0 {
+            Some(first * BITS_PER_WORD + masked.trailing_zeros() as usize)
+        } else {
+            self.bits[first + 1..].iter()
+                .position(|&word| word != !0)
+                .map(|w| first + 1 + w)
+                .map(|w| w * BITS_PER_WORD + self.bits[w].trailing_ones() as usize)
+        };
+
+        // padding past len() reads as clear
+        found.filter(|&i| i < self.len())
+    }
+
+    /// Returns the start of the first run of `run_len` consecutive clear
+    /// bits, or `None` if there is no such run. Runs are found by jumping
+    /// between set and clear bits a word at a time, so long runs of either
+    /// cost one read per word.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let pages = DenseBitSet::from_indices(128, vec![2, 5, 6, 20]);
+    ///
+    /// assert_eq!(pages.find_zero_run(2), Some(0));
+    /// assert_eq!(pages.find_zero_run(3), Some(7));
+    /// assert_eq!(pages.find_zero_run(14), Some(21));
+    /// assert_eq!(pages.find_zero_run(200), None);
+    /// ```
+    pub fn find_zero_run(&self, run_len: usize) -> Option<usize> {
+        self.find_zero_run_from(0, run_len)
+    }
+
+    /// Like `find_zero_run`, only considering runs starting at or after `from`
+    pub(crate) fn find_zero_run_from(&self, from: usize, run_len: usize) -> Option<usize> {
+        if run_len == 0 {
+            return Some(from.min(self.len()));
+        }
+
+        let mut pos = from;
+        while let Some(start) = self.next_clear_bit(pos) {
+            if self.len() - start < run_len {
+                return None;
+            }
+
+            match self.next_set_bit(start) {
+                Some(end) if end - start < run_len => pos = end,
+                _ => return Some(start),
+            }
+        }
+        None
+    }
+}
+
+// Search TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_find_first_and_last_set() {
+        for &i in &[0, 1, 63, 64, 65, 127, 200, 255] {
+            let bs = DenseBitSet::from_indices(256, vec![i]);
+
+            assert_eq!(bs.first_set(), Some(i));
+            assert_eq!(bs.last_set(), Some(i));
+        }
+
+        let bs = DenseBitSet::from_indices(256, vec![5, 100, 130]);
+        assert_eq!((bs.first_set(), bs.last_set()), (Some(5), Some(130)));
+        assert_eq!(DenseBitSet::with_capacity(0).last_set(), None);
+    }
+
+    #[test]
+    fn can_step_between_set_bits() {
+        let bs = DenseBitSet::from_indices(300, vec![0, 63, 64, 130, 299]);
+
+        let mut forward = Vec::new();
+        let mut i = bs.next_set_bit(0);
+        while let Some(j) = i {
+            forward.push(j);
+            i = bs.next_set_bit(j + 1);
+        }
+        assert_eq!(forward, bs.to_indices());
+
+        let mut backward = Vec::new();
+        let mut i = bs.prev_set_bit(usize::MAX);
+        while let Some(j) = i {
+            backward.push(j);
+            i = if j == 0 { None } else { bs.prev_set_bit(j - 1) };
+        }
+        backward.reverse();
+        assert_eq!(backward, forward);
+    }
+
+    #[test]
+    fn can_find_clear_bits() {
+        let mut bs = DenseBitSet::from_vec_masked(vec![!0, !0, !0], 2 * BITS_PER_WORD + 10);
+        assert_eq!(bs.first_clear(), None);
+
+        bs.remove(BITS_PER_WORD + 1);
+        bs.remove(2 * BITS_PER_WORD + 9);
+        assert_eq!(bs.first_clear(), Some(BITS_PER_WORD + 1));
+        assert_eq!(bs.next_clear_bit(BITS_PER_WORD + 2), Some(2 * BITS_PER_WORD + 9));
+        assert_eq!(bs.next_clear_bit(2 * BITS_PER_WORD + 10), None);
+    }
+
+    #[test]
+    fn can_find_zero_runs_across_words() {
+        let mut bs = DenseBitSet::with_capacity_and_state(4 * BITS_PER_WORD, !0);
+        bs.clear_range(BITS_PER_WORD - 3..BITS_PER_WORD + 2);
+        bs.clear_range(2 * BITS_PER_WORD + 10..4 * BITS_PER_WORD);
+
+        assert_eq!(bs.find_zero_run(0), Some(0));
+        assert_eq!(bs.find_zero_run(5), Some(BITS_PER_WORD - 3));
+        assert_eq!(bs.find_zero_run(6), Some(2 * BITS_PER_WORD + 10));
+        assert_eq!(bs.find_zero_run(2 * BITS_PER_WORD - 10), Some(2 * BITS_PER_WORD + 10));
+        assert_eq!(bs.find_zero_run(2 * BITS_PER_WORD - 9), None);
+    }
+}
@@ -0,0 +1,253 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `serde` support for `DenseBitSet`, enabled by the `serde` feature
+//!
+//! Sets are written as their length in bits plus the little-endian bytes of
+//! each word, so data written on one platform loads on any other regardless
+//! of its word size. The bytes are written with `serialize_bytes`, which
+//! lets binary formats such as bincode hand them back out by reference for a
+//! zero-copy `DenseBitSetView`.
+//!
+//! Loaded sets are passed through `DenseBitSet::normalize`, so bits past
+//! the written length are cleared. `DenseBitSet::deserialize_strict`
+//! rejects such input instead.
+
+use std::fmt;
+
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+use serde::de::{ Error, SeqAccess, Visitor };
+
+use word::words_from_le_bytes;
+use { DenseBitSet, DenseBitSetView, Normalize };
+
+#[derive(Serialize)]
+#[serde(rename = "DenseBitSet")]
+struct ReprRef<'a> {
+    len: u64,
+    bytes: Bytes<'a>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "DenseBitSet")]
+struct Repr {
+    len: u64,
+    bytes: ByteBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "DenseBitSet")]
+struct ReprBorrowed<'a> {
+    len: u64,
+    bytes: &'a [u8],
+}
+
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// owned bytes, accepted either as a byte string or a sequence of `u8`
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a byte string")
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+/// validates a length and byte buffer read from the wire
+fn make_view<E: Error>(len: u64, bytes: &[u8]) -> Result<DenseBitSetView<'_>, E> {
+    let num_bits = len as usize;
+    if num_bits as u64 != len {
+        return Err(E::custom("bit length does not fit in usize"));
+    }
+
+    DenseBitSetView::new(bytes, num_bits)
+        .ok_or_else(|| E::invalid_length(bytes.len(), &"one byte per 8 bits of len"))
+}
+
+impl Serialize for DenseBitSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        profiled!(Serialize, self.words(), {
+            let bytes = self.to_le_bytes();
+
+            ReprRef { len: self.len() as u64, bytes: Bytes(&bytes) }.serialize(serializer)
+        })
+    }
+}
+
+/// decodes a set and normalizes it against the written length, which the
+/// result keeps exactly.
+fn load<'de, D: Deserializer<'de>>(deserializer: D, options: Normalize) -> Result<DenseBitSet, D::Error> {
+    let repr = Repr::deserialize(deserializer)?;
+    let view = make_view::<D::Error>(repr.len, &repr.bytes.0)?;
+
+    let mut bs = DenseBitSet { bits: words_from_le_bytes(view.as_bytes()), num_bits: view.len() };
+    bs.normalize(options).map_err(D::Error::custom)?;
+    Ok(bs)
+}
+
+impl<'de> Deserialize<'de> for DenseBitSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        profiled!(Deserialize, 0, load(deserializer, Normalize::repair()))
+    }
+}
+
+impl DenseBitSet {
+    /// Deserializes a set like `Deserialize` does, but fails on input that
+    /// is not in normal form instead of repairing it. Use it with
+    /// `#[serde(deserialize_with = "DenseBitSet::deserialize_strict")]`.
+    pub fn deserialize_strict<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DenseBitSet, D::Error> {
+        profiled!(Deserialize, 0, load(deserializer, Normalize::strict()))
+    }
+}
+
+impl<'a> Serialize for DenseBitSetView<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ReprRef { len: self.len() as u64, bytes: Bytes(self.as_bytes()) }.serialize(serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for DenseBitSetView<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ReprBorrowed::deserialize(deserializer)?;
+
+        make_view(repr.len, repr.bytes)
+    }
+}
+
+// serde TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use bincode;
+    use serde_test::{ assert_de_tokens, assert_de_tokens_error, assert_tokens, Token };
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn can_round_trip_tokens() {
+        let bs = DenseBitSet::from_bits(0b1000000001);
+
+        assert_tokens(&bs, &[
+            Token::Struct { name: "DenseBitSet", len: 2 },
+            Token::Str("len"),
+            Token::U64(64),
+            Token::Str("bytes"),
+            Token::Bytes(&[0b1, 0b10, 0, 0, 0, 0, 0, 0]),
+            Token::StructEnd,
+        ]);
+    }
+
+    #[test]
+    fn can_load_narrower_words() {
+        // a 32 bit set written on a 32 bit platform
+        let bs = DenseBitSet::from_vec_masked(vec![1 << 31], 32);
+
+        assert_de_tokens(&bs, &[
+            Token::Struct { name: "DenseBitSet", len: 2 },
+            Token::Str("len"),
+            Token::U64(32),
+            Token::Str("bytes"),
+            Token::Seq { len: Some(4) },
+            Token::U8(0), Token::U8(0), Token::U8(0), Token::U8(0b1000_0000),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ]);
+    }
+
+    #[test]
+    fn rejects_mismatched_length() {
+        assert_de_tokens_error::<DenseBitSet>(&[
+            Token::Struct { name: "DenseBitSet", len: 2 },
+            Token::Str("len"),
+            Token::U64(64),
+            Token::Str("bytes"),
+            Token::Bytes(&[0]),
+            Token::StructEnd,
+        ], "invalid length 1, expected one byte per 8 bits of len");
+    }
+
+    #[test]
+    fn clears_or_rejects_bits_past_len() {
+        #[derive(Deserialize)]
+        struct Strict(#[serde(deserialize_with = "DenseBitSet::deserialize_strict")] #[allow(dead_code)] DenseBitSet);
+
+        let set = [
+            Token::Struct { name: "DenseBitSet", len: 2 },
+            Token::Str("len"),
+            Token::U64(4),
+            Token::Str("bytes"),
+            Token::Bytes(&[0b1001_0001]),
+            Token::StructEnd,
+        ];
+        assert_de_tokens(&DenseBitSet::from_vec_masked(vec![0b0001], 4), &set);
+
+        let mut strict = vec![Token::NewtypeStruct { name: "Strict" }];
+        strict.extend_from_slice(&set);
+        assert_de_tokens_error::<Strict>(&strict, "bits past the set length are set");
+    }
+
+    #[test]
+    fn can_view_bincode_without_copying() {
+        let bs = DenseBitSet::from_indices(4096, vec![0, 100, 4095]);
+        let encoded = bincode::serialize(&bs).unwrap();
+
+        let view: DenseBitSetView = bincode::deserialize(&encoded).unwrap();
+        let range = encoded.as_ptr_range();
+        assert!(range.contains(&view.as_bytes().as_ptr()));
+        assert_eq!(view.ones().collect::<Vec<_>>(), vec![0, 100, 4095]);
+
+        let decoded: DenseBitSet = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, bs);
+    }
+}
@@ -0,0 +1,226 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Transactional edits over a `DenseBitSet` using a word overlay
+
+use std::collections::BTreeMap;
+
+use { get_bitmask, get_word_offset, DenseBitSet };
+
+/// A `DenseBitSet` whose edits can be staged and then committed or rolled
+/// back as a unit.
+///
+/// Between `begin_edit()` and `commit()`/`rollback()`, writes land in an
+/// overlay holding only the words that were touched, so starting an edit
+/// does not copy the set and rolling back is just dropping the overlay.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, ShadowedBitSet };
+///
+/// let mut mask = ShadowedBitSet::new(DenseBitSet::with_capacity(128));
+///
+/// mask.begin_edit();
+/// mask.set(3);
+/// assert!(mask.test(3));
+/// mask.rollback();
+/// assert!(!mask.test(3));
+///
+/// mask.begin_edit();
+/// mask.set(3);
+/// mask.commit();
+/// assert!(mask.committed().test(3));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShadowedBitSet {
+    base: DenseBitSet,
+    overlay: Option<BTreeMap<usize, usize>>,
+}
+
+impl ShadowedBitSet {
+    /// Wraps `base` as the committed state
+    pub fn new(base: DenseBitSet) -> ShadowedBitSet {
+        ShadowedBitSet {
+            base,
+            overlay: None,
+        }
+    }
+
+    /// Starts staging edits. Panics if an edit is already in progress.
+    pub fn begin_edit(&mut self) {
+        assert!(self.overlay.is_none(), "edit already in progress");
+        self.overlay = Some(BTreeMap::new());
+    }
+
+    /// returns true between `begin_edit()` and `commit()`/`rollback()`
+    pub fn in_edit(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// returns the number of words modified by the current edit
+    pub fn pending_words(&self) -> usize {
+        self.overlay.as_ref().map_or(0, |overlay| overlay.len())
+    }
+
+    /// Writes all staged words into the committed set in one pass.
+    /// Does nothing if no edit is in progress.
+    pub fn commit(&mut self) {
+        if let Some(overlay) = self.overlay.take() {
+            for (idx, word) in overlay {
+                self.base.bits[idx] = word;
+            }
+        }
+    }
+
+    /// Discards all staged edits
+    pub fn rollback(&mut self) {
+        self.overlay = None;
+    }
+
+    /// returns the committed state, ignoring any staged edits
+    pub fn committed(&self) -> &DenseBitSet {
+        &self.base
+    }
+
+    /// Consumes the wrapper, returning the committed state
+    pub fn into_inner(self) -> DenseBitSet {
+        self.base
+    }
+
+    /// Tests whether the ith bit is set, including staged edits
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.base.len(), "index {} is out of bounds for a set of {} bits", i, self.base.len());
+        (self.word(get_word_offset(i)) & get_bitmask(i)) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.base.len(), "index {} is out of bounds for a set of {} bits", i, self.base.len());
+        let bitmask = get_bitmask(i);
+        let word = self.word_mut(get_word_offset(i));
+        let prior = *word;
+
+        *word |= bitmask;
+        (prior & bitmask) == 0
+    }
+
+    /// flips the value of the ith bit
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.base.len(), "index {} is out of bounds for a set of {} bits", i, self.base.len());
+        *self.word_mut(get_word_offset(i)) ^= get_bitmask(i);
+    }
+
+    /// returns the current value of a word, staged or committed
+    fn word(&self, idx: usize) -> usize {
+        match self.overlay.as_ref().and_then(|overlay| overlay.get(&idx)) {
+            Some(word) => *word,
+            None => self.base.bits[idx],
+        }
+    }
+
+    /// returns the word to write to, staging a copy if editing
+    fn word_mut(&mut self, idx: usize) -> &mut usize {
+        let base = &mut self.base.bits;
+        match self.overlay {
+            Some(ref mut overlay) => {
+                let committed = base[idx];
+                overlay.entry(idx).or_insert(committed)
+            }
+            None => &mut base[idx],
+        }
+    }
+}
+
+impl From<DenseBitSet> for ShadowedBitSet {
+    fn from(base: DenseBitSet) -> ShadowedBitSet {
+        ShadowedBitSet::new(base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rollback_discards_edits() {
+        let mut bs = ShadowedBitSet::new(DenseBitSet::from_bits(0b1010));
+
+        bs.begin_edit();
+        bs.flip(1);
+        bs.set(0);
+        assert!(!bs.test(1));
+        assert!(bs.test(0));
+        assert_eq!(bs.pending_words(), 1);
+
+        bs.rollback();
+        assert!(!bs.in_edit());
+        assert_eq!(bs.committed(), &DenseBitSet::from_bits(0b1010));
+    }
+
+    #[test]
+    fn commit_applies_edits() {
+        let mut bs = ShadowedBitSet::new(DenseBitSet::with_capacity(256));
+
+        bs.begin_edit();
+        bs.set(5);
+        bs.set(200);
+        assert!(!bs.committed().test(200));
+        assert_eq!(bs.pending_words(), 2);
+
+        bs.commit();
+        assert!(bs.committed().test(5));
+        assert!(bs.committed().test(200));
+        assert_eq!(bs.pending_words(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 20 is out of bounds for a set of 10 bits")]
+    fn rejects_staged_writes_past_len() {
+        let mut bs = ShadowedBitSet::new(DenseBitSet::from_vec_masked(vec![0], 10));
+
+        bs.begin_edit();
+        bs.set(20);
+    }
+
+    #[test]
+    fn commit_keeps_padding_clear() {
+        let mut bs = ShadowedBitSet::new(DenseBitSet::from_vec_masked(vec![0], 10));
+
+        bs.begin_edit();
+        bs.set(9);
+        bs.flip(0);
+        bs.commit();
+        assert_eq!(bs.committed().count_ones(), 2);
+        assert_eq!(bs.committed().to_indices(), vec![0, 9]);
+    }
+
+    #[test]
+    fn writes_outside_edit_are_direct() {
+        let mut bs = ShadowedBitSet::new(DenseBitSet::with_capacity(64));
+        bs.set(7);
+
+        assert!(bs.committed().test(7));
+    }
+}
@@ -0,0 +1,194 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `SharedBitSet`, a copy-on-write `DenseBitSet` behind an `Arc`
+
+use std::sync::Arc;
+
+use DenseBitSet;
+
+/// A `DenseBitSet` whose clones share storage until one of them is written.
+///
+/// Cloning is a reference count bump, so snapshots are cheap to hand out.
+/// A write through a handle whose storage is shared copies it first. Two
+/// handles for which `ptr_eq` returns true are known to hold the same bits
+/// without comparing them.
+///
+/// Each handle also carries a version that counts the writes that changed
+/// a bit; clones start from the version of their source. A cache keyed on
+/// a handle can compare versions to tell whether it went stale.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, SharedBitSet };
+///
+/// let mut live = SharedBitSet::new(DenseBitSet::with_capacity(1 << 20));
+/// let snapshot = live.clone();
+/// assert!(live.ptr_eq(&snapshot));
+///
+/// live.set(42);
+/// assert!(!live.ptr_eq(&snapshot));
+/// assert_eq!((snapshot.version(), live.version()), (0, 1));
+/// assert!(!snapshot.test(42));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedBitSet {
+    bits: Arc<DenseBitSet>,
+    version: u64,
+}
+
+impl SharedBitSet {
+    /// Wraps `bits` in fresh shared storage, at version 0
+    pub fn new(bits: DenseBitSet) -> SharedBitSet {
+        SharedBitSet { bits: Arc::new(bits), version: 0 }
+    }
+
+    /// Borrows the current bits
+    pub fn as_bitset(&self) -> &DenseBitSet {
+        &self.bits
+    }
+
+    /// Returns the bits, copying them only if the storage is still shared
+    pub fn into_inner(self) -> DenseBitSet {
+        Arc::try_unwrap(self.bits).unwrap_or_else(|shared| (*shared).clone())
+    }
+
+    /// Returns whether `self` and `other` share storage, in which case they
+    /// hold the same bits
+    pub fn ptr_eq(&self, other: &SharedBitSet) -> bool {
+        Arc::ptr_eq(&self.bits, &other.bits)
+    }
+
+    /// Returns whether another handle shares this storage
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.bits) > 1
+    }
+
+    /// Returns the number of writes through this handle, and the handle it
+    /// was cloned from, that changed a bit
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the number of bits
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        self.bits.test(i)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously. Setting a bit that is
+    /// already set neither copies the storage nor bumps the version.
+    pub fn set(&mut self, i: usize) -> bool {
+        if self.bits.test(i) {
+            return false;
+        }
+        self.update(|bits| { bits.set(i); });
+        true
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously. Clearing a bit that is
+    /// already clear neither copies the storage nor bumps the version.
+    pub fn remove(&mut self, i: usize) -> bool {
+        if !self.bits.test(i) {
+            return false;
+        }
+        self.update(|bits| { bits.remove(i); });
+        true
+    }
+
+    /// Flips the ith bit
+    pub fn flip(&mut self, i: usize) {
+        self.update(|bits| bits.flip(i));
+    }
+
+    /// Runs `f` on the bits, copying them first if the storage is shared,
+    /// and bumps the version
+    pub fn update<F: FnOnce(&mut DenseBitSet)>(&mut self, f: F) {
+        f(Arc::make_mut(&mut self.bits));
+        self.version += 1;
+    }
+}
+
+/// Compares contents, skipping the comparison when storage is shared
+impl PartialEq for SharedBitSet {
+    fn eq(&self, other: &SharedBitSet) -> bool {
+        self.ptr_eq(other) || self.bits == other.bits
+    }
+}
+
+impl Eq for SharedBitSet {}
+
+impl From<DenseBitSet> for SharedBitSet {
+    fn from(bits: DenseBitSet) -> SharedBitSet {
+        SharedBitSet::new(bits)
+    }
+}
+
+// SharedBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn writes_copy_shared_storage_only() {
+        let mut a = SharedBitSet::new(DenseBitSet::with_capacity(256));
+        a.set(1);
+        assert!(!a.is_shared());
+
+        let b = a.clone();
+        assert!(a.is_shared() && a.ptr_eq(&b) && a == b);
+
+        a.set(1);
+        a.remove(2);
+        assert!(a.ptr_eq(&b), "no-op writes must not copy");
+        assert_eq!(a.version(), 1);
+
+        a.flip(2);
+        assert!(!a.ptr_eq(&b) && !b.is_shared());
+        assert_eq!((a.version(), b.version()), (2, 1));
+        assert_eq!(b.into_inner().to_indices(), vec![1]);
+    }
+
+    #[test]
+    fn equal_contents_compare_equal_without_sharing() {
+        let a = SharedBitSet::new(DenseBitSet::from_indices(64, vec![3]));
+        let mut b = SharedBitSet::new(DenseBitSet::with_capacity(64));
+        b.update(|bits| { bits.set(3); });
+
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a, b);
+    }
+}
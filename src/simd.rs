@@ -0,0 +1,446 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Word-array kernels for the bulk boolean operations and popcounts
+//!
+//! On x86-64 the kernels use AVX2 when the CPU supports it, detected at
+//! run time, handling four words per instruction. Elsewhere they fall back
+//! to a loop that handles four words per iteration, which compilers turn
+//! into the target's baseline vector instructions such as SSE2 or NEON.
+//! Popcounts of large inputs use the Harley–Seal carry-save adder tree,
+//! which needs a full popcount for only one word or register in sixteen.
+
+/// words handled per iteration of the portable loops
+const LANES: usize = 4;
+
+/// fewest words for which the Harley–Seal popcount beats a plain loop
+const HARLEY_SEAL_MIN_WORDS: usize = 256;
+
+macro_rules! binary_kernel {
+    ($name:ident, |$a:ident, $b:ident| $word:expr) => {
+        /// Applies the operation word by word, writing into `dst`; extra
+        /// words of the longer slice are left alone
+        pub(crate) fn $name(dst: &mut [usize], src: &[usize]) {
+            let len = dst.len().min(src.len());
+            let (dst, src) = (&mut dst[..len], &src[..len]);
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    // the CPU supports AVX2, checked above
+                    return unsafe { avx2::$name(dst, src) };
+                }
+            }
+
+            let op = |$a: usize, $b: usize| $word;
+            let mut dst_chunks = dst.chunks_exact_mut(LANES);
+            let mut src_chunks = src.chunks_exact(LANES);
+            for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+                d[0] = op(d[0], s[0]);
+                d[1] = op(d[1], s[1]);
+                d[2] = op(d[2], s[2]);
+                d[3] = op(d[3], s[3]);
+            }
+            for (d, s) in dst_chunks.into_remainder().iter_mut().zip(src_chunks.remainder()) {
+                *d = op(*d, *s);
+            }
+        }
+    };
+}
+
+binary_kernel!(and, |a, b| a & b);
+binary_kernel!(and_not, |a, b| a & !b);
+binary_kernel!(or, |a, b| a | b);
+binary_kernel!(xor, |a, b| a ^ b);
+
+macro_rules! ternary_kernel {
+    ($name:ident, |$a:ident, $b:ident, $c:ident| $word:expr) => {
+        /// Applies the operation word by word, writing into `dst`, over the
+        /// shortest of the three lengths. Left to the compiler to
+        /// vectorize, since the fusion is the point rather than the width.
+        pub(crate) fn $name(dst: &mut [usize], b: &[usize], c: &[usize]) {
+            let len = dst.len().min(b.len()).min(c.len());
+            let op = |$a: usize, $b: usize, $c: usize| $word;
+            for ((d, &y), &z) in dst[..len].iter_mut().zip(&b[..len]).zip(&c[..len]) {
+                *d = op(*d, y, z);
+            }
+        }
+    };
+}
+
+ternary_kernel!(and_or, |a, b, c| a & (b | c));
+ternary_kernel!(or_and, |a, b, c| a | (b & c));
+ternary_kernel!(or_and_not, |a, b, c| a | (b & !c));
+ternary_kernel!(xor_and, |a, b, c| a ^ (b & c));
+
+/// Flips every bit of `words`
+pub(crate) fn not(words: &mut [usize]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // the CPU supports AVX2, checked above
+            return unsafe { avx2::not(words) };
+        }
+    }
+
+    let mut chunks = words.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        chunk[0] = !chunk[0];
+        chunk[1] = !chunk[1];
+        chunk[2] = !chunk[2];
+        chunk[3] = !chunk[3];
+    }
+    for word in chunks.into_remainder() {
+        *word = !*word;
+    }
+}
+
+/// Returns whether the two word slices are equal
+pub(crate) fn eq(a: &[usize], b: &[usize]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // the CPU supports AVX2, checked above
+            return unsafe { avx2::eq(a, b) };
+        }
+    }
+
+    let mut a_chunks = a.chunks_exact(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in (&mut a_chunks).zip(&mut b_chunks) {
+        if (x[0] ^ y[0]) | (x[1] ^ y[1]) | (x[2] ^ y[2]) | (x[3] ^ y[3]) != 0 {
+            return false;
+        }
+    }
+    a_chunks.remainder() == b_chunks.remainder()
+}
+
+/// Returns the number of set bits in `words`
+pub(crate) fn popcount(words: &[usize]) -> usize {
+    if words.len() < HARLEY_SEAL_MIN_WORDS {
+        return words.iter().map(|word| word.count_ones() as usize).sum();
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // the CPU supports AVX2, checked above
+            return unsafe { avx2::popcount(words) };
+        }
+    }
+    harley_seal(words.len(), |i| words[i])
+}
+
+macro_rules! popcount_kernel {
+    ($name:ident, |$a:ident, $b:ident| $word:expr) => {
+        /// Returns the popcount of the operation applied word by word, over
+        /// the shorter length, without materializing the result
+        pub(crate) fn $name(a: &[usize], b: &[usize]) -> usize {
+            let len = a.len().min(b.len());
+            let (a, b) = (&a[..len], &b[..len]);
+            let op = |$a: usize, $b: usize| $word;
+            if len < HARLEY_SEAL_MIN_WORDS {
+                return a.iter().zip(b).map(|(&x, &y)| op(x, y).count_ones() as usize).sum();
+            }
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    // the CPU supports AVX2, checked above
+                    return unsafe { avx2::$name(a, b) };
+                }
+            }
+            harley_seal(len, |i| op(a[i], b[i]))
+        }
+    };
+}
+
+popcount_kernel!(popcount_and, |a, b| a & b);
+popcount_kernel!(popcount_and_not, |a, b| a & !b);
+popcount_kernel!(popcount_or, |a, b| a | b);
+
+/// a carry-save adder: returns the carry and sum bits of `a + b + c`
+#[inline]
+fn csa(a: usize, b: usize, c: usize) -> (usize, usize) {
+    let u = a ^ b;
+    ((a & b) | (u & c), u ^ c)
+}
+
+/// Counts the set bits of `word(0..len)` with the Harley–Seal method:
+/// blocks of 16 words are summed bitwise by a tree of carry-save adders,
+/// so only one word in 16 needs a full popcount.
+fn harley_seal<F: Fn(usize) -> usize>(len: usize, word: F) -> usize {
+    let (mut ones, mut twos, mut fours, mut eights) = (0, 0, 0, 0);
+    let mut sixteens = 0;
+
+    let blocks = len / 16;
+    for block in 0..blocks {
+        let i = block * 16;
+        let (twos_a, o) = csa(ones, word(i), word(i + 1));
+        let (twos_b, o) = csa(o, word(i + 2), word(i + 3));
+        let (fours_a, t) = csa(twos, twos_a, twos_b);
+        let (twos_a, o) = csa(o, word(i + 4), word(i + 5));
+        let (twos_b, o) = csa(o, word(i + 6), word(i + 7));
+        let (fours_b, t) = csa(t, twos_a, twos_b);
+        let (eights_a, f) = csa(fours, fours_a, fours_b);
+        let (twos_a, o) = csa(o, word(i + 8), word(i + 9));
+        let (twos_b, o) = csa(o, word(i + 10), word(i + 11));
+        let (fours_a, t) = csa(t, twos_a, twos_b);
+        let (twos_a, o) = csa(o, word(i + 12), word(i + 13));
+        let (twos_b, o) = csa(o, word(i + 14), word(i + 15));
+        let (fours_b, t) = csa(t, twos_a, twos_b);
+        let (eights_b, f) = csa(f, fours_a, fours_b);
+        let (carry, e) = csa(eights, eights_a, eights_b);
+        sixteens += carry.count_ones() as usize;
+        ones = o;
+        twos = t;
+        fours = f;
+        eights = e;
+    }
+
+    let tail: usize = (blocks * 16..len).map(|i| word(i).count_ones() as usize).sum();
+    16 * sixteens + 8 * eights.count_ones() as usize + 4 * fours.count_ones() as usize
+        + 2 * twos.count_ones() as usize + ones.count_ones() as usize + tail
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    /// `usize` words per 256-bit register
+    const LANES: usize = 4;
+
+    macro_rules! avx2_binary {
+        ($name:ident, |$a:ident, $b:ident| $vector:expr, $word:expr) => {
+            #[target_feature(enable = "avx2")]
+            pub(super) unsafe fn $name(dst: &mut [usize], src: &[usize]) {
+                let vectors = dst.len() / LANES;
+                let (d, s) = (dst.as_mut_ptr() as *mut __m256i, src.as_ptr() as *const __m256i);
+                for i in 0..vectors {
+                    let $a = _mm256_loadu_si256(d.add(i));
+                    let $b = _mm256_loadu_si256(s.add(i));
+                    _mm256_storeu_si256(d.add(i), $vector);
+                }
+                for i in vectors * LANES..dst.len() {
+                    let ($a, $b) = (dst[i], src[i]);
+                    dst[i] = $word;
+                }
+            }
+        };
+    }
+
+    avx2_binary!(and, |a, b| _mm256_and_si256(a, b), a & b);
+    avx2_binary!(and_not, |a, b| _mm256_andnot_si256(b, a), a & !b);
+    avx2_binary!(or, |a, b| _mm256_or_si256(a, b), a | b);
+    avx2_binary!(xor, |a, b| _mm256_xor_si256(a, b), a ^ b);
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn not(words: &mut [usize]) {
+        let vectors = words.len() / LANES;
+        let p = words.as_mut_ptr() as *mut __m256i;
+        let ones = _mm256_set1_epi64x(-1);
+        for i in 0..vectors {
+            _mm256_storeu_si256(p.add(i), _mm256_xor_si256(_mm256_loadu_si256(p.add(i)), ones));
+        }
+        for word in &mut words[vectors * LANES..] {
+            *word = !*word;
+        }
+    }
+
+    /// returns the popcount of each 64-bit lane, by nibble table lookups
+    #[target_feature(enable = "avx2")]
+    unsafe fn count(v: __m256i) -> __m256i {
+        let table = _mm256_setr_epi8(
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+        );
+        let low = _mm256_set1_epi8(0x0f);
+        let lo = _mm256_and_si256(v, low);
+        let hi = _mm256_and_si256(_mm256_srli_epi16(v, 4), low);
+        let bytes = _mm256_add_epi8(_mm256_shuffle_epi8(table, lo), _mm256_shuffle_epi8(table, hi));
+        _mm256_sad_epu8(bytes, _mm256_setzero_si256())
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn csa(a: __m256i, b: __m256i, c: __m256i) -> (__m256i, __m256i) {
+        let u = _mm256_xor_si256(a, b);
+        (_mm256_or_si256(_mm256_and_si256(a, b), _mm256_and_si256(u, c)), _mm256_xor_si256(u, c))
+    }
+
+    /// the Harley–Seal popcount of `load(0..vectors)`, one register per step
+    #[target_feature(enable = "avx2")]
+    unsafe fn harley_seal<L: Fn(usize) -> __m256i>(vectors: usize, load: L) -> usize {
+        let zero = _mm256_setzero_si256();
+        let (mut ones, mut twos, mut fours, mut eights) = (zero, zero, zero, zero);
+        let mut total = zero;
+
+        let blocks = vectors / 16;
+        for block in 0..blocks {
+            let i = block * 16;
+            let (twos_a, o) = csa(ones, load(i), load(i + 1));
+            let (twos_b, o) = csa(o, load(i + 2), load(i + 3));
+            let (fours_a, t) = csa(twos, twos_a, twos_b);
+            let (twos_a, o) = csa(o, load(i + 4), load(i + 5));
+            let (twos_b, o) = csa(o, load(i + 6), load(i + 7));
+            let (fours_b, t) = csa(t, twos_a, twos_b);
+            let (eights_a, f) = csa(fours, fours_a, fours_b);
+            let (twos_a, o) = csa(o, load(i + 8), load(i + 9));
+            let (twos_b, o) = csa(o, load(i + 10), load(i + 11));
+            let (fours_a, t) = csa(t, twos_a, twos_b);
+            let (twos_a, o) = csa(o, load(i + 12), load(i + 13));
+            let (twos_b, o) = csa(o, load(i + 14), load(i + 15));
+            let (fours_b, t) = csa(t, twos_a, twos_b);
+            let (eights_b, f) = csa(f, fours_a, fours_b);
+            let (sixteens, e) = csa(eights, eights_a, eights_b);
+            total = _mm256_add_epi64(total, count(sixteens));
+            ones = o;
+            twos = t;
+            fours = f;
+            eights = e;
+        }
+
+        total = _mm256_slli_epi64(total, 4);
+        total = _mm256_add_epi64(total, _mm256_slli_epi64(count(eights), 3));
+        total = _mm256_add_epi64(total, _mm256_slli_epi64(count(fours), 2));
+        total = _mm256_add_epi64(total, _mm256_slli_epi64(count(twos), 1));
+        total = _mm256_add_epi64(total, count(ones));
+        for i in blocks * 16..vectors {
+            total = _mm256_add_epi64(total, count(load(i)));
+        }
+
+        let mut lanes = [0u64; LANES];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, total);
+        lanes.iter().sum::<u64>() as usize
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn popcount(words: &[usize]) -> usize {
+        let vectors = words.len() / LANES;
+        let p = words.as_ptr() as *const __m256i;
+        let tail: usize = words[vectors * LANES..].iter().map(|word| word.count_ones() as usize).sum();
+        harley_seal(vectors, |i| _mm256_loadu_si256(p.add(i))) + tail
+    }
+
+    macro_rules! avx2_popcount {
+        ($name:ident, |$a:ident, $b:ident| $vector:expr, $word:expr) => {
+            #[target_feature(enable = "avx2")]
+            pub(super) unsafe fn $name(a: &[usize], b: &[usize]) -> usize {
+                let vectors = a.len() / LANES;
+                let (pa, pb) = (a.as_ptr() as *const __m256i, b.as_ptr() as *const __m256i);
+                let tail: usize = a[vectors * LANES..].iter().zip(&b[vectors * LANES..])
+                    .map(|(&$a, &$b)| ($word).count_ones() as usize)
+                    .sum();
+                let load = |i: usize| {
+                    let ($a, $b) = (_mm256_loadu_si256(pa.add(i)), _mm256_loadu_si256(pb.add(i)));
+                    $vector
+                };
+                harley_seal(vectors, load) + tail
+            }
+        };
+    }
+
+    avx2_popcount!(popcount_and, |a, b| _mm256_and_si256(a, b), a & b);
+    avx2_popcount!(popcount_and_not, |a, b| _mm256_andnot_si256(b, a), a & !b);
+    avx2_popcount!(popcount_or, |a, b| _mm256_or_si256(a, b), a | b);
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn eq(a: &[usize], b: &[usize]) -> bool {
+        let vectors = a.len() / LANES;
+        let (pa, pb) = (a.as_ptr() as *const __m256i, b.as_ptr() as *const __m256i);
+        for i in 0..vectors {
+            let diff = _mm256_xor_si256(_mm256_loadu_si256(pa.add(i)), _mm256_loadu_si256(pb.add(i)));
+            if _mm256_testz_si256(diff, diff) == 0 {
+                return false;
+            }
+        }
+        a[vectors * LANES..] == b[vectors * LANES..]
+    }
+}
+
+// Kernel TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn words(len: usize, seed: usize) -> Vec<usize> {
+        (0..len).map(|i| (i + 1).wrapping_mul(seed).rotate_left(i as u32 % 61)).collect()
+    }
+
+    #[test]
+    fn kernels_match_scalar_at_every_length() {
+        for len in 0..=11 {
+            let (a, b) = (words(len, 0x9e37_79b9), words(len, 0x85eb_ca6b));
+            let zipped = |f: fn(usize, usize) -> usize| -> Vec<usize> {
+                a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect()
+            };
+
+            let mut out = a.clone();
+            and(&mut out, &b);
+            assert_eq!(out, zipped(|x, y| x & y));
+            let mut out = a.clone();
+            or(&mut out, &b);
+            assert_eq!(out, zipped(|x, y| x | y));
+            let mut out = a.clone();
+            xor(&mut out, &b);
+            assert_eq!(out, zipped(|x, y| x ^ y));
+            let mut out = a.clone();
+            and_not(&mut out, &b);
+            assert_eq!(out, zipped(|x, y| x & !y));
+            let mut out = a.clone();
+            not(&mut out);
+            assert_eq!(out, a.iter().map(|x| !x).collect::<Vec<_>>());
+
+            assert!(eq(&a, &a.clone()));
+            for i in 0..len {
+                let mut c = a.clone();
+                c[i] ^= 1 << (i % 7);
+                assert!(!eq(&a, &c));
+            }
+        }
+        assert!(!eq(&[0], &[0, 0]));
+    }
+
+    #[test]
+    fn popcounts_match_naive() {
+        for &len in &[0, 1, 15, 16, 17, 255, 256, 257, 300, 1000, 1029] {
+            let (a, b) = (words(len, 0x9e37_79b9), words(len, 0x85eb_ca6b));
+            let naive: usize = a.iter().map(|x| x.count_ones() as usize).sum();
+            let naive_and: usize = a.iter().zip(&b).map(|(x, y)| (x & y).count_ones() as usize).sum();
+
+            assert_eq!(popcount(&a), naive);
+            assert_eq!(harley_seal(len, |i| a[i]), naive);
+            assert_eq!(popcount_and(&a, &b), naive_and);
+            assert_eq!(harley_seal(len, |i| a[i] & b[i]), naive_and);
+
+            let naive_or: usize = a.iter().zip(&b).map(|(x, y)| (x | y).count_ones() as usize).sum();
+            let naive_and_not: usize = a.iter().zip(&b).map(|(x, y)| (x & !y).count_ones() as usize).sum();
+            assert_eq!(popcount_or(&a, &b), naive_or);
+            assert_eq!(popcount_and_not(&a, &b), naive_and_not);
+        }
+        assert_eq!(popcount(&[!0; 1000]), 1000 * ::BITS_PER_WORD);
+    }
+}
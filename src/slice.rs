@@ -0,0 +1,478 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Borrowed views over a range of bits
+//!
+//! `BitSlice` and `BitSliceMut` view any `start..end` of a set, unaligned at
+//! either end, and read or write through shifted words without copying the
+//! set. Both implement `BitWords`, so a view can be an operand of the
+//! word-wise operations: its words are lent out when the view starts on a
+//! word boundary, and shifted into a copy otherwise. `BitSliceMut` also
+//! implements `BitSet`.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::RangeBounds;
+
+use range::to_range;
+use { get_bit_offset, get_low_mask, get_word_offset, BitSet, BitWords, DenseBitSet, DenseOnesIterator, BITS_PER_WORD };
+
+/// Reads `len <= BITS_PER_WORD` bits of `words` starting at any bit offset,
+/// returned in the low bits of a word
+pub(crate) fn read_bits(words: &[usize], start: usize, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    let idx = get_word_offset(start);
+    let offset = get_bit_offset(start);
+    let mut value = words[idx] >> offset;
+    if offset + len > BITS_PER_WORD {
+        value |= words[idx + 1] << (BITS_PER_WORD - offset);
+    }
+    value & get_low_mask(len)
+}
+
+/// Writes the low `len <= BITS_PER_WORD` bits of `value` into `words` at
+/// any bit offset, leaving the surrounding bits untouched
+pub(crate) fn write_bits(words: &mut [usize], start: usize, len: usize, value: usize) {
+    if len == 0 {
+        return;
+    }
+
+    let idx = get_word_offset(start);
+    let offset = get_bit_offset(start);
+    let value = value & get_low_mask(len);
+    let mask = get_low_mask(len) << offset;
+
+    words[idx] = (words[idx] & !mask) | (value << offset);
+    if offset + len > BITS_PER_WORD {
+        let spill = get_low_mask(offset + len - BITS_PER_WORD);
+        words[idx + 1] = (words[idx + 1] & !spill) | (value >> (BITS_PER_WORD - offset));
+    }
+}
+
+/// A read-only view of bits `start..start + len()` of a set, renumbered
+/// from 0, as returned by `DenseBitSet::range`, `DenseBitSet::as_bit_slice`
+/// or `BitMatrix::row`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let bs = DenseBitSet::from_indices(256, vec![3, 64, 65, 200]);
+/// let view = bs.range(60..70);
+///
+/// assert_eq!(view.len(), 10);
+/// assert!(view.test(4));
+/// assert_eq!(view.count_ones(), 2);
+/// assert_eq!(view.ones().collect::<Vec<_>>(), vec![4, 5]);
+///
+/// // any view of the same length works as an operand
+/// let mask = DenseBitSet::from_indices(10, vec![4, 9]);
+/// assert_eq!(mask.and(&view).to_indices(), vec![4]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct BitSlice<'a> {
+    bits: &'a [usize],
+    /// position of the view's bit 0 in `bits`
+    start: usize,
+    num_bits: usize,
+}
+
+impl<'a> BitSlice<'a> {
+    /// views the first `num_bits` bits of `bits`
+    pub(crate) fn new(bits: &'a [usize], num_bits: usize) -> BitSlice<'a> {
+        debug_assert!(num_bits.div_ceil(BITS_PER_WORD) <= bits.len());
+        BitSlice { bits, start: 0, num_bits }
+    }
+
+    /// returns the number of bits in the view
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// returns whether the view holds no bits
+    pub fn is_empty(&self) -> bool {
+        self.num_bits == 0
+    }
+
+    /// Tests whether the ith bit of the view is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a view of {} bits", i, self.num_bits);
+        read_bits(self.bits, self.start + i, 1) != 0
+    }
+
+    /// Returns word `w` of the view, as if it were word aligned
+    pub(crate) fn word(&self, w: usize) -> usize {
+        let first = w * BITS_PER_WORD;
+        read_bits(self.bits, self.start + first, (self.num_bits - first).min(BITS_PER_WORD))
+    }
+
+    fn words(&self) -> usize {
+        self.num_bits.div_ceil(BITS_PER_WORD)
+    }
+
+    /// Returns the words of the view, borrowed from the set when the view
+    /// starts on a word boundary and its last word has no bits past the end
+    pub(crate) fn shifted_words(&self) -> Cow<'a, [usize]> {
+        let words = self.words();
+        if get_bit_offset(self.start) == 0 {
+            let first = get_word_offset(self.start);
+            let borrowed = &self.bits[first..first + words];
+            let tail = get_low_mask(self.num_bits - words.saturating_sub(1) * BITS_PER_WORD);
+            if borrowed.last().is_none_or(|&last| last & !tail == 0) {
+                return Cow::Borrowed(borrowed);
+            }
+        }
+        Cow::Owned((0..words).map(|w| self.word(w)).collect())
+    }
+
+    /// returns the number of set bits in the view
+    pub fn count_ones(&self) -> usize {
+        (0..self.words()).map(|w| self.word(w).count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, relative to the
+    /// start of the view, in ascending order
+    pub fn ones(&self) -> DenseOnesIterator<'a> {
+        DenseOnesIterator::over_range(self.bits, self.start, self.num_bits)
+    }
+
+    /// Narrows the view to `range`, relative to the start of the view
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::BitMatrix;
+    ///
+    /// let mut m = BitMatrix::new(4, 200);
+    /// m.set(2, 150);
+    ///
+    /// assert_eq!(m.row(2).range(100..).ones().collect::<Vec<_>>(), vec![50]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> BitSlice<'a> {
+        let range = to_range(range, self.num_bits);
+        BitSlice { bits: self.bits, start: self.start + range.start, num_bits: range.end - range.start }
+    }
+
+    /// Copies the view into an owned set of `len()` bits
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(self.shifted_words().into_owned(), self.num_bits)
+    }
+
+    /// Returns the bitwise-and of the view and `other` as an owned set
+    pub fn and<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.to_dense();
+        output.inplace_and(other);
+        output
+    }
+
+    /// Returns the bitwise-or of the view and `other` as an owned set
+    pub fn or<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.to_dense();
+        output.inplace_or(other);
+        output
+    }
+
+    /// Returns the bitwise-xor of the view and `other` as an owned set
+    pub fn xor<S: BitWords + ?Sized>(&self, other: &S) -> DenseBitSet {
+        let mut output = self.to_dense();
+        output.inplace_xor(other);
+        output
+    }
+}
+
+impl<'a, 'b> PartialEq<BitSlice<'b>> for BitSlice<'a> {
+    fn eq(&self, other: &BitSlice<'b>) -> bool {
+        self.num_bits == other.num_bits && (0..self.words()).all(|w| self.word(w) == other.word(w))
+    }
+}
+
+impl<'a> Eq for BitSlice<'a> {}
+
+impl<'a> fmt::Debug for BitSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitSlice: ")?;
+        for i in 0..self.len() {
+            write!(f, "{}", if self.test(i) { 1 } else { 0 })?;
+        }
+        Ok(())
+    }
+}
+
+/// A mutable view of bits `start..start + len()` of a set, renumbered from
+/// 0, as returned by `DenseBitSet::range_mut`. Writes land in the set;
+/// reads go through `as_bit_slice`.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let mut bs = DenseBitSet::with_capacity(128);
+/// {
+///     let mut view = bs.range_mut(60..70);
+///     view.set(0);
+///     view.set(9);
+///     view.flip(5);
+///     assert_eq!(view.as_bit_slice().count_ones(), 3);
+/// }
+///
+/// assert_eq!(bs.to_indices(), vec![60, 65, 69]);
+/// ```
+pub struct BitSliceMut<'a> {
+    bits: &'a mut [usize],
+    /// position of the view's bit 0 in `bits`
+    start: usize,
+    num_bits: usize,
+}
+
+impl<'a> BitSliceMut<'a> {
+    /// Reborrows the view as a read-only `BitSlice`
+    pub fn as_bit_slice(&self) -> BitSlice<'_> {
+        BitSlice { bits: self.bits, start: self.start, num_bits: self.num_bits }
+    }
+
+    /// returns the number of bits in the view
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// returns whether the view holds no bits
+    pub fn is_empty(&self) -> bool {
+        self.num_bits == 0
+    }
+
+    /// Sets the ith bit of the view.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        let prior = self.as_bit_slice().test(i);
+        write_bits(self.bits, self.start + i, 1, 1);
+        !prior
+    }
+
+    /// Clears the ith bit of the view.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        let prior = self.as_bit_slice().test(i);
+        write_bits(self.bits, self.start + i, 1, 0);
+        prior
+    }
+
+    /// flips the value of the ith bit of the view
+    pub fn flip(&mut self, i: usize) {
+        let prior = self.as_bit_slice().test(i);
+        write_bits(self.bits, self.start + i, 1, !prior as usize);
+    }
+
+    /// Sets every bit of the view to `value`, a word at a time
+    pub fn fill(&mut self, value: bool) {
+        let word = if value { !0 } else { 0 };
+        for first in (0..self.num_bits).step_by(BITS_PER_WORD) {
+            write_bits(self.bits, self.start + first, (self.num_bits - first).min(BITS_PER_WORD), word);
+        }
+    }
+}
+
+impl<'a> BitSet for BitSliceMut<'a> {
+    fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    fn test(&self, i: usize) -> bool {
+        self.as_bit_slice().test(i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        BitSliceMut::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        BitSliceMut::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        self.as_bit_slice().count_ones()
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        let from = from.min(self.num_bits);
+        self.as_bit_slice().range(from..).ones().next().map(|i| from + i)
+    }
+}
+
+impl<'a> fmt::Debug for BitSliceMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_bit_slice().fmt(f)
+    }
+}
+
+impl DenseBitSet {
+    /// Borrows the whole set as a `BitSlice`
+    pub fn as_bit_slice(&self) -> BitSlice<'_> {
+        BitSlice::new(&self.bits, self.num_bits)
+    }
+
+    /// Borrows the bits in `range` as a read-only view, renumbered from 0.
+    /// Unlike `get_range`, nothing is copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(256, vec![10, 100, 199, 200]);
+    ///
+    /// assert_eq!(bs.range(10..200).count_ones(), 3);
+    /// assert_eq!(bs.range(..=10).ones().collect::<Vec<_>>(), vec![10]);
+    /// ```
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> BitSlice<'_> {
+        self.as_bit_slice().range(range)
+    }
+
+    /// Borrows the bits in `range` as a mutable view, renumbered from 0
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> BitSliceMut<'_> {
+        let range = to_range(range, self.len());
+        BitSliceMut { bits: &mut self.bits, start: range.start, num_bits: range.end - range.start }
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for BitSlice<'a> {
+    fn from(bs: &'a DenseBitSet) -> BitSlice<'a> {
+        bs.as_bit_slice()
+    }
+}
+
+// BitSlice TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use BitExpr;
+
+    #[test]
+    fn can_view_dense_set() {
+        let bs = DenseBitSet::from_indices(200, vec![1, 100, 199]);
+        let slice = BitSlice::from(&bs);
+
+        assert_eq!(slice.len(), bs.len());
+        assert!(slice.test(100));
+        assert!(!slice.test(101));
+        assert_eq!(slice.ones().collect::<Vec<_>>(), vec![1, 100, 199]);
+        assert_eq!(slice.to_dense(), bs);
+    }
+
+    #[test]
+    fn views_match_copied_ranges() {
+        let bs = DenseBitSet::from_indices(256, (0..256).filter(|&i| i % 3 == 0 || i > 190));
+
+        for start in (0..256).step_by(7) {
+            for end in (start..=256).step_by(11) {
+                let view = bs.range(start..end);
+                let copy = bs.get_range(start..end);
+
+                assert_eq!(view.len(), end - start);
+                assert_eq!(view.to_dense(), copy);
+                assert_eq!(view.count_ones(), copy.count_ones());
+                assert_eq!(view.ones().collect::<Vec<_>>(), copy.to_indices());
+                assert!((0..view.len()).all(|i| view.test(i) == copy.test(i)));
+            }
+        }
+
+        // narrowing a view is relative to the view
+        assert_eq!(bs.range(10..200).range(50..60), bs.range(60..70));
+        assert_eq!(bs.as_bit_slice(), bs.range(..));
+    }
+
+    #[test]
+    fn writes_through_view_stay_in_range() {
+        let mut bs = DenseBitSet::from_indices(256, (0..256).filter(|&i| i % 2 == 0));
+        let before = bs.clone();
+        {
+            let mut view = bs.range_mut(61..131);
+            view.fill(true);
+            assert_eq!(view.as_bit_slice().count_ones(), 70);
+            assert!(view.remove(0));
+            assert!(view.set(0));
+            view.flip(69);
+            view.flip(69);
+            view.fill(false);
+            view.set(3);
+        }
+
+        for i in 0..256 {
+            let expected = if (61..131).contains(&i) { i == 64 } else { before.test(i) };
+            assert_eq!(bs.test(i), expected, "bit {}", i);
+        }
+    }
+
+    #[test]
+    fn views_are_operands() {
+        let bs = DenseBitSet::from_indices(256, (0..256).filter(|&i| i % 3 == 0 || i > 190));
+        let other: DenseBitSet = (0..100).map(|i| i % 4 == 0).collect();
+
+        // unaligned, aligned with a dirty tail, and aligned to the end of the set
+        for range in [61..161, 64..164, 156..256] {
+            let view = bs.range(range.clone());
+            let copy = bs.get_range(range.clone());
+            let borrowed = range.start % BITS_PER_WORD == 0 && range.end == 256;
+
+            assert_eq!(matches!(view.as_words(), Cow::Borrowed(_)), borrowed);
+            assert_eq!(other.and(&view), other.and(&copy));
+            assert_eq!(other.xor(&view), other.xor(&copy));
+            assert_eq!(other.intersection_count(&view), other.intersection_count(&copy));
+            assert_eq!(other.and_ref(&view).ones().collect::<Vec<_>>(), other.and(&copy).to_indices());
+            assert_eq!(DenseBitSet::concat(&view, &view), DenseBitSet::concat(&copy, &copy));
+        }
+    }
+
+    #[test]
+    fn mutable_views_are_bit_sets() {
+        fn mark_multiples<S: BitSet>(set: &mut S, n: usize) {
+            for i in (0..set.len()).step_by(n) {
+                set.set(i);
+            }
+        }
+
+        let mut bs = DenseBitSet::with_capacity(256);
+        {
+            let mut view = bs.range_mut(100..200);
+            mark_multiples(&mut view, 7);
+            assert_eq!(BitSet::count_ones(&view), 15);
+            assert_eq!(view.next_set_bit(8), Some(14));
+            assert_eq!(view.next_set_bit(99), None);
+            assert_eq!(view.next_set_bit(500), None);
+            assert_eq!(DenseBitSet::with_capacity(100).or(&view).count_ones(), 15);
+        }
+        assert_eq!(bs.to_indices(), (100..200).step_by(7).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn view_rejects_out_of_bounds() {
+        let bs = DenseBitSet::with_capacity(128);
+        bs.range(10..20).test(10);
+    }
+}
@@ -0,0 +1,192 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Per-position counts across many sets, kept as bit planes
+
+use combine::{ add_word, at_least, planes_for };
+use { BitWords, DenseBitSet, BITS_PER_WORD };
+
+/// Counts, for every position, how many of the added sets held it.
+///
+/// Counts are bit-sliced: plane `p` holds bit `p` of every position's count,
+/// so adding a set is a ripple-carry add of one word per plane and
+/// thresholding is a word-wise comparison, with no per-position loop.
+/// Planes are added as the number of sets grows, so memory is
+/// `len * log2(added)` bits.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ BitSlicedCounter, DenseBitSet };
+///
+/// let mut hits = BitSlicedCounter::new(64);
+/// hits.add(&DenseBitSet::from_indices(64, vec![1, 2]));
+/// hits.add(&DenseBitSet::from_indices(64, vec![2, 3]));
+/// hits.add(&DenseBitSet::from_indices(64, vec![2]));
+///
+/// assert_eq!(hits.count(2), 3);
+/// assert_eq!(hits.count(1), 1);
+/// assert_eq!(hits.at_least(2).ones().collect::<Vec<_>>(), vec![2]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitSlicedCounter {
+    /// number of positions counted
+    len: usize,
+    /// number of sets added so far
+    added: usize,
+    /// number of planes per word
+    planes: usize,
+    /// the planes of word `w` are `counts[w * planes..(w + 1) * planes]`,
+    /// lowest bit first
+    counts: Vec<usize>,
+}
+
+impl BitSlicedCounter {
+    /// Creates a counter over `len` positions with every count zero
+    pub fn new(len: usize) -> BitSlicedCounter {
+        BitSlicedCounter { len, added: 0, planes: 0, counts: Vec::new() }
+    }
+
+    /// returns the number of positions counted
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// returns the number of sets added since creation or the last clear
+    pub fn added(&self) -> usize {
+        self.added
+    }
+
+    /// returns the number of bit planes the counts are stored in
+    pub fn planes(&self) -> usize {
+        self.planes
+    }
+
+    fn words(&self) -> usize {
+        self.len.div_ceil(BITS_PER_WORD)
+    }
+
+    /// Spreads the counts over `planes` planes per word
+    fn widen(&mut self, planes: usize) {
+        let mut counts = vec![0; self.words() * planes];
+        if self.planes > 0 {
+            for (new, old) in counts.chunks_exact_mut(planes).zip(self.counts.chunks_exact(self.planes)) {
+                new[..self.planes].copy_from_slice(old);
+            }
+        }
+        self.counts = counts;
+        self.planes = planes;
+    }
+
+    /// Adds one to the count of every position set in `set`. Panics if
+    /// `set` is not `len()` bits long.
+    pub fn add<S: BitWords + ?Sized>(&mut self, set: &S) {
+        assert_eq!(set.bit_len(), self.len);
+
+        let planes = planes_for(self.added + 1);
+        if planes > self.planes {
+            self.widen(planes);
+        }
+        for (counter, &word) in self.counts.chunks_exact_mut(self.planes).zip(set.as_words().iter()) {
+            add_word(counter, word);
+        }
+        self.added += 1;
+    }
+
+    /// Returns how many of the added sets held position `i`
+    pub fn count(&self, i: usize) -> usize {
+        assert!(i < self.len);
+        if self.planes == 0 {
+            return 0;
+        }
+
+        let (word, bit) = (i / BITS_PER_WORD, i % BITS_PER_WORD);
+        let counter = &self.counts[word * self.planes..(word + 1) * self.planes];
+        counter.iter().enumerate().map(|(p, plane)| (plane >> bit & 1) << p).sum()
+    }
+
+    /// Returns the set of positions counted at least `k` times
+    pub fn at_least(&self, k: usize) -> DenseBitSet {
+        let mut bits = vec![0; self.words()];
+        if k == 0 {
+            bits.iter_mut().for_each(|w| *w = !0);
+        } else if k <= self.added {
+            for (out, counter) in bits.iter_mut().zip(self.counts.chunks_exact(self.planes)) {
+                *out = at_least(counter, k);
+            }
+        }
+        DenseBitSet::from_vec_masked(bits, self.len)
+    }
+
+    /// Resets every count to zero, keeping the allocation
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|w| *w = 0);
+        self.added = 0;
+    }
+}
+
+// BitSlicedCounter TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn counts_match_naive_counts() {
+        let len = 64 * 3 + 17;
+        let mut counter = BitSlicedCounter::new(len);
+        let mut naive = vec![0; len];
+
+        for step in 1..20 {
+            let mut set = DenseBitSet::from_vec_masked(vec![0; len.div_ceil(BITS_PER_WORD)], len);
+            for i in (0..len).step_by(step) {
+                set.set(i);
+                naive[i] += 1;
+            }
+            counter.add(&set);
+        }
+
+        assert_eq!(counter.added(), 19);
+        assert_eq!(counter.planes(), 5);
+        assert!((0..len).all(|i| counter.count(i) == naive[i]));
+        for k in 0..22 {
+            let expected: Vec<_> = (0..len).filter(|&i| naive[i] >= k).collect();
+            assert_eq!(counter.at_least(k).ones().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn can_clear_counts() {
+        let mut counter = BitSlicedCounter::new(10);
+        let set = DenseBitSet::from_vec_masked(vec![0b110], 10);
+        counter.add(&set);
+        counter.add(&set);
+        counter.clear();
+
+        assert_eq!(counter.count(1), 0);
+        assert_eq!(counter.at_least(1).count_ones(), 0);
+        counter.add(&set);
+        assert_eq!(counter.count(2), 1);
+    }
+}
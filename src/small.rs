@@ -0,0 +1,252 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `SmallBitSet`, a bit set stored inline until it outgrows a few words
+
+use std::borrow::Cow;
+
+use { get_bitmask, get_word_offset, BitSet, BitWords, DenseBitSet, DenseOnesIterator, BITS_PER_WORD };
+
+#[derive(Clone, Debug)]
+enum Storage<const N: usize> {
+    Inline([usize; N]),
+    Heap(Vec<usize>),
+}
+
+/// A bit set that keeps up to `N` words inline and only allocates when it
+/// needs more, like `SmallVec`. Short-lived sets of a few hundred bits
+/// never touch the heap.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::SmallBitSet;
+///
+/// let mut flags: SmallBitSet<4> = SmallBitSet::new(200);
+/// flags.set(199);
+/// assert!(!flags.spilled());
+///
+/// flags.grow(1000);
+/// flags.set(999);
+/// assert!(flags.spilled());
+/// assert_eq!(flags.to_dense().to_indices(), vec![199, 999]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SmallBitSet<const N: usize = 4> {
+    storage: Storage<N>,
+    num_bits: usize,
+}
+
+impl<const N: usize> SmallBitSet<N> {
+    /// Creates an empty set of `num_bits` bits, inline if it fits in `N` words
+    pub fn new(num_bits: usize) -> SmallBitSet<N> {
+        let words = num_bits.div_ceil(BITS_PER_WORD);
+        let storage = if words <= N { Storage::Inline([0; N]) } else { Storage::Heap(vec![0; words]) };
+        SmallBitSet { storage, num_bits }
+    }
+
+    /// Returns the number of bits the set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns whether the words have moved to the heap
+    pub fn spilled(&self) -> bool {
+        match self.storage {
+            Storage::Inline(_) => false,
+            Storage::Heap(_) => true,
+        }
+    }
+
+    /// Returns the words holding the bits
+    pub fn as_words(&self) -> &[usize] {
+        let words = self.num_bits.div_ceil(BITS_PER_WORD);
+        match self.storage {
+            Storage::Inline(ref bits) => &bits[..words],
+            Storage::Heap(ref bits) => bits,
+        }
+    }
+
+    fn as_words_mut(&mut self) -> &mut [usize] {
+        let words = self.num_bits.div_ceil(BITS_PER_WORD);
+        match self.storage {
+            Storage::Inline(ref mut bits) => &mut bits[..words],
+            Storage::Heap(ref mut bits) => bits,
+        }
+    }
+
+    /// Extends the set to `num_bits` bits, moving it to the heap if it no
+    /// longer fits inline. Does nothing if the set is already that long.
+    pub fn grow(&mut self, num_bits: usize) {
+        if num_bits <= self.num_bits {
+            return;
+        }
+
+        let words = num_bits.div_ceil(BITS_PER_WORD);
+        if words > N {
+            let mut bits = self.as_words().to_vec();
+            bits.resize(words, 0);
+            self.storage = Storage::Heap(bits);
+        }
+        self.num_bits = num_bits;
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        self.as_words()[get_word_offset(i)] & get_bitmask(i) != 0
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let word = &mut self.as_words_mut()[get_word_offset(i)];
+        let was_clear = *word & get_bitmask(i) == 0;
+        *word |= get_bitmask(i);
+        was_clear
+    }
+
+    /// Clears the ith bit.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let word = &mut self.as_words_mut()[get_word_offset(i)];
+        let was_set = *word & get_bitmask(i) != 0;
+        *word &= !get_bitmask(i);
+        was_set
+    }
+
+    /// Flips the ith bit
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        self.as_words_mut()[get_word_offset(i)] ^= get_bitmask(i);
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.as_words().iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> DenseOnesIterator<'_> {
+        DenseOnesIterator::new(self.as_words(), self.num_bits)
+    }
+
+    /// Copies the bits into a `DenseBitSet`
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(self.as_words().to_vec(), self.num_bits)
+    }
+}
+
+/// Compares lengths and bits, wherever they are stored
+impl<const N: usize> PartialEq for SmallBitSet<N> {
+    fn eq(&self, other: &SmallBitSet<N>) -> bool {
+        self.num_bits == other.num_bits && self.as_words() == other.as_words()
+    }
+}
+
+impl<const N: usize> Eq for SmallBitSet<N> {}
+
+impl<const N: usize> BitWords for SmallBitSet<N> {
+    fn as_words(&self) -> Cow<'_, [usize]> {
+        Cow::Borrowed(SmallBitSet::as_words(self))
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<const N: usize> BitSet for SmallBitSet<N> {
+    fn len(&self) -> usize {
+        SmallBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        SmallBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        SmallBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        SmallBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        SmallBitSet::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        self.ones().find(|&i| i >= from)
+    }
+}
+
+// SmallBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn stays_inline_until_it_outgrows_n_words() {
+        let mut bs: SmallBitSet<2> = SmallBitSet::new(2 * BITS_PER_WORD);
+        bs.set(0);
+        bs.set(2 * BITS_PER_WORD - 1);
+        assert!(!bs.spilled());
+
+        bs.grow(2 * BITS_PER_WORD + 1);
+        assert!(bs.spilled());
+        bs.set(2 * BITS_PER_WORD);
+        assert_eq!(bs.ones().collect::<Vec<_>>(), vec![0, 2 * BITS_PER_WORD - 1, 2 * BITS_PER_WORD]);
+
+        assert!(SmallBitSet::<1>::new(BITS_PER_WORD + 1).spilled());
+    }
+
+    #[test]
+    fn equality_ignores_storage() {
+        let mut inline: SmallBitSet<4> = SmallBitSet::new(100);
+        let mut heap: SmallBitSet<4> = SmallBitSet::new(10);
+        heap.storage = Storage::Heap(vec![0; 2]);
+        heap.num_bits = 100;
+
+        inline.set(70);
+        heap.set(70);
+        assert_eq!(inline, heap);
+    }
+
+    #[test]
+    fn combines_with_dense_sets() {
+        let mut small: SmallBitSet = SmallBitSet::new(128);
+        small.set(5);
+        small.flip(100);
+
+        let mut dense = DenseBitSet::from_indices(128, vec![6]);
+        dense.inplace_or(&small);
+        assert_eq!(dense.to_indices(), vec![5, 6, 100]);
+        assert_eq!(small.next_set_bit(6), Some(100));
+        assert_eq!(small.to_dense(), DenseBitSet::from_indices(128, vec![5, 100]));
+    }
+}
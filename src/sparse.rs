@@ -0,0 +1,239 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `SparseBitSet`, a bit set storing only its nonzero words
+
+use std::collections::HashMap;
+
+use { get_bit_offset, get_bitmask, get_word_offset, BitSet, DenseBitSet, BITS_PER_WORD };
+
+/// A bit set over a possibly huge domain that stores only the words with
+/// at least one set bit, in a hash map keyed by word index.
+///
+/// Memory grows with the number of occupied words rather than with `len()`,
+/// and `test`, `set` and `remove` stay constant time. Ordered queries such
+/// as `next_set_bit` and `to_indices` have to look at every stored word.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::SparseBitSet;
+///
+/// let mut seen = SparseBitSet::new(usize::MAX);
+/// seen.set(7);
+/// seen.set(1 << 60);
+///
+/// assert!(seen.test(1 << 60));
+/// assert_eq!(seen.to_indices(), vec![7, 1 << 60]);
+/// assert_eq!(seen.stored_words(), 2);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseBitSet {
+    words: HashMap<usize, usize>,
+    num_bits: usize,
+}
+
+impl SparseBitSet {
+    /// Creates an empty set of `num_bits` bits without allocating storage
+    pub fn new(num_bits: usize) -> SparseBitSet {
+        SparseBitSet { words: HashMap::new(), num_bits }
+    }
+
+    /// Returns the number of bits the set can accommodate
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Returns the number of nonzero words held in memory
+    pub fn stored_words(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        self.words.get(&get_word_offset(i)).is_some_and(|&word| word & get_bitmask(i) != 0)
+    }
+
+    /// Sets the ith bit.
+    /// Returns true if bit was not set previously
+    pub fn set(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let word = self.words.entry(get_word_offset(i)).or_insert(0);
+        let was_clear = *word & get_bitmask(i) == 0;
+        *word |= get_bitmask(i);
+        was_clear
+    }
+
+    /// Clears the ith bit, dropping its word once the word is empty.
+    /// Returns true if bit was set previously
+    pub fn remove(&mut self, i: usize) -> bool {
+        assert!(i < self.num_bits, "index {} is out of bounds for a set of {} bits", i, self.num_bits);
+        let w = get_word_offset(i);
+        match self.words.get_mut(&w) {
+            Some(word) if *word & get_bitmask(i) != 0 => {
+                *word &= !get_bitmask(i);
+                if *word == 0 {
+                    self.words.remove(&w);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Flips the ith bit
+    pub fn flip(&mut self, i: usize) {
+        if !self.remove(i) {
+            self.set(i);
+        }
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words.values().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns the index of the lowest set bit at or after `from`, or
+    /// `None` if there is none
+    pub fn next_set_bit(&self, from: usize) -> Option<usize> {
+        if from >= self.num_bits {
+            return None;
+        }
+
+        let first = get_word_offset(from);
+        self.words.iter()
+            .filter_map(|(&w, &word)| {
+                let word = if w == first { word & !0 << get_bit_offset(from) } else { word };
+                if w >= first && word != 0 {
+                    Some(w * BITS_PER_WORD + word.trailing_zeros() as usize)
+                } else {
+                    None
+                }
+            })
+            .min()
+    }
+
+    /// Returns the indices of the set bits in ascending order
+    pub fn to_indices(&self) -> Vec<usize> {
+        let mut words: Vec<(usize, usize)> = self.words.iter().map(|(&w, &word)| (w, word)).collect();
+        words.sort_unstable();
+
+        let mut indices = Vec::with_capacity(self.count_ones());
+        for (w, mut word) in words {
+            while word != 0 {
+                indices.push(w * BITS_PER_WORD + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
+        }
+        indices
+    }
+
+    /// Expands into a `DenseBitSet` of the same length
+    pub fn to_dense(&self) -> DenseBitSet {
+        let mut bits = vec![0; self.num_bits.div_ceil(BITS_PER_WORD)];
+        for (&w, &word) in &self.words {
+            bits[w] = word;
+        }
+        DenseBitSet::from_vec_masked(bits, self.num_bits)
+    }
+}
+
+impl<'a> From<&'a DenseBitSet> for SparseBitSet {
+    fn from(bits: &'a DenseBitSet) -> SparseBitSet {
+        SparseBitSet {
+            words: bits.bits.iter().cloned().enumerate().filter(|&(_, word)| word != 0).collect(),
+            num_bits: bits.len(),
+        }
+    }
+}
+
+impl BitSet for SparseBitSet {
+    fn len(&self) -> usize {
+        SparseBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        SparseBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        SparseBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        SparseBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        SparseBitSet::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        SparseBitSet::next_set_bit(self, from)
+    }
+
+    fn to_indices(&self) -> Vec<usize> {
+        SparseBitSet::to_indices(self)
+    }
+}
+
+// SparseBitSet TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn matches_dense() {
+        let dense = DenseBitSet::from_indices(1000, (0..1000).filter(|i| i % 97 < 3));
+        let sparse = SparseBitSet::from(&dense);
+
+        assert_eq!(sparse.to_dense(), dense);
+        assert_eq!(sparse.count_ones(), dense.count_ones());
+        assert_eq!(sparse.to_indices(), dense.to_indices());
+        for from in [0, 1, 97, 98, 500, 999] {
+            assert_eq!(sparse.next_set_bit(from), dense.next_set_bit(from));
+        }
+    }
+
+    #[test]
+    fn drops_emptied_words() {
+        let mut set = SparseBitSet::new(1 << 40);
+        assert!(set.set(5));
+        assert!(!set.set(5));
+        set.flip(6);
+        assert_eq!(set.stored_words(), 1);
+
+        assert!(set.remove(5));
+        set.flip(6);
+        assert_eq!(set.stored_words(), 0);
+        assert_eq!(set.next_set_bit(0), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_out_of_bounds() {
+        SparseBitSet::new(10).set(10);
+    }
+}
@@ -0,0 +1,379 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A checksummed word stream for persisting `DenseBitSet`s
+//!
+//! The stream is a sequence of blocks, each holding:
+//!
+//! * the number of words in the block, a little-endian `u32` of at most
+//!   `MAX_BLOCK_WORDS`,
+//! * that many words as little-endian `u64`s,
+//! * the CRC-32 (IEEE) of the word bytes, a little-endian `u32`.
+//!
+//! The stream ends with an empty block, a zero header followed by the zero
+//! checksum of no bytes, so a stream cut off at a block boundary is told
+//! apart from a complete one. Words are 64 bits on every platform, matching
+//! the little-endian byte format of `to_le_bytes`.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::io::{ self, Read, Write };
+
+use DenseBitSet;
+
+/// most words a block may hold
+pub const MAX_BLOCK_WORDS: usize = 1024;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| (crc >> 8) ^ CRC_TABLE[((crc ^ b as u32) & 0xff) as usize])
+}
+
+/// An error found while reading a checksummed word stream
+#[derive(Debug)]
+pub enum WordStreamError {
+    /// the reader failed, or the stream ended before its empty last block
+    Io(io::Error),
+    /// a block header announced more than `MAX_BLOCK_WORDS` words
+    BlockTooLarge {
+        /// index of the block in the stream
+        block: usize,
+        /// the announced number of words
+        words: usize,
+    },
+    /// the words of a block do not match its checksum
+    ChecksumMismatch {
+        /// index of the block in the stream
+        block: usize,
+        /// the checksum stored in the stream
+        expected: u32,
+        /// the checksum of the words read
+        found: u32,
+    },
+    /// the stream decoded, but its contents do not describe a valid set
+    InvalidSet,
+}
+
+impl fmt::Display for WordStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WordStreamError::Io(ref e) => write!(f, "word stream read failed: {}", e),
+            WordStreamError::BlockTooLarge { block, words } => {
+                write!(f, "block {} announces {} words, more than {}", block, words, MAX_BLOCK_WORDS)
+            }
+            WordStreamError::ChecksumMismatch { block, expected, found } => {
+                write!(f, "block {} checksum mismatch: expected {:08x}, found {:08x}", block, expected, found)
+            }
+            WordStreamError::InvalidSet => write!(f, "word stream does not describe a valid bit set"),
+        }
+    }
+}
+
+impl Error for WordStreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            WordStreamError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WordStreamError {
+    fn from(e: io::Error) -> WordStreamError {
+        WordStreamError::Io(e)
+    }
+}
+
+/// Writes `words` as a checksummed word stream
+pub fn write_checksummed_words<W: Write>(mut writer: W, words: &[u64]) -> io::Result<()> {
+    for chunk in words.chunks(MAX_BLOCK_WORDS) {
+        let bytes: Vec<u8> = chunk.iter().flat_map(|word| word.to_le_bytes()).collect();
+        writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        writer.write_all(&crc32(&bytes).to_le_bytes())?;
+    }
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&crc32(&[]).to_le_bytes())
+}
+
+/// Streams the words of a checksummed word stream from `reader`. Each
+/// block is verified against its checksum before any of its words are
+/// yielded; the first error is yielded as an `Err` item and ends the
+/// iteration.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ verify_and_iter_words, write_checksummed_words };
+///
+/// let mut stream = Vec::new();
+/// write_checksummed_words(&mut stream, &[1, 2, 3]).unwrap();
+///
+/// let words: Result<Vec<u64>, _> = verify_and_iter_words(&stream[..]).collect();
+/// assert_eq!(words.unwrap(), vec![1, 2, 3]);
+///
+/// stream[5] ^= 0xff;
+/// assert!(verify_and_iter_words(&stream[..]).any(|word| word.is_err()));
+/// ```
+pub fn verify_and_iter_words<R: Read>(reader: R) -> VerifiedWords<R> {
+    VerifiedWords { reader, block: 0, words: Vec::new(), pos: 0, done: false }
+}
+
+/// Iterator over the words of a checksummed word stream, returned by
+/// `verify_and_iter_words`
+pub struct VerifiedWords<R: Read> {
+    reader: R,
+    block: usize,
+    words: Vec<u64>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> VerifiedWords<R> {
+    /// reads and verifies the next block; `Ok(false)` at the empty last block
+    fn read_block(&mut self) -> Result<bool, WordStreamError> {
+        let mut header = [0u8; 4];
+        self.reader.read_exact(&mut header)?;
+
+        let count = u32::from_le_bytes(header) as usize;
+        if count > MAX_BLOCK_WORDS {
+            return Err(WordStreamError::BlockTooLarge { block: self.block, words: count });
+        }
+
+        let mut bytes = vec![0u8; count * 8];
+        self.reader.read_exact(&mut bytes)?;
+        let mut checksum = [0u8; 4];
+        self.reader.read_exact(&mut checksum)?;
+
+        let expected = u32::from_le_bytes(checksum);
+        let found = crc32(&bytes);
+        if expected != found {
+            return Err(WordStreamError::ChecksumMismatch { block: self.block, expected, found });
+        }
+
+        if count == 0 {
+            return Ok(false);
+        }
+
+        self.words.clear();
+        self.words.extend(bytes.chunks(8).map(|chunk| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        }));
+        self.pos = 0;
+        self.block += 1;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for VerifiedWords<R> {
+    type Item = Result<u64, WordStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.pos == self.words.len() {
+            match self.read_block() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        if self.pos < self.words.len() {
+            self.pos += 1;
+            Some(Ok(self.words[self.pos - 1]))
+        } else {
+            None
+        }
+    }
+}
+
+impl DenseBitSet {
+    /// Writes the set as a checksummed word stream: a first word holding
+    /// `len()`, then the bits as 64-bit words in `to_le_bytes` order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitsets::DenseBitSet;
+    ///
+    /// let bs = DenseBitSet::from_indices(200, vec![3, 150]);
+    /// let mut stream = Vec::new();
+    /// bs.write_checksummed(&mut stream).unwrap();
+    ///
+    /// assert_eq!(DenseBitSet::read_checksummed(&stream[..]).unwrap(), bs);
+    /// ```
+    pub fn write_checksummed<W: Write>(&self, writer: W) -> io::Result<()> {
+        profiled!(Serialize, self.words(), self.write_checksummed_untimed(writer))
+    }
+
+    /// Reads a set written by `write_checksummed`, verifying every block
+    pub fn read_checksummed<R: Read>(reader: R) -> Result<DenseBitSet, WordStreamError> {
+        profiled!(
+            Deserialize,
+            |read| read.as_ref().map_or(0, DenseBitSet::words),
+            DenseBitSet::read_checksummed_untimed(reader)
+        )
+    }
+
+    fn write_checksummed_untimed<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut bytes = self.to_le_bytes();
+        bytes.resize(bytes.len().div_ceil(8) * 8, 0);
+
+        let mut words = vec![self.len() as u64];
+        words.extend(bytes.chunks(8).map(|chunk| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        }));
+        write_checksummed_words(writer, &words)
+    }
+
+    fn read_checksummed_untimed<R: Read>(reader: R) -> Result<DenseBitSet, WordStreamError> {
+        let mut words = verify_and_iter_words(reader);
+        let num_bits = match words.next() {
+            Some(word) => usize::try_from(word?).map_err(|_| WordStreamError::InvalidSet)?,
+            None => return Err(WordStreamError::InvalidSet),
+        };
+
+        let mut bytes = Vec::new();
+        for word in words {
+            bytes.extend_from_slice(&word?.to_le_bytes());
+        }
+        if bytes.len() != num_bits.div_ceil(64) * 8 {
+            return Err(WordStreamError::InvalidSet);
+        }
+
+        bytes.truncate(num_bits.div_ceil(8));
+        let words = ::word::words_from_le_bytes(&bytes);
+        DenseBitSet::from_vec_with_len(words, num_bits).ok_or(WordStreamError::InvalidSet)
+    }
+}
+
+// Word stream TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn crc_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn can_stream_many_blocks() {
+        let words: Vec<u64> = (0..3000).map(|i| i * 0x9e37_79b9).collect();
+        let mut stream = Vec::new();
+        write_checksummed_words(&mut stream, &words).unwrap();
+
+        let read: Vec<u64> = verify_and_iter_words(&stream[..]).map(|w| w.unwrap()).collect();
+        assert_eq!(read, words);
+    }
+
+    #[test]
+    fn detects_corruption_in_later_block() {
+        let words: Vec<u64> = (0..2500).collect();
+        let mut stream = Vec::new();
+        write_checksummed_words(&mut stream, &words).unwrap();
+
+        // a word in the second block
+        let offset = 4 + MAX_BLOCK_WORDS * 8 + 4 + 4 + 16;
+        stream[offset] ^= 1;
+
+        let items: Vec<_> = verify_and_iter_words(&stream[..]).collect();
+        assert_eq!(items.iter().filter(|w| w.is_ok()).count(), MAX_BLOCK_WORDS);
+        match items.last() {
+            Some(Err(WordStreamError::ChecksumMismatch { block: 1, .. })) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detects_truncation() {
+        let bs = DenseBitSet::from_indices(1000, vec![1, 999]);
+        let mut stream = Vec::new();
+        bs.write_checksummed(&mut stream).unwrap();
+        stream.pop();
+
+        match DenseBitSet::read_checksummed(&stream[..]) {
+            Err(WordStreamError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detects_truncation_at_block_boundary() {
+        let words: Vec<u64> = (0..1500).collect();
+        let mut stream = Vec::new();
+        write_checksummed_words(&mut stream, &words).unwrap();
+
+        // keep the first block whole and drop the rest
+        stream.truncate(4 + MAX_BLOCK_WORDS * 8 + 4);
+
+        let items: Vec<_> = verify_and_iter_words(&stream[..]).collect();
+        assert_eq!(items.iter().filter(|w| w.is_ok()).count(), MAX_BLOCK_WORDS);
+        match items.last() {
+            Some(Err(WordStreamError::Io(ref e))) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn can_stream_no_words() {
+        let mut stream = Vec::new();
+        write_checksummed_words(&mut stream, &[]).unwrap();
+
+        assert_eq!(stream.len(), 8);
+        assert_eq!(verify_and_iter_words(&stream[..]).count(), 0);
+        assert!(verify_and_iter_words(&[][..]).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn rejects_stray_padding() {
+        let mut stream = Vec::new();
+        write_checksummed_words(&mut stream, &[3, 0b1000]).unwrap();
+
+        assert!(DenseBitSet::read_checksummed(&stream[..]).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `TypedIndexExt`, checked indexing with any integer type
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use BitSet;
+
+/// The error returned when a typed index does not name a bit of the set
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IndexError {
+    /// the index is negative or too large for `usize`
+    Unrepresentable,
+    /// the index is at or past the length of the set
+    OutOfBounds {
+        /// the index, converted to `usize`
+        index: usize,
+        /// the length of the set
+        len: usize,
+    },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexError::Unrepresentable => write!(f, "index is not representable as usize"),
+            IndexError::OutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for a set of {} bits", index, len)
+            }
+        }
+    }
+}
+
+impl Error for IndexError {}
+
+/// Checked `set`/`test`/`clear` taking any index type convertible to
+/// `usize`, such as `u32` or `i64` ids. Implemented for every `BitSet`;
+/// bring it into scope to opt in.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::{ DenseBitSet, IndexError, TypedIndexExt };
+///
+/// let mut users = DenseBitSet::with_capacity(1024);
+/// let id: i64 = 42;
+///
+/// assert_eq!(users.try_set(id), Ok(true));
+/// assert_eq!(users.try_test(42u32), Ok(true));
+/// assert_eq!(users.try_set(-1i64), Err(IndexError::Unrepresentable));
+/// assert_eq!(users.try_clear(5000u16), Err(IndexError::OutOfBounds { index: 5000, len: 1024 }));
+/// ```
+pub trait TypedIndexExt: BitSet {
+    /// Tests the bit at `index`
+    fn try_test<I: TryInto<usize>>(&self, index: I) -> Result<bool, IndexError> {
+        let i = checked_index(self.len(), index)?;
+        Ok(self.test(i))
+    }
+
+    /// Sets the bit at `index`, returning true if it was not set previously
+    fn try_set<I: TryInto<usize>>(&mut self, index: I) -> Result<bool, IndexError> {
+        let i = checked_index(self.len(), index)?;
+        Ok(self.set(i))
+    }
+
+    /// Clears the bit at `index`, returning true if it was set previously
+    fn try_clear<I: TryInto<usize>>(&mut self, index: I) -> Result<bool, IndexError> {
+        let i = checked_index(self.len(), index)?;
+        Ok(self.remove(i))
+    }
+}
+
+impl<S: BitSet + ?Sized> TypedIndexExt for S {}
+
+fn checked_index<I: TryInto<usize>>(len: usize, index: I) -> Result<usize, IndexError> {
+    let index = index.try_into().map_err(|_| IndexError::Unrepresentable)?;
+    if index < len {
+        Ok(index)
+    } else {
+        Err(IndexError::OutOfBounds { index, len })
+    }
+}
+
+// TypedIndexExt TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use DenseBitSet;
+
+    #[test]
+    fn works_for_every_backend() {
+        #[cfg(feature = "sparse")]
+        {
+            let mut sparse = ::SparseBitSet::new(1 << 40);
+            assert_eq!(sparse.try_set(1u64 << 39), Ok(true));
+            assert_eq!(sparse.try_test(1i128 << 39), Ok(true));
+            assert_eq!(sparse.try_clear(1u64 << 39), Ok(true));
+            assert_eq!(sparse.try_test(u128::MAX), Err(IndexError::Unrepresentable));
+        }
+
+        let mut dense = DenseBitSet::with_capacity(64);
+        assert_eq!(dense.try_set(63i8), Ok(true));
+        assert_eq!(dense.try_set(64u8), Err(IndexError::OutOfBounds { index: 64, len: 64 }));
+        assert_eq!(dense.to_indices(), vec![63]);
+    }
+}
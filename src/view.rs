@@ -0,0 +1,201 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Zero-copy, read-only views over serialized `DenseBitSet` words
+
+use std::fmt;
+
+use word::words_from_le_bytes;
+use { DenseBitSet, BITS_PER_BYTE };
+
+/// A read-only bit set borrowed from a byte buffer holding little-endian
+/// words, such as a memory-mapped file.
+///
+/// Bit `i` lives in bit `i % 8` of byte `i / 8`, which is exactly the
+/// layout of little-endian words of any width, so the buffer is queried in
+/// place: there is no deserialization pass and no alignment requirement.
+/// With the `serde` feature, a view can also be deserialized straight out
+/// of a buffer in any format that can lend out bytes, such as bincode.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSetView;
+///
+/// let bytes = [0b0000_0101, 0b1000_0000];
+/// let view = DenseBitSetView::new(&bytes, 16).unwrap();
+///
+/// assert!(view.test(0));
+/// assert!(view.test(15));
+/// assert_eq!(view.ones().collect::<Vec<_>>(), vec![0, 2, 15]);
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct DenseBitSetView<'a> {
+    num_bits: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> DenseBitSetView<'a> {
+    /// Creates a view of `num_bits` bits over `bytes`.
+    /// Returns `None` unless `bytes` holds exactly one byte per 8 bits.
+    pub fn new(bytes: &'a [u8], num_bits: usize) -> Option<DenseBitSetView<'a>> {
+        if bytes.len() == num_bits.div_ceil(BITS_PER_BYTE) {
+            Some(DenseBitSetView { num_bits, bytes })
+        } else {
+            None
+        }
+    }
+
+    /// returns the number of bits in the view
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.num_bits
+    }
+
+    /// returns the underlying little-endian bytes
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Tests whether the ith bit is set
+    pub fn test(&self, i: usize) -> bool {
+        assert!(i < self.num_bits);
+        (self.bytes[i / BITS_PER_BYTE] >> (i % BITS_PER_BYTE)) & 1 != 0
+    }
+
+    /// returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        let mut count: usize = self.bytes.iter().map(|byte| byte.count_ones() as usize).sum();
+
+        // padding bits of the last byte are not part of the set
+        if let Some(last) = self.bytes.last() {
+            let used = self.num_bits - (self.bytes.len() - 1) * BITS_PER_BYTE;
+            count -= last.checked_shr(used as u32).unwrap_or(0).count_ones() as usize;
+        }
+        count
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> DenseBitSetViewOnes<'a> {
+        DenseBitSetViewOnes {
+            num_bits: self.num_bits,
+            bytes: self.bytes,
+            byte_idx: 0,
+            current: self.bytes.first().cloned().unwrap_or(0),
+        }
+    }
+
+    /// Copies the view into an owned `DenseBitSet` of exactly `len()` bits
+    pub fn to_dense(&self) -> DenseBitSet {
+        DenseBitSet::from_vec_masked(words_from_le_bytes(self.bytes), self.num_bits)
+    }
+}
+
+impl<'a> fmt::Debug for DenseBitSetView<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DenseBitSetView: ")?;
+        for i in 0..self.len() {
+            write!(f, "{}", if self.test(i) { 1 } else { 0 })?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the indices of the set bits of a DenseBitSetView
+#[derive(Clone, Eq, PartialEq)]
+pub struct DenseBitSetViewOnes<'a> {
+    num_bits: usize,
+    bytes: &'a [u8],
+    byte_idx: usize,
+    current: u8,
+}
+
+impl<'a> Iterator for DenseBitSetViewOnes<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            self.byte_idx += 1;
+            if self.byte_idx >= self.bytes.len() {
+                return None;
+            }
+            self.current = self.bytes[self.byte_idx];
+        }
+
+        let i = self.byte_idx * BITS_PER_BYTE + self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+
+        if i < self.num_bits {
+            Some(i)
+        } else {
+            self.current = 0;
+            None
+        }
+    }
+}
+
+// DenseBitSetView TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn can_view_bytes() {
+        let bytes = [0b1, 0, 0b1000_0001];
+        let view = DenseBitSetView::new(&bytes, 24).unwrap();
+
+        assert!(view.test(0));
+        assert!(!view.test(1));
+        assert!(view.test(23));
+        assert_eq!(view.count_ones(), 3);
+        assert_eq!(view.ones().collect::<Vec<_>>(), vec![0, 16, 23]);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!(DenseBitSetView::new(&[0, 0], 24).is_none());
+        assert!(DenseBitSetView::new(&[0, 0, 0], 17).is_some());
+    }
+
+    #[test]
+    fn ignores_padding_bits() {
+        let bytes = [0xff, 0xff];
+        let view = DenseBitSetView::new(&bytes, 12).unwrap();
+
+        assert_eq!(view.count_ones(), 12);
+        assert_eq!(view.ones().last(), Some(11));
+
+        let dense = view.to_dense();
+        assert_eq!((dense.len(), dense.count_ones()), (12, 12));
+    }
+
+    #[test]
+    fn can_convert_to_dense() {
+        let bs = DenseBitSet::from_indices(256, vec![1, 64, 255]);
+        let bytes = bs.canonical_bytes();
+        let view = DenseBitSetView::new(&bytes, bytes.len() * 8).unwrap();
+
+        assert_eq!(view.to_dense().to_indices(), bs.to_indices());
+    }
+}
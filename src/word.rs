@@ -0,0 +1,234 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Word-width abstraction shared by the byte formats
+//!
+//! Everything that crosses a process boundary is written as little-endian
+//! bytes, never as native words; the byte order itself is handled by the
+//! `endian` module. The conversions here are generic over the
+//! storage word so tests can run them with 32-bit words on a 64-bit host and
+//! check both layouts produce the same bytes.
+
+use std::fmt;
+use std::ops::{ BitAnd, BitOr, BitXor, Not };
+
+use endian::{ words_from_bytes, words_to_bytes, LittleEndian };
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer used as bit storage: `u8`, `u16`, `u32`, `u64`,
+/// `u128` or `usize`. This trait is sealed.
+pub trait Word: private::Sealed + Copy + Eq + fmt::Debug
+    + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    /// number of bits in the word
+    const BITS: usize;
+    /// number of bytes in the word
+    const BYTES: usize;
+
+    /// the word with no bits set
+    const ZERO: Self;
+
+    /// the lowest `n` bits set, all bits if `n >= BITS`
+    fn low_mask(n: usize) -> Self;
+
+    /// number of set bits
+    fn count_ones(self) -> u32;
+
+    /// number of clear bits below the lowest set bit, `BITS` for zero
+    fn trailing_zeros(self) -> u32;
+
+    #[doc(hidden)]
+    fn write_le(self, out: &mut Vec<u8>);
+
+    /// reads up to `BYTES` little-endian bytes, zero-filling the rest
+    #[doc(hidden)]
+    fn read_le(chunk: &[u8]) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl private::Sealed for $t {}
+
+        impl Word for $t {
+            const BITS: usize = <$t>::BITS as usize;
+            const BYTES: usize = ::std::mem::size_of::<$t>();
+            const ZERO: Self = 0;
+
+            #[inline]
+            fn low_mask(n: usize) -> Self {
+                if n >= <Self as Word>::BITS { !0 } else { (1 << n) - 1 }
+            }
+
+            #[inline]
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+
+            #[inline]
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+
+            #[inline]
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            #[inline]
+            fn read_le(chunk: &[u8]) -> Self {
+                let mut buf = [0u8; ::std::mem::size_of::<$t>()];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                <$t>::from_le_bytes(buf)
+            }
+        }
+    )*};
+}
+
+impl_word!(u8, u16, u32, u64, u128, usize);
+
+/// A `Word` of the same width on every platform: every word type but
+/// `usize`. `DenseBitSet<usize>` has its own vectorized versions of the
+/// operations that sets of these words implement generically.
+pub trait FixedWidthWord: Word {}
+
+impl FixedWidthWord for u8 {}
+impl FixedWidthWord for u16 {}
+impl FixedWidthWord for u32 {}
+impl FixedWidthWord for u64 {}
+impl FixedWidthWord for u128 {}
+
+/// Writes the first `num_bits` bits of `words` as little-endian bytes,
+/// one byte per 8 bits. Bits past `num_bits` are written as zero.
+pub(crate) fn words_to_le_bytes<W: Word>(words: &[W], num_bits: usize) -> Vec<u8> {
+    words_to_bytes::<LittleEndian, W>(words, num_bits)
+}
+
+/// Reads little-endian bytes into as many words as needed to hold them
+pub(crate) fn words_from_le_bytes<W: Word>(bytes: &[u8]) -> Vec<W> {
+    words_from_bytes::<LittleEndian, W>(bytes)
+}
+
+// Word TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use DenseBitSet;
+
+    /// deterministic test bytes
+    fn pattern(len: usize, seed: u64) -> Vec<u8> {
+        let mut x = seed | 1;
+        (0..len).map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        }).collect()
+    }
+
+    /// a bit set laid out in `W` words, standing in for `DenseBitSet` on a
+    /// platform with that word size
+    struct Model<W: Word> {
+        num_bits: usize,
+        words: Vec<W>,
+    }
+
+    impl<W: Word> Model<W> {
+        fn from_le_bytes(bytes: &[u8]) -> Model<W> {
+            Model { num_bits: bytes.len() * 8, words: words_from_le_bytes(bytes) }
+        }
+
+        fn zip(&self, other: &Model<W>, f: fn(W, W) -> W) -> Model<W> {
+            let words = self.words.iter().zip(&other.words).map(|(a, b)| f(*a, *b)).collect();
+            Model { num_bits: self.num_bits, words }
+        }
+
+        fn not(&self) -> Model<W> {
+            Model { num_bits: self.num_bits, words: self.words.iter().map(|w| !*w).collect() }
+        }
+
+        fn to_le_bytes(&self) -> Vec<u8> {
+            words_to_le_bytes(&self.words, self.num_bits)
+        }
+
+        fn ones(&self) -> Vec<usize> {
+            let bytes = self.to_le_bytes();
+            (0..self.num_bits).filter(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect()
+        }
+    }
+
+    #[test]
+    fn word_sizes_agree_on_bytes() {
+        for len in 0..40 {
+            let bytes = pattern(len, len as u64);
+
+            assert_eq!(Model::<u32>::from_le_bytes(&bytes).to_le_bytes(), bytes);
+            assert_eq!(Model::<u64>::from_le_bytes(&bytes).to_le_bytes(), bytes);
+            assert_eq!(DenseBitSet::from_le_bytes(&bytes).to_le_bytes()[..len], bytes[..]);
+        }
+    }
+
+    #[test]
+    fn narrow_words_match_dense_operations() {
+        // lengths are whole 64-bit words so the 32-bit layout has the same len()
+        for words in 0..6 {
+            let a_bytes = pattern(words * 8, 3 + words as u64);
+            let b_bytes = pattern(words * 8, 101 + words as u64);
+            let (a, b) = (DenseBitSet::from_le_bytes(&a_bytes), DenseBitSet::from_le_bytes(&b_bytes));
+            let (na, nb) = (Model::<u32>::from_le_bytes(&a_bytes), Model::<u32>::from_le_bytes(&b_bytes));
+
+            assert_eq!(a.len(), na.num_bits);
+            assert_eq!(a.count_ones(), na.ones().len());
+            assert_eq!(a.to_indices(), na.ones());
+            assert_eq!(a.and(&b).to_le_bytes(), na.zip(&nb, |x, y| x & y).to_le_bytes());
+            assert_eq!(a.or(&b).to_le_bytes(), na.zip(&nb, |x, y| x | y).to_le_bytes());
+            assert_eq!(a.xor(&b).to_le_bytes(), na.zip(&nb, |x, y| x ^ y).to_le_bytes());
+
+            let mut not_a = a.clone();
+            not_a.inplace_not();
+            assert_eq!(not_a.to_le_bytes(), na.not().to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn byte_format_is_pinned() {
+        let bs = DenseBitSet::from_indices(64, vec![0, 9, 63]);
+
+        assert_eq!(bs.to_le_bytes(), vec![0x01, 0x02, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(Model::<u32>::from_le_bytes(&bs.to_le_bytes()).ones(), vec![0, 9, 63]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_format_is_pinned() {
+        let bs = DenseBitSet::from_indices(64, vec![0, 9, 63]);
+        let narrow = Model::<u32>::from_le_bytes(&bs.to_le_bytes());
+
+        let mut expected = vec![64, 0, 0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(&narrow.to_le_bytes());
+
+        assert_eq!(::bincode::serialize(&bs).unwrap(), expected);
+    }
+}
@@ -0,0 +1,235 @@
+// MIT License
+
+// Copyright (c) 2018 Arthur Maciejewicz
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! `DenseBitSet` over fixed-width words: `u8`, `u16`, `u32`, `u64`, `u128`
+
+use word::words_from_le_bytes;
+use { BitSet, DenseBitSet, FixedWidthWord };
+
+/// Whatever the word, the byte form from `to_le_bytes()` is the same as a
+/// `DenseBitSet<usize>`'s, so sets convert between word types and to
+/// `DenseBitSet` losslessly.
+///
+/// # Examples
+///
+/// ```
+/// use bitsets::DenseBitSet;
+///
+/// let mut bs = DenseBitSet::<u16>::new(40);
+/// bs.set(3);
+/// bs.set(33);
+///
+/// let wide = DenseBitSet::<u128>::from(&bs.to_dense());
+/// assert_eq!(wide.ones().collect::<Vec<_>>(), vec![3, 33]);
+/// assert_eq!(wide.to_le_bytes(), bs.to_le_bytes());
+/// ```
+impl<W: FixedWidthWord> DenseBitSet<W> {
+    /// Returns the words holding the bits
+    pub fn as_words(&self) -> &[W] {
+        &self.bits
+    }
+
+    /// Returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the indices of the set bits, in ascending order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(w, &word)| {
+            let mut word = word;
+            ::std::iter::from_fn(move || {
+                if word == W::ZERO {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word = word & !W::low_mask(bit + 1);
+                Some(w * W::BITS + bit)
+            })
+        })
+    }
+
+    /// In-place bitwise-not
+    pub fn inplace_not(&mut self) {
+        for word in &mut self.bits {
+            *word = !*word;
+        }
+        self.clear_padding();
+    }
+
+    /// In-place bitwise-and with `other`
+    pub fn inplace_and(&mut self, other: &DenseBitSet<W>) {
+        self.zip_with(other, |a, b| a & b);
+    }
+
+    /// In-place bitwise-or with `other`
+    pub fn inplace_or(&mut self, other: &DenseBitSet<W>) {
+        self.zip_with(other, |a, b| a | b);
+    }
+
+    /// In-place bitwise-xor with `other`
+    pub fn inplace_xor(&mut self, other: &DenseBitSet<W>) {
+        self.zip_with(other, |a, b| a ^ b);
+    }
+
+    /// Returns the bitwise-and of `self` and `other`
+    pub fn and(&self, other: &DenseBitSet<W>) -> DenseBitSet<W> {
+        let mut output = self.clone();
+        output.inplace_and(other);
+        output
+    }
+
+    /// Returns the bitwise-or of `self` and `other`
+    pub fn or(&self, other: &DenseBitSet<W>) -> DenseBitSet<W> {
+        let mut output = self.clone();
+        output.inplace_or(other);
+        output
+    }
+
+    /// Returns the bitwise-xor of `self` and `other`
+    pub fn xor(&self, other: &DenseBitSet<W>) -> DenseBitSet<W> {
+        let mut output = self.clone();
+        output.inplace_xor(other);
+        output
+    }
+
+    /// Converts into a `DenseBitSet<usize>` of the same length
+    pub fn to_dense(&self) -> DenseBitSet {
+        let bytes = self.to_le_bytes();
+        DenseBitSet::from_vec_masked(words_from_le_bytes(&bytes), self.num_bits)
+    }
+
+    fn zip_with(&mut self, other: &DenseBitSet<W>, f: fn(W, W) -> W) {
+        assert!(self.num_bits == other.num_bits);
+        for (word, other) in self.bits.iter_mut().zip(&other.bits) {
+            *word = f(*word, *other);
+        }
+    }
+}
+
+impl<W: FixedWidthWord> PartialEq for DenseBitSet<W> {
+    fn eq(&self, other: &DenseBitSet<W>) -> bool {
+        self.num_bits == other.num_bits && self.bits == other.bits
+    }
+}
+
+impl<W: FixedWidthWord> Eq for DenseBitSet<W> {}
+
+impl<'a, W: FixedWidthWord> From<&'a DenseBitSet> for DenseBitSet<W> {
+    fn from(bits: &'a DenseBitSet) -> DenseBitSet<W> {
+        DenseBitSet { num_bits: bits.len(), bits: words_from_le_bytes(&bits.to_le_bytes()) }
+    }
+}
+
+impl<W: FixedWidthWord> BitSet for DenseBitSet<W> {
+    fn len(&self) -> usize {
+        DenseBitSet::len(self)
+    }
+
+    fn test(&self, i: usize) -> bool {
+        DenseBitSet::test(self, i)
+    }
+
+    fn set(&mut self, i: usize) -> bool {
+        DenseBitSet::set(self, i)
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        DenseBitSet::remove(self, i)
+    }
+
+    fn count_ones(&self) -> usize {
+        <DenseBitSet<W>>::count_ones(self)
+    }
+
+    fn next_set_bit(&self, from: usize) -> Option<usize> {
+        self.ones().find(|&i| i >= from)
+    }
+}
+
+// DenseBitSet<W: FixedWidthWord> TESTS
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Word;
+
+    fn check_word<W: FixedWidthWord>() {
+        let len = 300;
+        let a = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 3 == 0));
+        let b = DenseBitSet::from_indices(len, (0..len).filter(|i| i % 7 < 2));
+        let (wa, wb) = (DenseBitSet::<W>::from(&a), DenseBitSet::<W>::from(&b));
+
+        assert_eq!(wa.as_words().len(), a.len().div_ceil(W::BITS));
+        assert_eq!(wa.to_dense(), a);
+        assert_eq!(wa.to_le_bytes(), a.to_le_bytes());
+        assert_eq!(wa.ones().collect::<Vec<_>>(), a.to_indices());
+        assert_eq!(wa.and(&wb).to_dense(), a.and(&b));
+        assert_eq!(wa.or(&wb).to_dense(), a.or(&b));
+        assert_eq!(wa.xor(&wb).to_dense(), a.xor(&b));
+        assert_eq!(BitSet::to_indices(&wb), b.to_indices());
+
+        let mut not = wa.clone();
+        not.inplace_not();
+        assert_eq!(not.count_ones(), a.len() - a.count_ones());
+    }
+
+    #[test]
+    fn every_word_type_matches_dense() {
+        check_word::<u8>();
+        check_word::<u16>();
+        check_word::<u32>();
+        check_word::<u64>();
+        check_word::<u128>();
+    }
+
+    fn check_single_bit_edits<W: Word>() {
+        let mut bs = DenseBitSet::<W>::new(17);
+        assert!(bs.set(16));
+        bs.flip(0);
+        assert!(bs.remove(16));
+        assert!(bs.test(0) && !bs.test(16));
+        assert_eq!(bs.words(), 17usize.div_ceil(W::BITS));
+
+        let full = DenseBitSet::<W>::from_le_bytes_with_len(&[0xff, 0xff, 0xff], 17).unwrap();
+        assert_eq!(full.to_le_bytes(), vec![0xff, 0xff, 0x01]);
+        assert!(DenseBitSet::<W>::from_le_bytes_with_len(&[0xff], 17).is_none());
+    }
+
+    #[test]
+    fn single_bit_edits_for_every_word() {
+        check_single_bit_edits::<u8>();
+        check_single_bit_edits::<u16>();
+        check_single_bit_edits::<u32>();
+        check_single_bit_edits::<u64>();
+        check_single_bit_edits::<u128>();
+        check_single_bit_edits::<usize>();
+    }
+
+    #[test]
+    fn debug_summarizes_like_dense() {
+        let bs = DenseBitSet::<u8>::from(&DenseBitSet::from_bits(0b1010));
+
+        assert_eq!(format!("{:?}", bs), "DenseBitSet { len: 64, ones: 2, first: [1, 3] }");
+    }
+}